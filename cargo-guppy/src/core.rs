@@ -6,7 +6,10 @@
 use clap::{ArgEnum, Parser};
 use color_eyre::eyre::{ensure, eyre, Result, WrapErr};
 use guppy::{
-    graph::{DependencyDirection, DependencyReq, PackageGraph, PackageLink, PackageQuery},
+    graph::{
+        DependencyDirection, DependencyReq, PackageGraph, PackageLink, PackageMetadata,
+        PackageQuery,
+    },
     platform::EnabledTernary,
     PackageId,
 };
@@ -22,6 +25,21 @@ pub enum Kind {
 }
 
 impl Kind {
+    /// Returns true if this package should be shown in output.
+    pub fn should_show(self, package: &PackageMetadata<'_>) -> bool {
+        match self {
+            Kind::All => true,
+            Kind::Workspace => package.in_workspace(),
+            Kind::DirectThirdParty => {
+                !package.in_workspace()
+                    && package
+                        .reverse_direct_links()
+                        .any(|link| link.from().in_workspace())
+            }
+            Kind::ThirdParty => !package.in_workspace(),
+        }
+    }
+
     /// Returns true if this link should be traversed.
     pub fn should_traverse(self, link: &PackageLink<'_>) -> bool {
         // NOTE: We always retain all workspace deps in the graph, otherwise
@@ -102,7 +120,7 @@ pub struct FilterOptions {
     pub include_build: bool,
 
     #[clap(long)]
-    /// Target to filter, "current", "any" or "always" [default: any]
+    /// Target to filter, "current", "any", "always", or a comma-separated list [default: any]
     pub target: Option<String>,
 }
 
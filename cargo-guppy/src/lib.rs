@@ -24,7 +24,11 @@
 //!
 //! * `select`: query packages and their transitive dependencies
 //! * `resolve-cargo`: query packages and features as would be built by cargo
+//! * `feature-cost`: print the packages and features each feature of a package pulls in
+//! * `feature-surplus`: print features that workspace packages get for free or request redundantly
+//! * `feature-powerset`: print deduplicated feature combinations of a workspace package for testing
 //! * `subtree-size`: print dependencies along with their unique subtree size
+//! * `metrics`: print centrality metrics for packages, as a table, CSV or JSON
 //! * `dups`: print duplicate packages
 //!
 //! ## Diff commands
@@ -48,18 +52,22 @@ use clap::{ArgEnum, Parser};
 use color_eyre::eyre::{bail, Result, WrapErr};
 use guppy::{
     graph::{
-        cargo::{CargoOptions, CargoSet},
-        feature::{FeatureSet, StandardFeatures},
+        cargo::{BuildPlatform, CargoOptions, CargoSet, FeatureCosts, FeatureSurplus},
+        feature::{FeaturePowersetOptions, FeatureSet, StandardFeatures},
         summaries::Summary,
         DependencyDirection, DotWrite, PackageDotVisitor, PackageGraph, PackageLink,
         PackageMetadata,
     },
-    PackageId,
+    platform::{EnabledTernary, Platform, PlatformSpec, PlatformStatus},
+    DependencyKind, PackageId,
 };
 use guppy_cmdlib::{
     string_to_platform_spec, CargoMetadataOptions, CargoResolverOpts, PackagesAndFeatures,
 };
-use std::{borrow::Cow, cmp, collections::HashSet, fmt, fs, io::Write, iter, path::PathBuf};
+use serde::Serialize;
+use std::{
+    borrow::Cow, cmp, collections::HashSet, fmt, fs, io::Write, iter, path::PathBuf, sync::Arc,
+};
 
 pub fn cmd_diff(json: bool, old: &str, new: &str) -> Result<()> {
     let old_json = fs::read_to_string(old)?;
@@ -175,17 +183,21 @@ pub struct ResolveCargoOptions {
     base_filter_opts: BaseFilterOptions,
 
     #[clap(long = "target-platform")]
-    /// Evaluate against target platform, "current" or "any" (default: any)
+    /// Evaluate against target platform, "current", "any", or a comma-separated list (default: any)
     target_platform: Option<String>,
 
     #[clap(long = "host-platform")]
-    /// Evaluate against host platform, "current" or "any" (default: any)
+    /// Evaluate against host platform, "current", "any", or a comma-separated list (default: any)
     host_platform: Option<String>,
 
     #[clap(long, arg_enum, default_value = "all")]
     /// Print packages built on target, host or both
     build_kind: BuildKind,
 
+    #[clap(long)]
+    /// Explain dependencies whose platform status is unknown (printed to stderr)
+    explain_unknown: bool,
+
     #[clap(long, parse(from_os_str))]
     /// Write summary file
     summary: Option<PathBuf>,
@@ -205,8 +217,8 @@ pub fn cmd_resolve_cargo(opts: &ResolveCargoOptions) -> Result<()> {
         .set_include_dev(opts.resolver_opts.include_dev)
         .set_resolver(opts.resolver_opts.resolver_version.to_guppy())
         .set_initials_platform(opts.resolver_opts.initials_platform.to_guppy())
-        .set_target_platform(target_platform)
-        .set_host_platform(host_platform)
+        .set_target_platform(target_platform.clone())
+        .set_host_platform(host_platform.clone())
         .add_omitted_packages(opts.base_filter_opts.omitted_package_ids(&pkg_graph));
 
     let (initials, features_only) = opts.pf.make_feature_sets(&pkg_graph)?;
@@ -266,6 +278,15 @@ pub fn cmd_resolve_cargo(opts: &ResolveCargoOptions) -> Result<()> {
         BuildKind::Host => print_packages(cargo_set.host_features()),
     }
 
+    if opts.explain_unknown {
+        explain_unknown_links(
+            cargo_set.target_features(),
+            &target_platform,
+            &host_platform,
+        );
+        explain_unknown_links(cargo_set.host_features(), &host_platform, &host_platform);
+    }
+
     if let Some(summary_path) = &opts.summary {
         let summary = cargo_set.to_summary(&cargo_opts)?;
         let mut out = "# This summary file was @generated by cargo-guppy.\n\n".to_string();
@@ -277,6 +298,416 @@ pub fn cmd_resolve_cargo(opts: &ResolveCargoOptions) -> Result<()> {
     Ok(())
 }
 
+/// Prints, to stderr, an explanation for each link within `feature_set` whose status could not be
+/// determined.
+///
+/// Normal and dev dependencies are evaluated against `platform`, and build dependencies against
+/// `build_platform`.
+fn explain_unknown_links(
+    feature_set: &FeatureSet<'_>,
+    platform: &PlatformSpec,
+    build_platform: &PlatformSpec,
+) {
+    let platforms = spec_platforms(platform);
+    let build_platforms = spec_platforms(build_platform);
+
+    for link in feature_set
+        .to_package_set()
+        .links(DependencyDirection::Forward)
+    {
+        for (kind, platforms) in [
+            (DependencyKind::Normal, platforms),
+            (DependencyKind::Build, build_platforms),
+            (DependencyKind::Development, platforms),
+        ] {
+            let status = link.req_for_kind(kind).status();
+            for platform_status in [status.required_status(), status.optional_status()] {
+                let eval = match platform_status {
+                    PlatformStatus::PlatformDependent { eval } => eval,
+                    PlatformStatus::Always | PlatformStatus::Never => continue,
+                };
+                for platform in platforms {
+                    if eval.eval(platform) != EnabledTernary::Unknown {
+                        continue;
+                    }
+                    eprintln!(
+                        "{} -> {} ({} dependency) is unknown on {}:",
+                        link.from().name(),
+                        link.to().name(),
+                        kind.to_str(),
+                        platform.triple_str(),
+                    );
+                    for trace in eval.eval_explain(platform) {
+                        for line in trace.to_string().lines() {
+                            eprintln!("  {}", line);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the individual platforms in a spec. Evaluating against any other spec never produces an
+/// unknown result.
+fn spec_platforms(spec: &PlatformSpec) -> &[Arc<Platform>] {
+    match spec {
+        PlatformSpec::Platform(platform) => std::slice::from_ref(platform),
+        PlatformSpec::Platforms(platforms) => platforms,
+        _ => &[],
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct FeatureCostOptions {
+    #[clap(rename_all = "screaming_snake_case")]
+    /// The package whose features are measured
+    package: String,
+
+    #[clap(flatten)]
+    pf: PackagesAndFeatures,
+
+    #[clap(flatten)]
+    resolver_opts: CargoResolverOpts,
+
+    #[clap(long = "target-platform", multiple_occurrences(true))]
+    /// Evaluate against target platform, "current", "any", or a comma-separated list; repeat to
+    /// print costs for each target platform separately (default: any)
+    target_platforms: Vec<String>,
+
+    #[clap(long = "host-platform")]
+    /// Evaluate against host platform, "current", "any", or a comma-separated list (default: any)
+    host_platform: Option<String>,
+
+    #[clap(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_feature_cost(opts: &FeatureCostOptions) -> Result<()> {
+    let host_platform = string_to_platform_spec(opts.host_platform.as_deref())?;
+    let command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+
+    let mut cargo_opts = CargoOptions::new();
+    cargo_opts
+        .set_include_dev(opts.resolver_opts.include_dev)
+        .set_resolver(opts.resolver_opts.resolver_version.to_guppy())
+        .set_initials_platform(opts.resolver_opts.initials_platform.to_guppy())
+        .set_host_platform(host_platform);
+
+    let mut candidates = pkg_graph
+        .resolve_package_name(&opts.package)
+        .package_ids(DependencyDirection::Forward)
+        .collect::<Vec<_>>();
+    let package_id = match (candidates.pop(), candidates.is_empty()) {
+        (Some(package_id), true) => package_id,
+        (Some(_), false) => bail!("multiple packages named '{}' found", opts.package),
+        (None, _) => bail!("no package named '{}' found", opts.package),
+    };
+
+    let (initials, features_only) = opts.pf.make_feature_sets(&pkg_graph)?;
+    if opts.target_platforms.len() <= 1 {
+        let target_platform =
+            string_to_platform_spec(opts.target_platforms.first().map(|s| s.as_str()))?;
+        cargo_opts.set_target_platform(target_platform);
+        let costs = FeatureCosts::new(initials, features_only, package_id, &cargo_opts)?;
+        print_feature_costs(&costs);
+    } else {
+        let target_platforms = opts
+            .target_platforms
+            .iter()
+            .map(|s| string_to_platform_spec(Some(s)))
+            .collect::<Result<Vec<_>>>()?;
+        let per_platform = FeatureCosts::new_per_platform(
+            initials,
+            features_only,
+            package_id,
+            &cargo_opts,
+            target_platforms,
+        )?;
+        for (name, costs) in opts.target_platforms.iter().zip(&per_platform) {
+            println!("target platform: {}", name);
+            print_feature_costs(costs);
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+fn print_feature_costs(costs: &FeatureCosts<'_>) {
+    println!(
+        "{:<32} {:>8} {:>8} {:>8} {:>8}",
+        "feature", "target", "host", "t-feats", "h-feats"
+    );
+    for cost in costs.sorted_by_cost() {
+        println!(
+            "{:<32} {:>8} {:>8} {:>8} {:>8}",
+            cost.feature_id().label().to_string(),
+            cost.added_packages(BuildPlatform::Target).len(),
+            cost.added_packages(BuildPlatform::Host).len(),
+            cost.added_features(BuildPlatform::Target).len(),
+            cost.added_features(BuildPlatform::Host).len(),
+        );
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct FeatureSurplusOptions {
+    #[clap(flatten)]
+    pf: PackagesAndFeatures,
+
+    #[clap(flatten)]
+    resolver_opts: CargoResolverOpts,
+
+    #[clap(long = "target-platform")]
+    /// Evaluate against target platform, "current", "any", or a comma-separated list (default: any)
+    target_platform: Option<String>,
+
+    #[clap(long = "host-platform")]
+    /// Evaluate against host platform, "current", "any", or a comma-separated list (default: any)
+    host_platform: Option<String>,
+
+    #[clap(long)]
+    /// Also print dependencies that get exactly the features they request
+    all: bool,
+
+    #[clap(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_feature_surplus(opts: &FeatureSurplusOptions) -> Result<()> {
+    let target_platform = string_to_platform_spec(opts.target_platform.as_deref())?;
+    let host_platform = string_to_platform_spec(opts.host_platform.as_deref())?;
+    let command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+
+    let mut cargo_opts = CargoOptions::new();
+    cargo_opts
+        .set_include_dev(opts.resolver_opts.include_dev)
+        .set_resolver(opts.resolver_opts.resolver_version.to_guppy())
+        .set_initials_platform(opts.resolver_opts.initials_platform.to_guppy())
+        .set_target_platform(target_platform)
+        .set_host_platform(host_platform);
+
+    let (initials, features_only) = opts.pf.make_feature_sets(&pkg_graph)?;
+    let cargo_set = CargoSet::new(initials, features_only, &cargo_opts)?;
+    let surplus = FeatureSurplus::new(&cargo_set);
+
+    for entry in surplus.iter().filter(|entry| opts.all || !entry.is_exact()) {
+        let (from, to) = entry.link().endpoints();
+        println!("{} -> {} {}", from.name(), to.name(), to.version());
+        println!("  requested: {}", entry.requested().join(", "));
+        println!("  built:     {}", entry.built().join(", "));
+        if !entry.free().is_empty() {
+            println!("  free:      {}", entry.free().join(", "));
+        }
+        for (feature, reason) in entry.redundant() {
+            println!("  redundant: {} ({:?})", feature, reason);
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct PowersetOptions {
+    #[clap(rename_all = "screaming_snake_case")]
+    /// The workspace package whose feature combinations are generated
+    package: String,
+
+    #[clap(long)]
+    /// Maximum number of features per combination, not counting always-included features
+    /// (default: 2)
+    depth: Option<usize>,
+
+    #[clap(long, conflicts_with = "depth")]
+    /// Generate combinations of any number of features (may take exponential time)
+    unlimited_depth: bool,
+
+    #[clap(long, multiple_occurrences(true), use_delimiter(true))]
+    /// Features to never enable
+    exclude: Vec<String>,
+
+    #[clap(long, multiple_occurrences(true))]
+    /// Comma-separated groups of features, at most one of which is enabled at a time
+    mutually_exclusive: Vec<String>,
+
+    #[clap(long, multiple_occurrences(true), use_delimiter(true))]
+    /// Features to enable in every combination
+    always_include: Vec<String>,
+
+    #[clap(long)]
+    /// Output combinations as JSON
+    json: bool,
+
+    #[clap(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_feature_powerset(opts: &PowersetOptions) -> Result<()> {
+    let command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+    let package = pkg_graph.workspace().member_by_name(&opts.package)?;
+
+    // Options on the command line are added to options in the package's metadata.
+    let mut powerset_opts = FeaturePowersetOptions::from_package_metadata(&package)?;
+    if opts.unlimited_depth {
+        powerset_opts.set_depth(None);
+    } else if opts.depth.is_some() {
+        powerset_opts.set_depth(opts.depth);
+    }
+    powerset_opts
+        .add_exclude(&opts.exclude)
+        .add_always_include(&opts.always_include);
+    for group in &opts.mutually_exclusive {
+        powerset_opts.add_mutually_exclusive(group.split(','));
+    }
+
+    let powerset = pkg_graph
+        .feature_graph()
+        .feature_powerset(package.id(), &powerset_opts)?;
+    if opts.json {
+        let combinations: Vec<_> = powerset
+            .iter()
+            .map(|combination| combination.features())
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&combinations)?);
+    } else {
+        // Each line can be passed in to `cargo build --no-default-features --features`.
+        for combination in powerset.iter() {
+            println!("{}", combination.features().join(","));
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(ArgEnum, Copy, Clone, Debug)]
+pub enum MetricsFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Parser)]
+pub struct MetricsOptions {
+    #[clap(flatten)]
+    filter_opts: FilterOptions,
+
+    #[clap(long, arg_enum, default_value = "table")]
+    /// Output format
+    format: MetricsFormat,
+
+    #[clap(flatten)]
+    query_opts: QueryOptions,
+
+    #[clap(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct MetricsRow<'g> {
+    name: &'g str,
+    version: String,
+    id: &'g str,
+    workspace: bool,
+    workspace_dependents: usize,
+    betweenness: u64,
+    depth: Option<usize>,
+    fan_in: usize,
+}
+
+pub fn cmd_metrics(options: &MetricsOptions) -> Result<()> {
+    let command = options.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+
+    let query = options.query_opts.apply(&pkg_graph)?;
+    let resolver = options.filter_opts.make_resolver(&pkg_graph)?;
+    let metrics = query.resolve_with_fn(resolver).metrics();
+
+    let mut rows: Vec<_> = metrics
+        .iter()
+        .filter(|metric| {
+            options
+                .filter_opts
+                .base_opts
+                .kind
+                .should_show(&metric.package())
+        })
+        .map(|metric| {
+            let package = metric.package();
+            MetricsRow {
+                name: package.name(),
+                version: package.version().to_string(),
+                id: package.id().repr(),
+                workspace: package.in_workspace(),
+                workspace_dependents: metric.workspace_dependents(),
+                betweenness: metric.betweenness(),
+                depth: metric.depth(),
+                fan_in: metric.fan_in(),
+            }
+        })
+        .collect();
+    // Most central packages first.
+    rows.sort_by(|a, b| {
+        b.workspace_dependents
+            .cmp(&a.workspace_dependents)
+            .then_with(|| b.betweenness.cmp(&a.betweenness))
+            .then_with(|| a.id.cmp(b.id))
+    });
+
+    match options.format {
+        MetricsFormat::Table => {
+            println!(
+                "{:<32} {:<12} {:>10} {:>12} {:>6} {:>6}",
+                "name", "version", "dependents", "betweenness", "depth", "fan-in"
+            );
+            for row in &rows {
+                let depth = row.depth.map_or_else(|| "-".to_owned(), |d| d.to_string());
+                println!(
+                    "{:<32} {:<12} {:>10} {:>12} {:>6} {:>6}",
+                    row.name,
+                    row.version,
+                    row.workspace_dependents,
+                    row.betweenness,
+                    depth,
+                    row.fan_in
+                );
+            }
+        }
+        MetricsFormat::Csv => {
+            println!("name,version,id,workspace,workspace_dependents,betweenness,depth,fan_in");
+            for row in &rows {
+                println!(
+                    "{},{},{},{},{},{},{},{}",
+                    csv_escape(row.name),
+                    csv_escape(&row.version),
+                    csv_escape(row.id),
+                    row.workspace,
+                    row.workspace_dependents,
+                    row.betweenness,
+                    row.depth.map_or_else(String::new, |d| d.to_string()),
+                    row.fan_in
+                );
+            }
+        }
+        MetricsFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn csv_escape(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
 struct NameVisitor;
 
 impl PackageDotVisitor for NameVisitor {
@@ -317,20 +748,9 @@ pub fn cmd_select(options: &CmdSelectOptions) -> Result<()> {
     let resolver = options.filter_opts.make_resolver(&pkg_graph)?;
     let package_set = query.resolve_with_fn(resolver);
 
-    for package_id in package_set.package_ids(options.output_direction) {
-        let package = pkg_graph.metadata(package_id).unwrap();
-        let in_workspace = package.in_workspace();
-        let direct_dep = package
-            .reverse_direct_links()
-            .any(|link| link.from().in_workspace() && !link.to().in_workspace());
-        let show_package = match options.filter_opts.base_opts.kind {
-            Kind::All => true,
-            Kind::Workspace => in_workspace,
-            Kind::DirectThirdParty => direct_dep,
-            Kind::ThirdParty => !in_workspace,
-        };
-        if show_package {
-            println!("{}", package_id);
+    for package in package_set.packages(options.output_direction) {
+        if options.filter_opts.base_opts.kind.should_show(&package) {
+            println!("{}", package.id());
         }
     }
 
@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use cargo_guppy::{
-    CmdSelectOptions, DiffSummariesOptions, DupsOptions, MvOptions, ResolveCargoOptions,
-    SubtreeSizeOptions,
+    CmdSelectOptions, DiffSummariesOptions, DupsOptions, FeatureCostOptions, FeatureSurplusOptions,
+    MetricsOptions, MvOptions, PowersetOptions, ResolveCargoOptions, SubtreeSizeOptions,
 };
 use clap::Parser;
 use color_eyre::Result;
@@ -35,12 +35,24 @@ enum Command {
     #[structopt(name = "resolve-cargo")]
     /// Return packages and features that would be built by Cargo
     ResolveCargo(ResolveCargoOptions),
+    #[structopt(name = "feature-cost")]
+    /// Print the packages and features pulled in by each feature of a package
+    FeatureCost(FeatureCostOptions),
+    #[structopt(name = "feature-surplus")]
+    /// Print features that workspace packages get for free or request redundantly
+    FeatureSurplus(FeatureSurplusOptions),
+    #[structopt(name = "feature-powerset")]
+    /// Print deduplicated feature combinations of a workspace package for testing
+    FeaturePowerset(PowersetOptions),
     #[structopt(name = "select")]
     /// Select packages and their transitive dependencies
     Select(CmdSelectOptions),
     #[structopt(name = "subtree-size")]
     /// Print a list of dependencies along with their unique subtree size
     SubtreeSize(SubtreeSizeOptions),
+    #[structopt(name = "metrics")]
+    /// Print centrality metrics for packages, as a table, CSV or JSON
+    Metrics(MetricsOptions),
     #[structopt(name = "mv")]
     /// Move packages to another location, fixing up workspace paths
     ///
@@ -85,8 +97,12 @@ fn main() -> Result<()> {
         Command::DiffSummaries(options) => options.exec(),
         Command::Duplicates(ref options) => cargo_guppy::cmd_dups(options),
         Command::ResolveCargo(ref options) => cargo_guppy::cmd_resolve_cargo(options),
+        Command::FeatureCost(ref options) => cargo_guppy::cmd_feature_cost(options),
+        Command::FeatureSurplus(ref options) => cargo_guppy::cmd_feature_surplus(options),
+        Command::FeaturePowerset(ref options) => cargo_guppy::cmd_feature_powerset(options),
         Command::Select(ref options) => cargo_guppy::cmd_select(options),
         Command::SubtreeSize(ref options) => cargo_guppy::cmd_subtree_size(options),
+        Command::Metrics(ref options) => cargo_guppy::cmd_metrics(options),
         Command::Mv(ref options) => options.exec(),
     }
 }
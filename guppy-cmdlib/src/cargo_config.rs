@@ -0,0 +1,423 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Support for reading Cargo configuration files.
+
+use color_eyre::eyre::{bail, Result, WrapErr};
+use guppy::{
+    graph::cargo::CargoOptions,
+    platform::{Platform, TargetFeatures},
+};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+use target_spec::TargetSpec;
+
+/// Target and host platforms derived from Cargo configuration.
+///
+/// This reads the same sources Cargo does:
+///
+/// * `build.target` in `.cargo/config.toml` files, overridden by the `CARGO_BUILD_TARGET`
+///   environment variable.
+/// * Rust flags from, in order of precedence: the `CARGO_ENCODED_RUSTFLAGS` and `RUSTFLAGS`
+///   environment variables, matching `target.<triple>.rustflags` and `target.<cfg>.rustflags`
+///   sections, and `build.rustflags` (overridden by `CARGO_BUILD_RUSTFLAGS`). As with Cargo, only
+///   the first source present is used.
+///
+/// `--cfg` options in Rust flags are added to platforms as flags or key-value cfgs.
+///
+/// As with Cargo, if a target is specified, Rust flags are only applied to the target platform,
+/// not to the host platform.
+#[derive(Clone, Debug)]
+pub struct CargoConfigPlatforms {
+    target: Platform,
+    host: Platform,
+}
+
+impl CargoConfigPlatforms {
+    /// Loads Cargo configuration for the given directory, and the current environment.
+    ///
+    /// Configuration files are looked up in `.cargo` directories within `dir` and all its
+    /// ancestors, and in `$CARGO_HOME`. Files in deeper directories take precedence.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let config = CargoConfig::load(dir.as_ref())?;
+        Self::from_config(&config, |var| env::var(var).ok())
+    }
+
+    /// Returns the target platform.
+    pub fn target(&self) -> &Platform {
+        &self.target
+    }
+
+    /// Returns the host platform.
+    pub fn host(&self) -> &Platform {
+        &self.host
+    }
+
+    /// Sets the target and host platforms on the given `CargoOptions`.
+    pub fn apply(&self, options: &mut CargoOptions<'_>) {
+        options
+            .set_target_platform(self.target.clone())
+            .set_host_platform(self.host.clone());
+    }
+
+    fn from_config(config: &CargoConfig, env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let mut host = Platform::current()?;
+        let target_triple = match env("CARGO_BUILD_TARGET") {
+            Some(target) => Some(target),
+            None => config.build_target.clone(),
+        };
+
+        let Some(target_triple) = target_triple else {
+            // Without an explicit target, Rust flags apply to the host, which is also the
+            // target.
+            let rustflags = config.rustflags(&host, &env)?;
+            apply_rustflags(&mut host, &rustflags)?;
+            return Ok(Self {
+                target: host.clone(),
+                host,
+            });
+        };
+
+        if target_triple.ends_with(".json") {
+            bail!(
+                "target `{target_triple}` is a custom target specification, which is not supported"
+            );
+        }
+        let mut target = Platform::new(target_triple.clone(), TargetFeatures::Unknown)
+            .wrap_err_with(|| format!("unknown target `{target_triple}`"))?;
+        let rustflags = config.rustflags(&target, &env)?;
+        apply_rustflags(&mut target, &rustflags)?;
+        Ok(Self { target, host })
+    }
+}
+
+/// Cargo configuration, merged across files.
+#[derive(Clone, Debug, Default)]
+struct CargoConfig {
+    build_target: Option<String>,
+    build_rustflags: Option<Vec<String>>,
+    target_rustflags: BTreeMap<String, Vec<String>>,
+}
+
+impl CargoConfig {
+    fn load(dir: &Path) -> Result<Self> {
+        let mut paths: Vec<PathBuf> = dir
+            .ancestors()
+            .filter_map(|ancestor| config_file(&ancestor.join(".cargo")))
+            .collect();
+        if let Some(cargo_home) = cargo_home() {
+            if let Some(path) = config_file(&cargo_home) {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+
+        // Merge files from lowest to highest precedence.
+        let mut config = CargoConfig::default();
+        for path in paths.iter().rev() {
+            let contents = fs::read_to_string(path)
+                .wrap_err_with(|| format!("reading Cargo config {} failed", path.display()))?;
+            let file: ConfigFile = toml::from_str(&contents)
+                .wrap_err_with(|| format!("parsing Cargo config {} failed", path.display()))?;
+            config.merge(file)?;
+        }
+        Ok(config)
+    }
+
+    fn merge(&mut self, file: ConfigFile) -> Result<()> {
+        if let Some(target) = file.build.target {
+            self.build_target = Some(match target {
+                StringOrList::String(target) => target,
+                StringOrList::List(mut targets) if targets.len() == 1 => targets.remove(0),
+                StringOrList::List(targets) => {
+                    bail!(
+                        "multiple build targets ({}) are not supported",
+                        targets.join(", ")
+                    )
+                }
+            });
+        }
+        // As with Cargo, lists are joined, with higher-precedence items placed later.
+        if let Some(rustflags) = file.build.rustflags {
+            self.build_rustflags
+                .get_or_insert_with(Vec::new)
+                .extend(rustflags.into_flags());
+        }
+        for (key, target) in file.target {
+            if let Some(rustflags) = target.rustflags {
+                self.target_rustflags
+                    .entry(key)
+                    .or_default()
+                    .extend(rustflags.into_flags());
+            }
+        }
+        Ok(())
+    }
+
+    fn rustflags(
+        &self,
+        platform: &Platform,
+        env: &impl Fn(&str) -> Option<String>,
+    ) -> Result<Vec<String>> {
+        if let Some(encoded) = env("CARGO_ENCODED_RUSTFLAGS") {
+            return Ok(encoded
+                .split('\x1f')
+                .filter(|flag| !flag.is_empty())
+                .map(|flag| flag.to_owned())
+                .collect());
+        }
+        if let Some(rustflags) = env("RUSTFLAGS") {
+            return Ok(split_flags(&rustflags));
+        }
+
+        let mut target_rustflags = vec![];
+        let mut any_matched = false;
+        for (key, rustflags) in &self.target_rustflags {
+            let matches = if key.starts_with("cfg(") {
+                let spec = TargetSpec::new(key.clone())
+                    .wrap_err_with(|| format!("parsing Cargo config key `target.{key}` failed"))?;
+                spec.eval(platform) == Some(true)
+            } else {
+                key == platform.triple_str()
+            };
+            if matches {
+                any_matched = true;
+                target_rustflags.extend(rustflags.iter().cloned());
+            }
+        }
+        if any_matched {
+            return Ok(target_rustflags);
+        }
+
+        if let Some(rustflags) = env("CARGO_BUILD_RUSTFLAGS") {
+            return Ok(split_flags(&rustflags));
+        }
+        Ok(self.build_rustflags.clone().unwrap_or_default())
+    }
+}
+
+/// The parts of a Cargo configuration file relevant to platforms.
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    build: BuildConfig,
+    #[serde(default)]
+    target: BTreeMap<String, TargetConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BuildConfig {
+    target: Option<StringOrList>,
+    rustflags: Option<StringOrList>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TargetConfig {
+    rustflags: Option<StringOrList>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+impl StringOrList {
+    fn into_flags(self) -> Vec<String> {
+        match self {
+            StringOrList::String(flags) => split_flags(&flags),
+            StringOrList::List(flags) => flags,
+        }
+    }
+}
+
+fn config_file(cargo_dir: &Path) -> Option<PathBuf> {
+    // Cargo prefers the legacy config file if both exist.
+    ["config", "config.toml"]
+        .iter()
+        .map(|name| cargo_dir.join(name))
+        .find(|path| path.is_file())
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    if let Some(cargo_home) = env::var_os("CARGO_HOME") {
+        return Some(cargo_home.into());
+    }
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| Path::new(&home).join(".cargo"))
+}
+
+fn split_flags(flags: &str) -> Vec<String> {
+    flags
+        .split_whitespace()
+        .map(|flag| flag.to_owned())
+        .collect()
+}
+
+/// Adds `--cfg` options in `rustflags` to the platform.
+fn apply_rustflags(platform: &mut Platform, rustflags: &[String]) -> Result<()> {
+    let mut iter = rustflags.iter();
+    while let Some(flag) = iter.next() {
+        let cfg = if flag == "--cfg" {
+            match iter.next() {
+                Some(cfg) => cfg.as_str(),
+                None => bail!("`--cfg` in Rust flags is missing a value"),
+            }
+        } else if let Some(cfg) = flag.strip_prefix("--cfg=") {
+            cfg
+        } else {
+            continue;
+        };
+
+        match cfg.split_once('=') {
+            Some((key, value)) => {
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .unwrap_or(value);
+                platform.add_key_values([(key.trim().to_owned(), value.to_owned())]);
+            }
+            None => platform.add_flags([cfg.to_owned()]),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(files: &[&str]) -> CargoConfig {
+        let mut config = CargoConfig::default();
+        for file in files {
+            config
+                .merge(toml::from_str(file).expect("valid config"))
+                .expect("merge succeeded");
+        }
+        config
+    }
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn target_rustflags() {
+        let config = config(&[
+            r#"
+            [build]
+            rustflags = ["--cfg", "ignored"]
+            "#,
+            r#"
+            [build]
+            target = "aarch64-unknown-linux-gnu"
+
+            [target.aarch64-unknown-linux-gnu]
+            rustflags = ["--cfg", "tokio_unstable", "-C", "opt-level=3"]
+
+            [target.'cfg(target_arch = "aarch64")']
+            rustflags = "--cfg=tokio_backend=\"io-uring\""
+
+            [target.'cfg(windows)']
+            rustflags = ["--cfg", "windows_only"]
+            "#,
+        ]);
+        let platforms = CargoConfigPlatforms::from_config(&config, no_env).unwrap();
+
+        let target = platforms.target();
+        assert_eq!(target.triple_str(), "aarch64-unknown-linux-gnu");
+        assert_eq!(target.flags().collect::<Vec<_>>(), ["tokio_unstable"]);
+        assert_eq!(
+            target.key_values().collect::<Vec<_>>(),
+            [("tokio_backend", "io-uring")]
+        );
+
+        // Rust flags don't apply to the host if a target is specified.
+        assert_eq!(platforms.host().flags().len(), 0);
+    }
+
+    #[test]
+    fn build_rustflags() {
+        let config = config(&[
+            r#"
+            [build]
+            rustflags = ["--cfg", "lower"]
+            "#,
+            r#"
+            [build]
+            rustflags = "--cfg higher"
+
+            [target.'cfg(target_os = "none")']
+            rustflags = ["--cfg", "unmatched"]
+            "#,
+        ]);
+        let platforms = CargoConfigPlatforms::from_config(&config, no_env).unwrap();
+
+        // Without a target, Rust flags apply to both the target and the host.
+        for platform in [platforms.target(), platforms.host()] {
+            assert_eq!(platform.flags().collect::<Vec<_>>(), ["higher", "lower"]);
+        }
+    }
+
+    #[test]
+    fn env_overrides() {
+        let config = config(&[r#"
+            [build]
+            target = "aarch64-unknown-linux-gnu"
+            rustflags = ["--cfg", "from_config"]
+            "#]);
+        let env = |var: &str| match var {
+            "CARGO_BUILD_TARGET" => Some("x86_64-pc-windows-msvc".to_owned()),
+            "RUSTFLAGS" => Some("--cfg from_env".to_owned()),
+            _ => None,
+        };
+        let platforms = CargoConfigPlatforms::from_config(&config, env).unwrap();
+        assert_eq!(platforms.target().triple_str(), "x86_64-pc-windows-msvc");
+        assert_eq!(platforms.target().flags().collect::<Vec<_>>(), ["from_env"]);
+
+        let env = |var: &str| match var {
+            "CARGO_ENCODED_RUSTFLAGS" => Some("--cfg\x1ffoo=\"bar baz\"".to_owned()),
+            "RUSTFLAGS" => Some("--cfg ignored".to_owned()),
+            _ => None,
+        };
+        let platforms = CargoConfigPlatforms::from_config(&config, env).unwrap();
+        assert_eq!(platforms.target().flags().len(), 0);
+        assert_eq!(
+            platforms.target().key_values().collect::<Vec<_>>(),
+            [("foo", "bar baz")]
+        );
+    }
+
+    #[test]
+    fn invalid_configs() {
+        let mut config = CargoConfig::default();
+        let file = toml::from_str(
+            r#"
+            [build]
+            target = ["x86_64-unknown-linux-gnu", "aarch64-unknown-linux-gnu"]
+            "#,
+        )
+        .unwrap();
+        assert!(
+            config.merge(file).is_err(),
+            "multiple targets are unsupported"
+        );
+
+        let config = self::config(&[r#"
+            [build]
+            target = "my-target.json"
+            "#]);
+        assert!(
+            CargoConfigPlatforms::from_config(&config, no_env).is_err(),
+            "custom targets are unsupported"
+        );
+    }
+}
@@ -5,9 +5,12 @@
 //!
 //! This library allows translating command-line arguments into guppy's data structures.
 
+mod cargo_config;
 #[cfg(feature = "proptest1")]
 pub mod proptest;
 
+pub use cargo_config::CargoConfigPlatforms;
+
 use clap::{ArgEnum, Parser};
 use color_eyre::eyre::Result;
 use guppy::{
@@ -175,7 +178,11 @@ impl CargoMetadataOptions {
     }
 }
 
-/// Parse a given triple, the string "current", or "any", into a platform.
+/// Parse a given triple, the string "current", "always" or "any", or a comma-separated list of
+/// triples, into a platform.
+///
+/// A comma-separated list is treated as the union of the listed platforms. "current" may be
+/// used as an element of the list.
 ///
 /// TODO: This should eventually support JSON specs as well, probably.
 pub fn string_to_platform_spec(s: Option<&str>) -> Result<PlatformSpec> {
@@ -183,7 +190,21 @@ pub fn string_to_platform_spec(s: Option<&str>) -> Result<PlatformSpec> {
         Some("current") => Ok(PlatformSpec::current()?),
         Some("always") => Ok(PlatformSpec::Always),
         Some("any") => Ok(PlatformSpec::Any),
-        Some(triple) => Ok(Platform::new(triple.to_owned(), TargetFeatures::Unknown)?.into()),
+        Some(list) if list.contains(',') => {
+            let platforms = list
+                .split(',')
+                .map(|triple| string_to_platform(triple.trim()))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(PlatformSpec::platforms(platforms))
+        }
+        Some(triple) => Ok(string_to_platform(triple)?.into()),
         None => Ok(PlatformSpec::Any),
     }
 }
+
+fn string_to_platform(s: &str) -> Result<Platform> {
+    match s {
+        "current" => Ok(Platform::current()?),
+        triple => Ok(Platform::new(triple.to_owned(), TargetFeatures::Unknown)?),
+    }
+}
@@ -0,0 +1,26 @@
+# {{crate}}
+
+[![guppy-miette on crates.io](https://img.shields.io/crates/v/guppy-miette)](https://crates.io/crates/guppy-miette)
+[![Documentation (latest release)](https://img.shields.io/badge/docs-latest-brightgreen.svg)](https://docs.rs/guppy-miette/)
+[![Documentation (main)](https://img.shields.io/badge/docs-main-purple)](https://guppy-rs.github.io/guppy/rustdoc/guppy_miette/)
+[![Changelog](https://img.shields.io/badge/changelog-latest-blue)](CHANGELOG.md)
+[![License](https://img.shields.io/badge/license-Apache-green.svg)](../LICENSE-APACHE)
+[![License](https://img.shields.io/badge/license-MIT-green.svg)](../LICENSE-MIT)
+
+{{readme}}
+
+## Contributing
+
+See the [CONTRIBUTING](../CONTRIBUTING.md) file for how to help out.
+
+## License
+
+This project is available under the terms of either the [Apache 2.0 license](../LICENSE-APACHE) or the [MIT
+license](../LICENSE-MIT).
+
+<!--
+README.md is generated from README.tpl by cargo readme. To regenerate:
+
+cargo install cargo-readme
+./scripts/regenerate-readmes.sh
+-->
@@ -0,0 +1,226 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{find_quoted, GuppyDiagnostic};
+use determinator::{
+    errors::{RulesError, RulesErrorKind},
+    rules::RuleIndex,
+};
+use guppy::Error;
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
+use std::{error::Error as StdError, fmt, ops::Range};
+
+/// A diagnostic for an error that occurred while resolving determinator rules.
+///
+/// This diagnostic points at the rule that failed to resolve within the rules file and, where
+/// possible, the glob or package name within it.
+pub struct RulesDiagnostic {
+    error: RulesError,
+    path: String,
+    source: NamedSource<String>,
+    rule_span: Option<(usize, usize)>,
+    span: Option<(usize, usize)>,
+    guppy_error: Option<GuppyDiagnostic>,
+}
+
+impl RulesDiagnostic {
+    /// Creates a new `RulesDiagnostic` from the path to the rules file, its contents, and the
+    /// error returned while resolving the rules.
+    pub fn new(path: impl Into<String>, contents: impl Into<String>, error: RulesError) -> Self {
+        let path = path.into();
+        let contents = contents.into();
+
+        let rule_range = rule_range(&contents, error.rule_index());
+        let needle = match error.kind() {
+            RulesErrorKind::ResolveRef(Error::UnknownWorkspaceName(name)) => Some(name.as_str()),
+            RulesErrorKind::ResolveRef(Error::UnknownWorkspacePath(path)) => Some(path.as_str()),
+            RulesErrorKind::GlobParse {
+                glob: Some(glob), ..
+            } => Some(glob.as_str()),
+            _ => None,
+        };
+        let span = rule_range
+            .clone()
+            .and_then(|range| needle.and_then(|needle| find_quoted(&contents, range, needle)));
+        // Only point at the rule as a whole if nothing more specific was found.
+        let rule_span = match span {
+            Some(_) => None,
+            None => rule_range.map(|range| {
+                let header_len = contents[range.clone()].find('\n').unwrap_or(range.len());
+                (range.start, header_len)
+            }),
+        };
+
+        // guppy::Error isn't Clone, so copy over the variants that rule resolution can produce.
+        let guppy_error = match error.kind() {
+            RulesErrorKind::ResolveRef(err) => copy_guppy_error(err).map(GuppyDiagnostic::new),
+            _ => None,
+        };
+
+        Self {
+            error,
+            source: NamedSource::new(&path, contents),
+            path,
+            rule_span,
+            span,
+            guppy_error,
+        }
+    }
+
+    /// Returns the underlying error.
+    pub fn error(&self) -> &RulesError {
+        &self.error
+    }
+}
+
+impl fmt::Debug for RulesDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.error, f)
+    }
+}
+
+impl fmt::Display for RulesDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "error resolving determinator rules in {}: {}",
+            self.path,
+            self.error.rule_index()
+        )
+    }
+}
+
+impl StdError for RulesDiagnostic {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl Diagnostic for RulesDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let code = match self.error.kind() {
+            RulesErrorKind::ResolveRef(_) => "determinator::resolve_ref",
+            RulesErrorKind::GlobParse { .. } => "determinator::glob_parse",
+            _ => return None,
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        match self.error.rule_index() {
+            RuleIndex::DefaultPath(_) => Some(Box::new(
+                "this error is in a default rule: set `use-default-rules = false` to disable \
+                 default rules",
+            )),
+            _ => None,
+        }
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let label = if let Some(span) = self.span {
+            let text = match self.error.kind() {
+                RulesErrorKind::GlobParse { err, .. } => err.to_string(),
+                _ => "unknown package".to_owned(),
+            };
+            LabeledSpan::new_with_span(Some(text), span)
+        } else {
+            LabeledSpan::new_with_span(Some(self.error.kind().to_string()), self.rule_span?)
+        };
+        Some(Box::new(std::iter::once(label)))
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn Diagnostic> {
+        self.guppy_error
+            .as_ref()
+            .map(|diagnostic| diagnostic as &dyn Diagnostic)
+    }
+}
+
+/// Returns the byte range of the rule at `rule_index` in `contents`, starting at its header and
+/// ending before the next table header.
+fn rule_range(contents: &str, rule_index: RuleIndex) -> Option<Range<usize>> {
+    let (header, index) = match rule_index {
+        RuleIndex::CustomPath(index) => ("[[path-rule]]", index),
+        RuleIndex::Package(index) => ("[[package-rule]]", index),
+        // Default rules aren't part of the rules file.
+        RuleIndex::DefaultPath(_) => return None,
+    };
+
+    let mut offset = 0;
+    let mut start = None;
+    let mut seen = 0;
+    for line in contents.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            if let Some(start) = start {
+                return Some(start..offset);
+            }
+            if trimmed == header {
+                if seen == index {
+                    start = Some(offset + (line.len() - line.trim_start().len()));
+                }
+                seen += 1;
+            }
+        }
+        offset += line.len();
+    }
+    start.map(|start| start..contents.len())
+}
+
+/// Makes a copy of the variants of `guppy::Error` that can be returned while resolving rules.
+fn copy_guppy_error(error: &Error) -> Option<Error> {
+    match error {
+        Error::UnknownWorkspaceName(name) => Some(Error::UnknownWorkspaceName(name.clone())),
+        Error::UnknownWorkspacePath(path) => Some(Error::UnknownWorkspacePath(path.clone())),
+        Error::UnknownPackageId(package_id) => Some(Error::UnknownPackageId(package_id.clone())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use determinator::rules::DeterminatorRules;
+    use guppy::graph::PackageGraph;
+
+    #[test]
+    fn rule_spans() {
+        let contents = r#"
+        [[path-rule]]
+        globs = ["foo/**"]
+        mark-changed = "all"
+
+        [[package-rule]]
+        on-affected = ["testcrate"]
+        mark-changed = "all"
+
+        [[package-rule]]
+        on-affected = ["nonexistent"]
+        mark-changed = "all"
+        "#;
+        let rules = DeterminatorRules::parse(contents).expect("rules parsed");
+        let graph =
+            PackageGraph::from_json(include_str!("../../fixtures/small/metadata1.json")).unwrap();
+        let error = determinator::Determinator::new(&graph, &graph)
+            .set_rules(&rules)
+            .expect_err("unknown package name");
+        assert_eq!(error.rule_index(), RuleIndex::Package(1));
+
+        let diagnostic = RulesDiagnostic::new("rules.toml", contents, error);
+        let (offset, len) = diagnostic.span.expect("span found");
+        assert_eq!(&contents[offset..offset + len], "nonexistent");
+        assert!(diagnostic.diagnostic_source().is_some());
+
+        let range = rule_range(contents, RuleIndex::CustomPath(0)).expect("rule found");
+        assert!(contents[range.clone()].starts_with("[[path-rule]]"));
+        assert!(contents[range]
+            .trim_end()
+            .ends_with("mark-changed = \"all\""));
+        assert_eq!(rule_range(contents, RuleIndex::CustomPath(1)), None);
+        assert_eq!(rule_range(contents, RuleIndex::DefaultPath(0)), None);
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{find_quoted, IntoMietteDiagnostic};
+use guppy::Error;
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
+use std::{error::Error as StdError, fmt, fs};
+use target_spec_miette::IntoMietteDiagnostic as _;
+
+/// A wrapper around [`guppy::Error`] that implements [`Diagnostic`].
+///
+/// For target specifications in a package's `Cargo.toml` that failed to parse, this diagnostic
+/// reads the manifest and points at the offending specification.
+pub struct GuppyDiagnostic {
+    error: Error,
+    manifest: Option<(NamedSource<String>, (usize, usize))>,
+    target_spec: Option<Box<dyn Diagnostic + Send + Sync + 'static>>,
+}
+
+impl GuppyDiagnostic {
+    /// Creates a new `GuppyDiagnostic`.
+    pub fn new(error: Error) -> Self {
+        let mut manifest = None;
+        let target_spec = match &error {
+            Error::TargetSpecError(_, error) => Some(error.clone().into_diagnostic()),
+            Error::DependencyTargetSpecError(_, manifest_path, _, spec, error) => {
+                // The manifest may not be available, for example if the graph was constructed
+                // from saved `cargo metadata` output.
+                if let Ok(contents) = fs::read_to_string(manifest_path) {
+                    if let Some(span) = find_quoted(&contents, 0..contents.len(), spec) {
+                        manifest = Some((NamedSource::new(manifest_path, contents), span));
+                    }
+                }
+                Some((**error).clone().into_diagnostic())
+            }
+            _ => None,
+        };
+
+        Self {
+            error,
+            manifest,
+            target_spec,
+        }
+    }
+
+    /// Returns the underlying error.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+}
+
+impl fmt::Debug for GuppyDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.error, f)
+    }
+}
+
+impl fmt::Display for GuppyDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl StdError for GuppyDiagnostic {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.error.source()
+    }
+}
+
+impl Diagnostic for GuppyDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let code = match &self.error {
+            Error::CommandError(_) => "guppy::command_error",
+            Error::MetadataParseError(_) => "guppy::metadata_parse",
+            Error::PackageGraphConstructError(_) => "guppy::package_graph_construct",
+            Error::UnknownPackageId(_) => "guppy::unknown_package_id",
+            Error::UnknownFeatureId(..) => "guppy::unknown_feature_id",
+            Error::UnknownWorkspacePath(_) => "guppy::unknown_workspace_path",
+            Error::UnknownWorkspaceName(_) => "guppy::unknown_workspace_name",
+            Error::NotInWorkspace(_) => "guppy::not_in_workspace",
+            Error::TargetSpecError(..) | Error::DependencyTargetSpecError(..) => {
+                "guppy::target_spec"
+            }
+            Error::PackageMetadataTableError(..) => "guppy::package_metadata_table",
+            _ => return None,
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = match &self.error {
+            Error::UnknownPackageId(_) | Error::UnknownFeatureId(..) => {
+                "package IDs are as reported by `cargo metadata` for this workspace"
+            }
+            Error::UnknownWorkspacePath(_) => {
+                "workspace paths are relative to the workspace root, and must be listed in \
+                 `workspace.members`"
+            }
+            Error::UnknownWorkspaceName(_) => {
+                "workspace package names are as specified by `package.name` in each member's \
+                 Cargo.toml"
+            }
+            Error::DependencyTargetSpecError(..) => {
+                "target specifications are either a target triple, or a `cfg()` expression"
+            }
+            _ => return None,
+        };
+        Some(Box::new(help))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.manifest
+            .as_ref()
+            .map(|(source, _)| source as &dyn SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let (_, span) = self.manifest.as_ref()?;
+        let label =
+            LabeledSpan::new_with_span(Some("invalid target specification".to_owned()), *span);
+        Some(Box::new(std::iter::once(label)))
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn Diagnostic> {
+        self.target_spec
+            .as_deref()
+            .map(|diagnostic| diagnostic as &dyn Diagnostic)
+    }
+}
+
+impl IntoMietteDiagnostic for Error {
+    type IntoDiagnostic = GuppyDiagnostic;
+
+    fn into_diagnostic(self) -> Self::IntoDiagnostic {
+        GuppyDiagnostic::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino::Utf8PathBuf;
+    use guppy::graph::PackageGraph;
+
+    #[test]
+    fn dependency_target_spec() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = Utf8PathBuf::try_from(dir.path().join("Cargo.toml")).unwrap();
+        fs::write(
+            &manifest_path,
+            "[package]\nname = \"foo\"\n\n[target.'cfg(unix'.dependencies]\nbar = \"1\"\n",
+        )
+        .unwrap();
+
+        let error = Error::DependencyTargetSpecError(
+            guppy::PackageId::new("foo 0.1.0"),
+            manifest_path.clone(),
+            "bar".to_owned(),
+            "cfg(unix".to_owned(),
+            Box::new(
+                "cfg(unix"
+                    .parse::<target_spec::TargetSpec>()
+                    .expect_err("invalid spec"),
+            ),
+        );
+        let diagnostic = error.into_diagnostic();
+        let (_, (offset, len)) = diagnostic.manifest.as_ref().expect("manifest was read");
+        let contents = fs::read_to_string(&manifest_path).unwrap();
+        assert_eq!(&contents[*offset..*offset + *len], "cfg(unix");
+        assert!(diagnostic.diagnostic_source().is_some());
+
+        // Unknown workspace names have a help message.
+        let graph =
+            PackageGraph::from_json(include_str!("../../fixtures/small/metadata1.json")).unwrap();
+        let error = graph
+            .resolve_workspace_names(["nonexistent"])
+            .expect_err("unknown name");
+        let diagnostic = error.into_diagnostic();
+        assert!(diagnostic.help().is_some());
+        assert_eq!(
+            diagnostic.code().unwrap().to_string(),
+            "guppy::unknown_workspace_name"
+        );
+    }
+}
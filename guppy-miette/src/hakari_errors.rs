@@ -0,0 +1,212 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{find_quoted, GuppyDiagnostic, IntoMietteDiagnostic};
+use guppy::Error;
+use hakari::CargoTomlError;
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
+use std::{error::Error as StdError, fmt};
+
+/// A wrapper around [`hakari::CargoTomlError`] that implements [`Diagnostic`].
+pub struct CargoTomlDiagnostic(CargoTomlError);
+
+impl CargoTomlDiagnostic {
+    /// Creates a new `CargoTomlDiagnostic`.
+    pub fn new(error: CargoTomlError) -> Self {
+        Self(error)
+    }
+
+    /// Returns the underlying error.
+    pub fn error(&self) -> &CargoTomlError {
+        &self.0
+    }
+}
+
+impl fmt::Debug for CargoTomlDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for CargoTomlDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl StdError for CargoTomlDiagnostic {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.0.source()
+    }
+}
+
+impl Diagnostic for CargoTomlDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let code = match &self.0 {
+            CargoTomlError::Io { .. } => "hakari::cargo_toml_io",
+            CargoTomlError::GeneratedSectionNotFound { .. } => "hakari::section_not_found",
+            _ => return None,
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        match &self.0 {
+            CargoTomlError::GeneratedSectionNotFound { .. } => Some(Box::new(
+                "add the lines `### BEGIN HAKARI SECTION` and `### END HAKARI SECTION` to the \
+                 workspace-hack's Cargo.toml, or run `cargo hakari init` to create a new \
+                 workspace-hack package",
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl IntoMietteDiagnostic for CargoTomlError {
+    type IntoDiagnostic = CargoTomlDiagnostic;
+
+    fn into_diagnostic(self) -> Self::IntoDiagnostic {
+        CargoTomlDiagnostic::new(self)
+    }
+}
+
+/// A diagnostic for an error that occurred while turning a `hakari.toml` configuration into a
+/// `HakariBuilder`.
+///
+/// For unknown packages and registries, this diagnostic points at the locations in `hakari.toml`
+/// where they're referred to.
+pub struct HakariConfigDiagnostic {
+    error: GuppyDiagnostic,
+    path: String,
+    source: NamedSource<String>,
+    spans: Vec<(String, (usize, usize))>,
+}
+
+impl HakariConfigDiagnostic {
+    /// Creates a new `HakariConfigDiagnostic` from the path to `hakari.toml`, its contents, and the
+    /// error returned by `HakariBuilderSummary::to_hakari_builder`.
+    pub fn new(path: impl Into<String>, contents: impl Into<String>, error: Error) -> Self {
+        let path = path.into();
+        let contents = contents.into();
+        let needles: Vec<(&str, &str)> = match &error {
+            Error::UnknownWorkspaceName(name) => vec![("unknown workspace package", name)],
+            Error::UnknownWorkspacePath(path) => vec![("unknown workspace path", path.as_str())],
+            Error::UnknownPackageSetSummary {
+                unknown_workspace_members,
+                unknown_third_party,
+                ..
+            } => unknown_workspace_members
+                .iter()
+                .map(|name| ("unknown workspace package", name.as_str()))
+                .chain(
+                    unknown_third_party
+                        .iter()
+                        .map(|summary| ("unknown third-party package", summary.name.as_str())),
+                )
+                .collect(),
+            Error::UnknownRegistryName { registry_name, .. } => {
+                vec![("unknown registry", registry_name)]
+            }
+            _ => vec![],
+        };
+        let spans = needles
+            .into_iter()
+            .filter_map(|(label, needle)| {
+                let span = find_quoted(&contents, 0..contents.len(), needle)?;
+                Some((label.to_owned(), span))
+            })
+            .collect();
+        Self {
+            error: GuppyDiagnostic::new(error),
+            source: NamedSource::new(&path, contents),
+            path,
+            spans,
+        }
+    }
+
+    /// Returns the underlying error.
+    pub fn error(&self) -> &Error {
+        self.error.error()
+    }
+}
+
+impl fmt::Debug for HakariConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.error(), f)
+    }
+}
+
+impl fmt::Display for HakariConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error reading hakari config from {}", self.path)
+    }
+}
+
+impl StdError for HakariConfigDiagnostic {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.error())
+    }
+}
+
+impl Diagnostic for HakariConfigDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new("hakari::config"))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        if self.spans.is_empty() {
+            return None;
+        }
+        Some(Box::new(self.spans.iter().map(|(label, span)| {
+            LabeledSpan::new_with_span(Some(label.clone()), *span)
+        })))
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn Diagnostic> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use guppy::graph::PackageGraph;
+    use hakari::summaries::HakariConfig;
+
+    #[test]
+    fn unknown_packages() {
+        let contents = r#"
+        hakari-package = "testcrate"
+        resolver = "2"
+
+        [traversal-excludes]
+        workspace-members = ["nonexistent"]
+        third-party = [{ name = "not-a-crate" }]
+        "#;
+        let config: HakariConfig = contents.parse().expect("config parsed");
+        let graph =
+            PackageGraph::from_json(include_str!("../../fixtures/small/metadata1.json")).unwrap();
+        let error = config
+            .builder
+            .to_hakari_builder(&graph)
+            .expect_err("unknown packages");
+        let diagnostic = HakariConfigDiagnostic::new("hakari.toml", contents, error);
+        let spans: Vec<_> = diagnostic
+            .spans
+            .iter()
+            .map(|(label, (offset, len))| (label.as_str(), &contents[*offset..*offset + *len]))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("unknown workspace package", "nonexistent"),
+                ("unknown third-party package", "not-a-crate"),
+            ]
+        );
+        assert!(diagnostic.diagnostic_source().is_some());
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Integrate [guppy](https://crates.io/crates/guppy) errors with [miette](https://docs.rs/miette).
+//!
+//! This crate has implementations of `Diagnostic` for errors produced by guppy and, with the
+//! corresponding features enabled, [hakari](https://crates.io/crates/hakari) and
+//! [determinator](https://crates.io/crates/determinator). Where possible, diagnostics point at
+//! the exact location of a mistake within `Cargo.toml`, `hakari.toml` or determinator rules
+//! files.
+//!
+//! Errors from target-spec are converted using
+//! [target-spec-miette](https://crates.io/crates/target-spec-miette).
+//!
+//! ## Optional features
+//!
+//! * `hakari`: diagnostics for hakari's `CargoTomlError` and configuration errors.
+//! * `determinator`: diagnostics for determinator's `RulesError`.
+//!
+//! ## Minimum supported Rust version
+//!
+//! The minimum supported Rust version (MSRV) is **Rust 1.73**. While this crate is in pre-release
+//! status (0.x), The MSRV may be bumped in patch releases.
+
+#![warn(missing_docs)]
+#![forbid(unsafe_code)]
+#![cfg_attr(doc_cfg, feature(doc_cfg, doc_auto_cfg))]
+
+#[cfg(feature = "determinator")]
+mod determinator_errors;
+mod guppy_errors;
+#[cfg(feature = "hakari")]
+mod hakari_errors;
+mod toml_errors;
+
+#[cfg(feature = "determinator")]
+pub use determinator_errors::*;
+pub use guppy_errors::*;
+#[cfg(feature = "hakari")]
+pub use hakari_errors::*;
+pub use toml_errors::*;
+
+/// Extension trait that converts errors into a [`miette::Diagnostic`].
+///
+/// This is the equivalent of `target_spec_miette::IntoMietteDiagnostic` for the errors supported
+/// by this crate.
+pub trait IntoMietteDiagnostic {
+    /// The `Diagnostic` type that `self` will be converted to.
+    type IntoDiagnostic;
+
+    /// Converts the underlying error into [`Self::IntoDiagnostic`].
+    ///
+    /// This can be used to pretty-print errors returned by guppy and related crates.
+    fn into_diagnostic(self) -> Self::IntoDiagnostic;
+}
+
+/// Returns the span of the first occurrence of `needle` within `contents[range]` that's enclosed
+/// in quotes, not including the quotes.
+pub(crate) fn find_quoted(
+    contents: &str,
+    range: std::ops::Range<usize>,
+    needle: &str,
+) -> Option<(usize, usize)> {
+    let haystack = &contents[range.clone()];
+    ["\"", "'"].iter().find_map(|quote| {
+        let quoted = format!("{quote}{needle}{quote}");
+        haystack
+            .find(&quoted)
+            .map(|offset| (range.start + offset + quote.len(), needle.len()))
+    })
+}
@@ -0,0 +1,101 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
+use std::{error::Error as StdError, fmt};
+
+/// A diagnostic for an error that occurred while deserializing a TOML file.
+///
+/// This is used for `hakari.toml` and determinator rules files, both of which are parsed with
+/// `toml` 0.5.
+pub struct TomlParseDiagnostic {
+    error: toml::de::Error,
+    path: String,
+    source: NamedSource<String>,
+    offset: Option<usize>,
+}
+
+impl TomlParseDiagnostic {
+    /// Creates a new `TomlParseDiagnostic` from the path to the file, its contents, and the error
+    /// returned while parsing them.
+    pub fn new(
+        path: impl Into<String>,
+        contents: impl Into<String>,
+        error: toml::de::Error,
+    ) -> Self {
+        let path = path.into();
+        let contents = contents.into();
+        let offset = error
+            .line_col()
+            .and_then(|(line, col)| line_col_to_offset(&contents, line, col));
+        Self {
+            error,
+            source: NamedSource::new(&path, contents),
+            path,
+            offset,
+        }
+    }
+
+    /// Returns the underlying error.
+    pub fn error(&self) -> &toml::de::Error {
+        &self.error
+    }
+}
+
+impl fmt::Debug for TomlParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.error, f)
+    }
+}
+
+impl fmt::Display for TomlParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error parsing {}", self.path)
+    }
+}
+
+impl StdError for TomlParseDiagnostic {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl Diagnostic for TomlParseDiagnostic {
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let offset = self.offset?;
+        let label = LabeledSpan::new_with_span(Some(self.error.to_string()), (offset, 0));
+        Some(Box::new(std::iter::once(label)))
+    }
+}
+
+/// Converts a 0-based line and column into a byte offset.
+fn line_col_to_offset(contents: &str, line: usize, col: usize) -> Option<usize> {
+    let mut offset = 0;
+    for (idx, line_str) in contents.split_inclusive('\n').enumerate() {
+        if idx == line {
+            // toml 0.5 reports columns in bytes.
+            return Some(offset + col.min(line_str.len()));
+        }
+        offset += line_str.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_parse_offset() {
+        let contents = "a = 1\nb = \"é\" c\n";
+        let error = toml::from_str::<toml::Value>(contents).expect_err("invalid TOML");
+        let diagnostic = TomlParseDiagnostic::new("test.toml", contents, error);
+        let offset = diagnostic.offset.expect("offset found");
+        assert_eq!(&contents[offset..], "c\n");
+        assert_eq!(diagnostic.to_string(), "error parsing test.toml");
+    }
+}
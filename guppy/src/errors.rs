@@ -29,12 +29,23 @@ pub enum Error {
     UnknownWorkspacePath(Utf8PathBuf),
     /// A package specified by name was unknown to this workspace.
     UnknownWorkspaceName(String),
+    /// A package ID was known to this `PackageGraph`, but was expected to be in the workspace and
+    /// wasn't.
+    NotInWorkspace(PackageId),
     /// An error was returned by `target-spec`.
     TargetSpecError(String, TargetSpecError),
+    /// A dependency of a package had a target specification that `target-spec` could not parse.
+    ///
+    /// The fields are the package that has the dependency, the path to its `Cargo.toml`, the name
+    /// of the dependency, the target specification as written in the manifest, and the error
+    /// returned by `target-spec`.
+    DependencyTargetSpecError(PackageId, Utf8PathBuf, String, String, Box<TargetSpecError>),
     /// An internal error occurred within this `PackageGraph`.
     PackageGraphInternalError(String),
     /// An internal error occurred within this `FeatureGraph`.
     FeatureGraphInternalError(String),
+    /// The `package.metadata` section for a package was invalid.
+    PackageMetadataTableError(PackageId, serde_json::Error),
     /// A summary ID was unknown to this `PackageGraph`.
     ///
     /// This is present if the `summaries` feature is enabled.
@@ -99,9 +110,18 @@ impl fmt::Display for Error {
             }
             UnknownWorkspacePath(path) => write!(f, "unknown workspace path: {}", path),
             UnknownWorkspaceName(name) => write!(f, "unknown workspace package name: {}", name),
+            NotInWorkspace(id) => write!(f, "package ID not in workspace: {}", id),
             TargetSpecError(msg, _) => write!(f, "target spec error while {}", msg),
+            DependencyTargetSpecError(package_id, _, dep_name, spec, _) => write!(
+                f,
+                "for package '{}': for dependency '{}', parsing target '{}' failed",
+                package_id, dep_name, spec
+            ),
             PackageGraphInternalError(msg) => write!(f, "internal error in package graph: {}", msg),
             FeatureGraphInternalError(msg) => write!(f, "internal error in feature graph: {}", msg),
+            PackageMetadataTableError(id, _) => {
+                write!(f, "invalid package.metadata section for {}", id)
+            }
             #[cfg(feature = "summaries")]
             UnknownSummaryId(summary_id) => write!(f, "unknown summary ID: {}", summary_id),
             #[cfg(feature = "summaries")]
@@ -161,9 +181,12 @@ impl error::Error for Error {
             UnknownFeatureId(_, _) => None,
             UnknownWorkspacePath(_) => None,
             UnknownWorkspaceName(_) => None,
+            NotInWorkspace(_) => None,
             TargetSpecError(_, err) => Some(err),
+            DependencyTargetSpecError(_, _, _, _, err) => Some(&**err),
             PackageGraphInternalError(_) => None,
             FeatureGraphInternalError(_) => None,
+            PackageMetadataTableError(_, err) => Some(err),
             #[cfg(feature = "summaries")]
             UnknownSummaryId(_) => None,
             #[cfg(feature = "summaries")]
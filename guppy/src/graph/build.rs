@@ -70,6 +70,7 @@ impl PackageGraph {
         Ok(Self {
             dep_graph,
             sccs: OnceCell::new(),
+            reachability: OnceCell::new(),
             feature_graph: OnceCell::new(),
             data: PackageGraphData {
                 packages,
@@ -258,7 +259,8 @@ impl<'a> GraphBuildState<'a> {
         {
             let dep_id = PackageId::from_metadata(pkg);
             let (dep_data, deps) = dep_resolver.resolve(&resolved_name, &dep_id, &dep_kinds)?;
-            let link = PackageLinkImpl::new(&package_id, &resolved_name, deps)?;
+            let link =
+                PackageLinkImpl::new(&package_id, &package.manifest_path, &resolved_name, deps)?;
             // Use update_edge instead of add_edge to prevent multiple edges from being added
             // between these two nodes.
             // XXX maybe check for an existing edge?
@@ -827,6 +829,7 @@ impl<'g> DependencyReqs<'g> {
 impl PackageLinkImpl {
     fn new<'a>(
         from_id: &PackageId,
+        from_manifest_path: &Utf8Path,
         resolved_name: &str,
         deps: impl IntoIterator<Item = &'a Dependency>,
     ) -> Result<Self, Box<Error>> {
@@ -866,9 +869,11 @@ impl PackageLinkImpl {
             }
 
             match dep.kind {
-                DependencyKind::Normal => normal.add_instance(from_id, dep)?,
-                DependencyKind::Build => build.add_instance(from_id, dep)?,
-                DependencyKind::Development => dev.add_instance(from_id, dep)?,
+                DependencyKind::Normal => normal.add_instance(from_id, from_manifest_path, dep)?,
+                DependencyKind::Build => build.add_instance(from_id, from_manifest_path, dep)?,
+                DependencyKind::Development => {
+                    dev.add_instance(from_id, from_manifest_path, dep)?
+                }
                 _ => {
                     // unknown dependency kind -- can't do much with this!
                     continue;
@@ -927,27 +932,40 @@ impl PackageLinkImpl {
 /// causes this union-ing to *not* happen, so that's why we store all the features enabled by
 /// each target separately.
 impl DependencyReqImpl {
-    fn add_instance(&mut self, from_id: &PackageId, dep: &Dependency) -> Result<(), Box<Error>> {
+    fn add_instance(
+        &mut self,
+        from_id: &PackageId,
+        from_manifest_path: &Utf8Path,
+        dep: &Dependency,
+    ) -> Result<(), Box<Error>> {
         if dep.optional {
-            self.optional.add_instance(from_id, dep)
+            self.optional.add_instance(from_id, from_manifest_path, dep)
         } else {
-            self.required.add_instance(from_id, dep)
+            self.required.add_instance(from_id, from_manifest_path, dep)
         }
     }
 }
 
 impl DepRequiredOrOptional {
-    fn add_instance(&mut self, from_id: &PackageId, dep: &Dependency) -> Result<(), Box<Error>> {
+    fn add_instance(
+        &mut self,
+        from_id: &PackageId,
+        from_manifest_path: &Utf8Path,
+        dep: &Dependency,
+    ) -> Result<(), Box<Error>> {
         // target_spec is None if this is not a platform-specific dependency.
         let target_spec = match dep.target.as_ref() {
             Some(spec_or_triple) => {
                 // This is a platform-specific dependency, so add it to the list of specs.
                 let spec_or_triple = format!("{}", spec_or_triple);
                 let target_spec: TargetSpec = spec_or_triple.parse().map_err(|err| {
-                    Error::PackageGraphConstructError(format!(
-                        "for package '{}': for dependency '{}', parsing target '{}' failed: {}",
-                        from_id, dep.name, spec_or_triple, err
-                    ))
+                    Error::DependencyTargetSpecError(
+                        from_id.clone(),
+                        from_manifest_path.to_owned(),
+                        dep.name.clone(),
+                        spec_or_triple.clone(),
+                        Box::new(err),
+                    )
                 })?;
                 Some(target_spec)
             }
@@ -1051,6 +1069,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_dependency_target_spec_error() {
+        // cargo-platform and target-spec accept the same target specifications, so an invalid one
+        // can't be produced through JSON. Construct it directly instead.
+        let mut metadata: Metadata = serde_json::from_str(include_str!(
+            "../../../fixtures/small/metadata_targets1.json"
+        ))
+        .expect("parsing metadata JSON should succeed");
+        let invalid = cargo_platform::Platform::Name("not a triple".to_owned());
+
+        let package = metadata
+            .packages
+            .iter_mut()
+            .find(|package| package.name == "testcrate-targets")
+            .expect("testcrate-targets is present");
+        let package_id = PackageId::from_metadata(package.id.clone());
+        let manifest_path = package.manifest_path.clone();
+        let dep = package
+            .dependencies
+            .iter_mut()
+            .find(|dep| dep.target.is_some())
+            .expect("testcrate-targets has a platform-specific dependency");
+        let dep_name = dep.name.clone();
+        let original = dep.target.replace(invalid.clone());
+
+        // The resolve section must refer to the same target for the dependency to be matched.
+        for node in &mut metadata.resolve.as_mut().expect("resolve is present").nodes {
+            for dep_kind in node
+                .deps
+                .iter_mut()
+                .flat_map(|dep| dep.dep_kinds.iter_mut())
+            {
+                if dep_kind.target == original {
+                    dep_kind.target = Some(invalid.clone());
+                }
+            }
+        }
+
+        let err = PackageGraph::from_metadata(crate::CargoMetadata(metadata))
+            .expect_err("invalid target spec should fail");
+        match err {
+            Error::DependencyTargetSpecError(
+                err_package_id,
+                err_manifest_path,
+                err_dep_name,
+                spec,
+                _,
+            ) => {
+                assert_eq!(err_package_id, package_id, "package ID matches");
+                assert_eq!(err_manifest_path, manifest_path, "manifest path matches");
+                assert_eq!(err_dep_name, dep_name, "dependency name matches");
+                assert_eq!(spec, "not a triple", "spec matches");
+            }
+            other => panic!("expected DependencyTargetSpecError, found {}", other),
+        }
+    }
+
     #[test]
     fn test_convert_forward_slashes() {
         let components = vec!["..", "..", "foo", "bar", "baz.txt"];
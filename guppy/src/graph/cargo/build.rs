@@ -9,12 +9,14 @@ use crate::{
         feature::{ConditionalLink, FeatureLabel, FeatureQuery, FeatureSet, StandardFeatures},
         DependencyDirection, PackageGraph, PackageIx, PackageLink, PackageSet,
     },
-    platform::{EnabledTernary, PlatformSpec},
+    platform::{EnabledTernary, PlatformEval, PlatformSpec, PlatformStatus},
     sorted_set::SortedSet,
     DependencyKind, Error,
 };
 use fixedbitset::FixedBitSet;
 use petgraph::{prelude::*, visit::VisitMap};
+use std::collections::BTreeMap;
+use target_spec::TargetSpec;
 
 pub(super) struct CargoSetBuildState<'a> {
     opts: &'a CargoOptions<'a>,
@@ -40,44 +42,109 @@ impl<'a> CargoSetBuildState<'a> {
         initials: FeatureSet<'g>,
         features_only: FeatureSet<'g>,
     ) -> CargoSet<'g> {
-        match self.opts.resolver {
-            CargoResolverVersion::V1 => self.new_v1(initials, features_only, false),
-            CargoResolverVersion::V1Install => {
-                let avoid_dev_deps = !self.opts.include_dev;
-                self.new_v1(initials, features_only, avoid_dev_deps)
-            }
-            CargoResolverVersion::V2 => self.new_v2(initials, features_only),
-        }
+        let target_platform = &self.opts.target_platform;
+        let intermediate_set = self.build_intermediate_impl(
+            initials
+                .union(&features_only)
+                .to_feature_query(DependencyDirection::Forward),
+            target_platform,
+        );
+        self.build_set(initials, features_only, &intermediate_set, target_platform)
     }
 
     pub(super) fn build_intermediate(self, query: FeatureQuery) -> CargoIntermediateSet {
+        self.build_intermediate_impl(query, &self.opts.target_platform)
+    }
+
+    /// Builds one `CargoSet` per target platform, sharing work across platforms where possible.
+    pub(super) fn build_multi<'g>(
+        &self,
+        initials: &FeatureSet<'g>,
+        features_only: &FeatureSet<'g>,
+        target_platforms: &[PlatformSpec],
+    ) -> Vec<CargoSet<'g>> {
+        let query = initials
+            .union(features_only)
+            .to_feature_query(DependencyDirection::Forward);
+
+        // The V1 resolvers unify features across all platforms, so the intermediate set doesn't
+        // depend on the target platform and only needs to be computed once.
+        let shared_intermediate = match self.opts.resolver {
+            CargoResolverVersion::V1 | CargoResolverVersion::V1Install => {
+                Some(self.build_intermediate_impl(query.clone(), &PlatformSpec::Any))
+            }
+            CargoResolverVersion::V2 => None,
+        };
+
+        // Target platforms only affect the result through platform-specific dependencies, so
+        // platforms that evaluate them identically produce the same `CargoSet`. Only resolve the
+        // first platform in each such group.
+        let representatives =
+            group_target_platforms(initials.graph().package_graph, target_platforms);
+        let unique: Vec<_> = representatives
+            .iter()
+            .enumerate()
+            .filter(|&(idx, &representative)| idx == representative)
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let build_one = |&idx: &usize| {
+            let target_platform = &target_platforms[idx];
+            let owned_intermediate;
+            let intermediate_set = match &shared_intermediate {
+                Some(intermediate_set) => intermediate_set,
+                None => {
+                    owned_intermediate =
+                        self.build_intermediate_impl(query.clone(), target_platform);
+                    &owned_intermediate
+                }
+            };
+            let cargo_set = self.build_set(
+                initials.clone(),
+                features_only.clone(),
+                intermediate_set,
+                target_platform,
+            );
+            (idx, cargo_set)
+        };
+
+        let unique_sets: BTreeMap<usize, CargoSet<'g>> = {
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "rayon1")] {
+                    use rayon::prelude::*;
+                    unique.par_iter().map(build_one).collect::<Vec<_>>().into_iter().collect()
+                } else {
+                    unique.iter().map(build_one).collect()
+                }
+            }
+        };
+
+        representatives
+            .iter()
+            .zip(target_platforms)
+            .map(|(representative, target_platform)| {
+                let mut cargo_set = unique_sets[representative].clone();
+                cargo_set.target_platform = target_platform.clone();
+                cargo_set
+            })
+            .collect()
+    }
+
+    fn build_intermediate_impl<'g>(
+        &self,
+        query: FeatureQuery<'g>,
+        target_platform: &PlatformSpec,
+    ) -> CargoIntermediateSet<'g> {
         match self.opts.resolver {
             CargoResolverVersion::V1 => self.new_v1_intermediate(query, false),
             CargoResolverVersion::V1Install => {
                 let avoid_dev_deps = !self.opts.include_dev;
                 self.new_v1_intermediate(query, avoid_dev_deps)
             }
-            CargoResolverVersion::V2 => self.new_v2_intermediate(query),
+            CargoResolverVersion::V2 => self.new_v2_intermediate(query, target_platform),
         }
     }
 
-    fn new_v1<'g>(
-        self,
-        initials: FeatureSet<'g>,
-        features_only: FeatureSet<'g>,
-        avoid_dev_deps: bool,
-    ) -> CargoSet<'g> {
-        self.build_set(initials, features_only, |query| {
-            self.new_v1_intermediate(query, avoid_dev_deps)
-        })
-    }
-
-    fn new_v2<'g>(self, initials: FeatureSet<'g>, features_only: FeatureSet<'g>) -> CargoSet<'g> {
-        self.build_set(initials, features_only, |query| {
-            self.new_v2_intermediate(query)
-        })
-    }
-
     // ---
     // Helper methods
     // ---
@@ -90,7 +157,8 @@ impl<'a> CargoSetBuildState<'a> {
         &self,
         initials: FeatureSet<'g>,
         features_only: FeatureSet<'g>,
-        intermediate_fn: impl FnOnce(FeatureQuery<'g>) -> CargoIntermediateSet<'g>,
+        intermediate_set: &CargoIntermediateSet<'g>,
+        target_platform: &PlatformSpec,
     ) -> CargoSet<'g> {
         // Prepare a package query for step 2.
         let graph = *initials.graph();
@@ -132,12 +200,8 @@ impl<'a> CargoSetBuildState<'a> {
             .package_graph
             .query_from_parts(SortedSet::new(target_ixs), DependencyDirection::Forward);
 
-        // 1. Build the intermediate set containing the features for any possible package that can
-        // be built, including features-only packages.
-        let initials_plus_features_only = initials.union(&features_only);
-        let intermediate_set = intermediate_fn(
-            initials_plus_features_only.to_feature_query(DependencyDirection::Forward),
-        );
+        // 1. The intermediate set contains the features for any possible package that can be
+        // built, including features-only packages.
         let (target_set, host_set) = intermediate_set.target_host_sets();
 
         // While doing traversal 2 below, record any packages discovered along build edges for use
@@ -185,7 +249,6 @@ impl<'a> CargoSetBuildState<'a> {
 
         // 2. Figure out what packages will be included on the target platform, i.e. normal + dev
         // (if requested).
-        let target_platform = &self.opts.target_platform;
         let host_platform = &self.opts.host_platform;
 
         let target_packages = target_query.resolve_with_fn(|query, link| {
@@ -310,6 +373,8 @@ impl<'a> CargoSetBuildState<'a> {
             host_direct_deps,
             proc_macro_edge_ixs: SortedSet::new(proc_macro_edge_ixs),
             build_dep_edge_ixs: SortedSet::new(build_dep_edge_ixs),
+            target_platform: target_platform.clone(),
+            host_platform: host_platform.clone(),
         }
     }
 
@@ -340,7 +405,11 @@ impl<'a> CargoSetBuildState<'a> {
         CargoIntermediateSet::Unified(complete_set)
     }
 
-    fn new_v2_intermediate<'g>(&self, query: FeatureQuery<'g>) -> CargoIntermediateSet<'g> {
+    fn new_v2_intermediate<'g>(
+        &self,
+        query: FeatureQuery<'g>,
+        target_platform: &PlatformSpec,
+    ) -> CargoIntermediateSet<'g> {
         let graph = *query.graph();
         // Note that proc macros specified in initials take part in feature resolution
         // for both target and host ixs. If they didn't, then the query would be partitioned into
@@ -381,7 +450,6 @@ impl<'a> CargoSetBuildState<'a> {
         let target_query_2 = target_query.clone();
 
         // 1. Perform a feature query for the target.
-        let target_platform = &self.opts.target_platform;
         let host_platform = &self.opts.host_platform;
         let target = target_query.resolve_with_fn(|query, link| {
             let (from, to) = link.endpoints();
@@ -442,3 +510,53 @@ impl<'a> CargoSetBuildState<'a> {
         CargoIntermediateSet::TargetHost { target, host }
     }
 }
+
+/// For each target platform, returns the index of the first target platform that evaluates every
+/// platform-specific dependency in the graph the same way.
+///
+/// Every platform-specific status is a union of target specs, so two platforms that agree on
+/// each target spec agree on every status. Build dependencies are evaluated against the host
+/// platform, so only normal and dev-dependencies are considered.
+fn group_target_platforms(graph: &PackageGraph, target_platforms: &[PlatformSpec]) -> Vec<usize> {
+    let specs: Vec<&TargetSpec> = graph
+        .dep_graph()
+        .edge_weights()
+        .flat_map(|link| [&link.normal, &link.dev])
+        .flat_map(|req| [&req.required, &req.optional])
+        .flat_map(|req| {
+            [
+                &req.build_if,
+                &req.default_features_if,
+                &req.no_default_features_if,
+            ]
+            .into_iter()
+            .chain(req.feature_targets.values())
+        })
+        .flat_map(|status| status.specs())
+        .collect();
+
+    let signatures: Vec<Vec<EnabledTernary>> = target_platforms
+        .iter()
+        .map(|platform_spec| {
+            specs
+                .iter()
+                .map(|&spec| {
+                    let status = PlatformStatus::PlatformDependent {
+                        eval: PlatformEval::new(std::slice::from_ref(spec)),
+                    };
+                    status.enabled_on(platform_spec)
+                })
+                .collect()
+        })
+        .collect();
+
+    signatures
+        .iter()
+        .map(|signature| {
+            signatures
+                .iter()
+                .position(|other| other == signature)
+                .expect("signature is present in the list")
+        })
+        .collect()
+}
@@ -206,6 +206,8 @@ pub struct CargoSet<'g> {
     pub(super) host_direct_deps: PackageSet<'g>,
     pub(super) proc_macro_edge_ixs: SortedSet<EdgeIndex<PackageIx>>,
     pub(super) build_dep_edge_ixs: SortedSet<EdgeIndex<PackageIx>>,
+    pub(super) target_platform: PlatformSpec,
+    pub(super) host_platform: PlatformSpec,
 }
 
 assert_covariant!(CargoSet);
@@ -0,0 +1,199 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    graph::{
+        cargo::{BuildPlatform, CargoSet},
+        feature::{ConditionalLink, FeatureId, FeatureLabel},
+        DependencyDirection, PackageLink,
+    },
+    platform::{EnabledTernary, PlatformSpec},
+    DependencyKind, PackageId,
+};
+use ahash::{AHashMap, AHashSet};
+
+/// Dependencies declared with `default-features = false` that are built with default features
+/// anyway, because of feature unification.
+///
+/// A package may turn off a dependency's default features to avoid pulling in code it doesn't
+/// need. If any other link in the build enables the dependency's `default` feature, Cargo builds
+/// the dependency with default features regardless. This report lists each such declaration, along
+/// with the links that enable the `default` feature.
+///
+/// Only normal and build dependencies are considered. Dependencies that don't have a `default`
+/// feature are skipped, since `default-features = false` has no effect on them.
+///
+/// Constructed through `DefeatedDefaults::new`.
+#[derive(Clone, Debug)]
+pub struct DefeatedDefaults<'g> {
+    defeated: Vec<DefeatedDefault<'g>>,
+}
+
+assert_covariant!(DefeatedDefaults);
+
+impl<'g> DefeatedDefaults<'g> {
+    /// Finds the `default-features = false` declarations defeated in this `CargoSet`.
+    ///
+    /// The target and host platforms used to compute the `CargoSet` determine which links are
+    /// considered: declarations and enabling links that are disabled on the platform they're
+    /// evaluated against are skipped.
+    pub fn new(cargo_set: &CargoSet<'g>) -> Self {
+        let target_enablers = default_enablers(cargo_set, BuildPlatform::Target);
+        let host_enablers = default_enablers(cargo_set, BuildPlatform::Host);
+
+        let mut seen: AHashSet<(&'g PackageId, &'g PackageId, DependencyKind, BuildPlatform)> =
+            AHashSet::new();
+        let mut defeated = vec![];
+        for (from_platform, feature_set) in cargo_set.all_features() {
+            for package in feature_set
+                .to_package_set()
+                .packages(DependencyDirection::Forward)
+            {
+                for link in package.direct_links() {
+                    let to = link.to();
+                    if !to.has_default_feature() {
+                        continue;
+                    }
+                    let default_id = to.default_feature_id();
+
+                    for kind in [DependencyKind::Normal, DependencyKind::Build] {
+                        let req = link.req_for_kind(kind);
+                        if !req.is_present() || !req.default_features().is_never() {
+                            continue;
+                        }
+                        let eval_platform = eval_platform(cargo_set, from_platform, kind);
+                        if req.status().enabled_on(eval_platform) == EnabledTernary::Disabled {
+                            // This declaration doesn't apply to the platform being built for.
+                            continue;
+                        }
+                        let build_platform = if kind == DependencyKind::Build || to.is_proc_macro()
+                        {
+                            BuildPlatform::Host
+                        } else {
+                            from_platform
+                        };
+                        let enablers = match build_platform {
+                            BuildPlatform::Target => &target_enablers,
+                            BuildPlatform::Host => &host_enablers,
+                        };
+                        let enabled_by = match enablers.get(&default_id) {
+                            Some(enabled_by) => enabled_by,
+                            None => continue,
+                        };
+                        if seen.insert((package.id(), to.id(), kind, build_platform)) {
+                            defeated.push(DefeatedDefault {
+                                link,
+                                kind,
+                                build_platform,
+                                enabled_by: enabled_by.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        Self { defeated }
+    }
+
+    /// Returns the number of defeated declarations.
+    pub fn len(&self) -> usize {
+        self.defeated.len()
+    }
+
+    /// Returns true if no `default-features = false` declarations are defeated.
+    pub fn is_empty(&self) -> bool {
+        self.defeated.is_empty()
+    }
+
+    /// Iterates over the defeated declarations.
+    ///
+    /// Declarations are returned in forward topological order of the package that makes them.
+    pub fn iter<'a>(&'a self) -> impl ExactSizeIterator<Item = &'a DefeatedDefault<'g>> + 'a {
+        self.defeated.iter()
+    }
+}
+
+/// Returns the links that enable each default feature built on this platform.
+fn default_enablers<'g>(
+    cargo_set: &CargoSet<'g>,
+    build_platform: BuildPlatform,
+) -> AHashMap<FeatureId<'g>, Vec<ConditionalLink<'g>>> {
+    let mut enablers: AHashMap<_, Vec<_>> = AHashMap::new();
+    for link in cargo_set
+        .platform_features(build_platform)
+        .conditional_links(DependencyDirection::Forward)
+    {
+        let is_enabled = DependencyKind::VALUES.iter().any(|&kind| {
+            link.status_for_kind(kind)
+                .enabled_on(eval_platform(cargo_set, build_platform, kind))
+                != EnabledTernary::Disabled
+        });
+        if is_enabled && link.to().label() == FeatureLabel::Named("default") {
+            enablers
+                .entry(link.to().feature_id())
+                .or_default()
+                .push(link);
+        }
+    }
+    enablers
+}
+
+/// Returns the platform that a dependency of the given kind, from a package built on
+/// `from_platform`, is evaluated against.
+///
+/// This matches how `CargoSet` follows links: everything built on the host is evaluated against
+/// the host platform, and build dependencies of target packages are as well.
+fn eval_platform<'a>(
+    cargo_set: &'a CargoSet<'_>,
+    from_platform: BuildPlatform,
+    kind: DependencyKind,
+) -> &'a PlatformSpec {
+    match (from_platform, kind) {
+        (BuildPlatform::Target, DependencyKind::Normal | DependencyKind::Development) => {
+            &cargo_set.target_platform
+        }
+        (BuildPlatform::Target, DependencyKind::Build) | (BuildPlatform::Host, _) => {
+            &cargo_set.host_platform
+        }
+    }
+}
+
+/// A single `default-features = false` declaration defeated by feature unification.
+///
+/// Returned by `DefeatedDefaults`.
+#[derive(Clone, Debug)]
+pub struct DefeatedDefault<'g> {
+    link: PackageLink<'g>,
+    kind: DependencyKind,
+    build_platform: BuildPlatform,
+    enabled_by: Vec<ConditionalLink<'g>>,
+}
+
+assert_covariant!(DefeatedDefault);
+
+impl<'g> DefeatedDefault<'g> {
+    /// Returns the link on which `default-features = false` is declared.
+    pub fn link(&self) -> PackageLink<'g> {
+        self.link
+    }
+
+    /// Returns the kind of dependency the declaration is for.
+    pub fn kind(&self) -> DependencyKind {
+        self.kind
+    }
+
+    /// Returns the build platform the dependency is built on with default features.
+    pub fn build_platform(&self) -> BuildPlatform {
+        self.build_platform
+    }
+
+    /// Returns the feature links in the build that enable the dependency's `default` feature.
+    ///
+    /// The `from` end of each link is the feature that re-enables default features. This may be
+    /// the base feature of a package that depends on the dependency with default features, or a
+    /// named feature that enables `dep/default`.
+    pub fn enabled_by(&self) -> &[ConditionalLink<'g>] {
+        &self.enabled_by
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    graph::{
+        cargo::{BuildPlatform, CargoOptions, CargoSet, MultiPlatformCargoSet},
+        feature::{FeatureId, FeatureSet},
+        PackageSet,
+    },
+    platform::PlatformSpec,
+    Error, PackageId,
+};
+
+/// The additional packages and features pulled into a Cargo build by enabling each named feature
+/// of a package.
+///
+/// Costs are computed relative to a baseline `CargoSet` for a single target platform, and are
+/// reported separately for the target and host platforms. To compute costs for several target
+/// platforms at once, use [`FeatureCosts::new_per_platform`].
+#[derive(Clone, Debug)]
+pub struct FeatureCosts<'g> {
+    target_platform: PlatformSpec,
+    baseline: CargoSet<'g>,
+    costs: Vec<FeatureCost<'g>>,
+}
+
+assert_covariant!(FeatureCosts);
+
+impl<'g> FeatureCosts<'g> {
+    /// Computes the cost of enabling each named feature of `package_id`, on top of a Cargo build
+    /// of `initials` and `features_only` with the given options.
+    ///
+    /// For more about `initials` and `features_only`, see the documentation for
+    /// [`CargoSet::new`](CargoSet::new).
+    ///
+    /// If the package is part of `initials`, each feature is added to `initials`. Otherwise, each
+    /// feature is added to `features_only`: this simulates a dependency being built with the
+    /// feature turned on, for example through a workspace package's `Cargo.toml`.
+    ///
+    /// Returns an error if the package ID is unknown.
+    pub fn new(
+        initials: FeatureSet<'g>,
+        features_only: FeatureSet<'g>,
+        package_id: &PackageId,
+        opts: &CargoOptions<'_>,
+    ) -> Result<Self, Error> {
+        let feature_graph = *initials.graph();
+        let package = feature_graph.package_graph().metadata(package_id)?;
+        let in_initials = initials.contains_package(package_id)?;
+
+        let baseline = CargoSet::new(initials.clone(), features_only.clone(), opts)?;
+
+        let costs = package
+            .named_features()
+            .map(|feature_name| {
+                let feature_id = FeatureId::named(package.id(), feature_name);
+                let feature_set = feature_graph.resolve_ids([feature_id])?;
+                let cargo_set = if in_initials {
+                    CargoSet::new(initials.union(&feature_set), features_only.clone(), opts)?
+                } else {
+                    CargoSet::new(initials.clone(), features_only.union(&feature_set), opts)?
+                };
+                Ok(FeatureCost::new(feature_id, &baseline, &cargo_set))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self {
+            target_platform: opts.target_platform.clone(),
+            baseline,
+            costs,
+        })
+    }
+
+    /// Computes the cost of enabling each named feature of `package_id` on each of the given
+    /// target platforms.
+    ///
+    /// Returns one `FeatureCosts` per target platform, in the order the platforms were specified
+    /// in. The target platform in `opts` is ignored. Builds are simulated through
+    /// [`MultiPlatformCargoSet`], so work is shared across target platforms where possible.
+    ///
+    /// For more about the other arguments, see [`FeatureCosts::new`].
+    ///
+    /// Returns an error if the package ID is unknown.
+    pub fn new_per_platform(
+        initials: FeatureSet<'g>,
+        features_only: FeatureSet<'g>,
+        package_id: &PackageId,
+        opts: &CargoOptions<'_>,
+        target_platforms: impl IntoIterator<Item = impl Into<PlatformSpec>>,
+    ) -> Result<Vec<Self>, Error> {
+        let target_platforms: Vec<PlatformSpec> =
+            target_platforms.into_iter().map(Into::into).collect();
+        let feature_graph = *initials.graph();
+        let package = feature_graph.package_graph().metadata(package_id)?;
+        let in_initials = initials.contains_package(package_id)?;
+
+        let baseline = MultiPlatformCargoSet::new(
+            initials.clone(),
+            features_only.clone(),
+            opts,
+            target_platforms.iter().cloned(),
+        )?;
+
+        let mut per_platform: Vec<Self> = baseline
+            .iter()
+            .map(|(target_platform, baseline)| Self {
+                target_platform: target_platform.clone(),
+                baseline: baseline.clone(),
+                costs: Vec::new(),
+            })
+            .collect();
+
+        for feature_name in package.named_features() {
+            let feature_id = FeatureId::named(package.id(), feature_name);
+            let feature_set = feature_graph.resolve_ids([feature_id])?;
+            let (initials, features_only) = if in_initials {
+                (initials.union(&feature_set), features_only.clone())
+            } else {
+                (initials.clone(), features_only.union(&feature_set))
+            };
+            let multi = MultiPlatformCargoSet::new(
+                initials,
+                features_only,
+                opts,
+                target_platforms.iter().cloned(),
+            )?;
+            for (costs, (_, cargo_set)) in per_platform.iter_mut().zip(multi.iter()) {
+                let cost = FeatureCost::new(feature_id, &costs.baseline, cargo_set);
+                costs.costs.push(cost);
+            }
+        }
+
+        Ok(per_platform)
+    }
+
+    /// Returns the target platform these costs were computed for.
+    pub fn target_platform(&self) -> &PlatformSpec {
+        &self.target_platform
+    }
+
+    /// Returns the baseline `CargoSet` that costs are computed relative to.
+    pub fn baseline(&self) -> &CargoSet<'g> {
+        &self.baseline
+    }
+
+    /// Returns the costs for each named feature, in the order the features are declared in.
+    pub fn iter<'a>(&'a self) -> impl ExactSizeIterator<Item = &'a FeatureCost<'g>> + 'a {
+        self.costs.iter()
+    }
+
+    /// Returns the costs for each named feature, with the most expensive features first.
+    ///
+    /// Features are sorted by the number of packages they add across the target and host
+    /// platforms, then by the number of features they add. Ties are broken by feature name.
+    pub fn sorted_by_cost<'a>(&'a self) -> Vec<&'a FeatureCost<'g>> {
+        let mut costs: Vec<_> = self.costs.iter().collect();
+        costs.sort_by(|a, b| {
+            b.added_package_count()
+                .cmp(&a.added_package_count())
+                .then_with(|| b.added_feature_count().cmp(&a.added_feature_count()))
+                .then_with(|| a.feature_id.cmp(&b.feature_id))
+        });
+        costs
+    }
+}
+
+/// The additional packages and features pulled in by a single named feature.
+///
+/// Returned by `FeatureCosts`.
+#[derive(Clone, Debug)]
+pub struct FeatureCost<'g> {
+    feature_id: FeatureId<'g>,
+    target_features: FeatureSet<'g>,
+    host_features: FeatureSet<'g>,
+    target_packages: PackageSet<'g>,
+    host_packages: PackageSet<'g>,
+}
+
+assert_covariant!(FeatureCost);
+
+impl<'g> FeatureCost<'g> {
+    fn new(feature_id: FeatureId<'g>, baseline: &CargoSet<'g>, cargo_set: &CargoSet<'g>) -> Self {
+        let added_features = |build_platform| {
+            cargo_set
+                .platform_features(build_platform)
+                .difference(baseline.platform_features(build_platform))
+        };
+        let added_packages = |build_platform| {
+            cargo_set
+                .platform_features(build_platform)
+                .to_package_set()
+                .difference(&baseline.platform_features(build_platform).to_package_set())
+        };
+
+        Self {
+            feature_id,
+            target_features: added_features(BuildPlatform::Target),
+            host_features: added_features(BuildPlatform::Host),
+            target_packages: added_packages(BuildPlatform::Target),
+            host_packages: added_packages(BuildPlatform::Host),
+        }
+    }
+
+    /// Returns the feature this cost is for.
+    pub fn feature_id(&self) -> FeatureId<'g> {
+        self.feature_id
+    }
+
+    /// Returns the features added on the specified build platform.
+    ///
+    /// This includes new features of packages that were already present in the baseline.
+    pub fn added_features(&self, build_platform: BuildPlatform) -> &FeatureSet<'g> {
+        match build_platform {
+            BuildPlatform::Target => &self.target_features,
+            BuildPlatform::Host => &self.host_features,
+        }
+    }
+
+    /// Returns the packages added on the specified build platform.
+    pub fn added_packages(&self, build_platform: BuildPlatform) -> &PackageSet<'g> {
+        match build_platform {
+            BuildPlatform::Target => &self.target_packages,
+            BuildPlatform::Host => &self.host_packages,
+        }
+    }
+
+    /// Returns the number of packages added across the target and host platforms.
+    ///
+    /// A package added on both platforms is counted twice, since it is built twice.
+    pub fn added_package_count(&self) -> usize {
+        self.target_packages.len() + self.host_packages.len()
+    }
+
+    /// Returns the number of features added across the target and host platforms.
+    pub fn added_feature_count(&self) -> usize {
+        self.target_features.len() + self.host_features.len()
+    }
+
+    /// Returns true if enabling this feature doesn't add any packages or features.
+    pub fn is_free(&self) -> bool {
+        self.target_features.is_empty() && self.host_features.is_empty()
+    }
+}
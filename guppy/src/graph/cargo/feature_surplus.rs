@@ -0,0 +1,281 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    graph::{
+        cargo::CargoSet,
+        feature::{FeatureGraph, FeatureId, FeatureLabel},
+        DependencyDirection, PackageLink,
+    },
+    PackageId,
+};
+use ahash::AHashMap;
+use std::collections::BTreeSet;
+
+/// A comparison between the features each workspace package requests from its direct
+/// dependencies, and the features those dependencies are actually built with.
+///
+/// `DependencyReq::features` shows what a workspace package asks for, while a `CargoSet` shows
+/// what Cargo builds after feature unification. This report lines the two up, to help find
+/// features that can be removed from `Cargo.toml` files, or features that are only enabled by
+/// accident.
+///
+/// The report has one entry per workspace package in the initials of the `CargoSet`, and per
+/// direct dependency of that package that is built.
+///
+/// ## Approximations
+///
+/// Features are compared by name, and the target and host builds of a dependency are treated as
+/// one. A feature graph link is considered active if both its endpoints are built, so platform-
+/// and dependency-kind-specific feature requests are combined.
+#[derive(Clone, Debug)]
+pub struct FeatureSurplus<'g> {
+    entries: Vec<FeatureSurplusEntry<'g>>,
+}
+
+assert_covariant!(FeatureSurplus);
+
+impl<'g> FeatureSurplus<'g> {
+    /// Computes the feature surplus for the workspace packages in this `CargoSet`.
+    pub fn new(cargo_set: &CargoSet<'g>) -> Self {
+        let feature_graph = *cargo_set.feature_graph();
+        let built = cargo_set.target_features().union(cargo_set.host_features());
+
+        // For each package, the features enabled by links from other packages, along with the
+        // package each link comes from.
+        let mut incoming: AHashMap<&'g PackageId, Vec<(&'g PackageId, FeatureId<'g>)>> =
+            AHashMap::new();
+        for link in built.conditional_links(DependencyDirection::Forward) {
+            let (from, to) = (link.from().package_id(), link.to().package_id());
+            if from != to {
+                incoming
+                    .entry(to)
+                    .or_default()
+                    .push((from, link.to().feature_id()));
+            }
+        }
+
+        let members = cargo_set
+            .initials()
+            .to_package_set()
+            .packages(DependencyDirection::Forward)
+            .filter(|package| package.in_workspace())
+            .collect::<Vec<_>>();
+
+        let entries = members
+            .into_iter()
+            .flat_map(|member| member.direct_links())
+            .filter_map(|link| {
+                let built_features = built
+                    .features_for(link.to().id())
+                    .expect("valid package ID")?;
+                let incoming = incoming.get(link.to().id())?;
+                let (requests, others): (Vec<_>, Vec<_>) = incoming
+                    .iter()
+                    .partition(|(from, _)| *from == link.from().id());
+                if requests.is_empty() {
+                    // The dependency isn't active for this package, e.g. a dev-dependency that
+                    // is built for another reason.
+                    return None;
+                }
+                Some(FeatureSurplusEntry::new(
+                    &feature_graph,
+                    link,
+                    requests.into_iter().map(|(_, feature_id)| feature_id),
+                    others.into_iter().map(|(_, feature_id)| feature_id),
+                    built_features.named_features().collect(),
+                ))
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    /// Returns the number of entries in this report.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if this report has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the entries in this report.
+    ///
+    /// Entries are returned in forward topological order of workspace packages, and in the order
+    /// each package's dependencies are listed in.
+    pub fn iter<'a>(&'a self) -> impl ExactSizeIterator<Item = &'a FeatureSurplusEntry<'g>> + 'a {
+        self.entries.iter()
+    }
+
+    /// Iterates over the entries for which the workspace package gets features it doesn't ask
+    /// for, or asks for features it doesn't need to.
+    pub fn surplus<'a>(&'a self) -> impl Iterator<Item = &'a FeatureSurplusEntry<'g>> + 'a {
+        self.entries.iter().filter(|entry| !entry.is_exact())
+    }
+}
+
+/// The requested, built, free and redundant features for a single dependency of a workspace
+/// package.
+///
+/// Returned by `FeatureSurplus`.
+#[derive(Clone, Debug)]
+pub struct FeatureSurplusEntry<'g> {
+    link: PackageLink<'g>,
+    requested: Vec<&'g str>,
+    built: Vec<&'g str>,
+    free: Vec<&'g str>,
+    redundant: Vec<(&'g str, RedundantReason)>,
+}
+
+assert_covariant!(FeatureSurplusEntry);
+
+impl<'g> FeatureSurplusEntry<'g> {
+    fn new(
+        feature_graph: &FeatureGraph<'g>,
+        link: PackageLink<'g>,
+        requests: impl IntoIterator<Item = FeatureId<'g>>,
+        others: impl IntoIterator<Item = FeatureId<'g>>,
+        built: Vec<&'g str>,
+    ) -> Self {
+        let dep_id = link.to().id();
+
+        let closure = |seeds: &mut dyn Iterator<Item = FeatureId<'g>>| {
+            named_closure(feature_graph, dep_id, seeds)
+        };
+        let requested: BTreeSet<&'g str> = requests
+            .into_iter()
+            .filter_map(|feature_id| match feature_id.label() {
+                FeatureLabel::Named(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        let requested: Vec<_> = requested.into_iter().collect();
+        let named = |feature: &&'g str| FeatureId::named(dep_id, feature);
+
+        let from_request = closure(&mut requested.iter().map(named));
+        let from_default = if requested.contains(&"default") {
+            closure(&mut std::iter::once(FeatureId::named(dep_id, "default")))
+        } else {
+            BTreeSet::new()
+        };
+        let from_others = closure(&mut others.into_iter());
+
+        let free = built
+            .iter()
+            .copied()
+            .filter(|feature| !from_request.contains(feature))
+            .collect();
+
+        let redundant = requested
+            .iter()
+            .filter_map(|&feature| {
+                if feature == "default" {
+                    return None;
+                }
+                if from_default.contains(feature) {
+                    return Some((feature, RedundantReason::Default));
+                }
+                let from_rest = closure(
+                    &mut requested
+                        .iter()
+                        .filter(|&&other| other != feature)
+                        .map(named),
+                );
+                if from_rest.contains(feature) {
+                    return Some((feature, RedundantReason::Requested));
+                }
+                if from_others.contains(feature) {
+                    return Some((feature, RedundantReason::OtherEdges));
+                }
+                None
+            })
+            .collect();
+
+        Self {
+            link,
+            requested,
+            built,
+            free,
+            redundant,
+        }
+    }
+
+    /// Returns the link from the workspace package to its dependency.
+    pub fn link(&self) -> PackageLink<'g> {
+        self.link
+    }
+
+    /// Returns the named features the workspace package requests for this dependency, sorted by
+    /// name.
+    ///
+    /// This includes features listed in the dependency's `features` array, features enabled
+    /// through the package's own `[features]` section (for example `"dep/feature"`), and `default`
+    /// if default features are requested. Only requests from features of the workspace package
+    /// that are built are considered.
+    pub fn requested(&self) -> &[&'g str] {
+        &self.requested
+    }
+
+    /// Returns the named features this dependency is built with, after feature unification.
+    pub fn built(&self) -> &[&'g str] {
+        &self.built
+    }
+
+    /// Returns the named features this dependency is built with that aren't enabled by the
+    /// workspace package's own request. These features are enabled by other packages in the build.
+    ///
+    /// Code in the workspace package that relies on these features only compiles because of
+    /// feature unification.
+    pub fn free(&self) -> &[&'g str] {
+        &self.free
+    }
+
+    /// Returns the requested features that would be enabled even if they weren't requested, along
+    /// with the reason they are redundant.
+    pub fn redundant(&self) -> &[(&'g str, RedundantReason)] {
+        &self.redundant
+    }
+
+    /// Returns true if the workspace package gets exactly the features it asks for, and doesn't
+    /// ask for any redundant features.
+    pub fn is_exact(&self) -> bool {
+        self.free.is_empty() && self.redundant.is_empty()
+    }
+}
+
+/// Returns the named features of a package enabled by the given features, following links within
+/// the package.
+fn named_closure<'g>(
+    feature_graph: &FeatureGraph<'g>,
+    package_id: &'g PackageId,
+    seeds: impl Iterator<Item = FeatureId<'g>>,
+) -> BTreeSet<&'g str> {
+    feature_graph
+        .query_forward(seeds.chain(std::iter::once(FeatureId::base(package_id))))
+        .expect("feature IDs are valid")
+        .resolve_with_fn(|_, link| link.to().package_id() == package_id)
+        .features_for(package_id)
+        .expect("valid package ID")
+        .map_or_else(BTreeSet::new, |list| list.named_features().collect())
+}
+
+/// The reason a requested feature is redundant.
+///
+/// Returned by `FeatureSurplusEntry::redundant`.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
+pub enum RedundantReason {
+    /// The feature is enabled by the dependency's default features, which are requested.
+    Default,
+
+    /// The feature is enabled by another feature requested on the same dependency.
+    Requested,
+
+    /// The feature is enabled by another package in the build.
+    ///
+    /// Removing the feature from the request may still be a mistake: the workspace package will
+    /// then only build because of feature unification.
+    OtherEdges,
+}
@@ -8,5 +8,13 @@
 
 pub(super) mod build;
 mod cargo_api;
+mod defeated_defaults;
+mod feature_cost;
+mod feature_surplus;
+mod multi_platform;
 
 pub use cargo_api::*;
+pub use defeated_defaults::*;
+pub use feature_cost::*;
+pub use feature_surplus::*;
+pub use multi_platform::*;
@@ -0,0 +1,189 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    graph::{
+        cargo::{build::CargoSetBuildState, BuildPlatform, CargoOptions, CargoSet},
+        feature::FeatureSet,
+        PackageSet,
+    },
+    platform::PlatformSpec,
+    Error,
+};
+
+/// A set of packages and features as would be built by Cargo, for several target platforms at
+/// once.
+///
+/// This is equivalent to computing one `CargoSet` per target platform, but shares work across
+/// platforms in two ways:
+///
+/// * Target platforms that evaluate every platform-specific dependency in the graph the same way
+///   (for example, two Linux targets in a graph that only has `cfg(windows)` dependencies) share a
+///   single resolution. This applies to all resolver versions.
+/// * With the V1 resolvers, features are unified across all platforms, so feature resolution is
+///   only performed once.
+///
+/// Beyond that, each distinct target platform is resolved on its own: with the V2 resolver, this
+/// includes feature resolution. If the `rayon1` feature is enabled, distinct target platforms are
+/// resolved in parallel.
+#[derive(Clone, Debug)]
+pub struct MultiPlatformCargoSet<'g> {
+    platforms: Vec<PlatformSpec>,
+    cargo_sets: Vec<CargoSet<'g>>,
+}
+
+assert_covariant!(MultiPlatformCargoSet);
+
+impl<'g> MultiPlatformCargoSet<'g> {
+    /// Simulates a Cargo build of this feature set on each of the given target platforms.
+    ///
+    /// The target platform in `opts` is ignored, and the host platform in `opts` is used for every
+    /// build. For more about `initials` and `features_only`, see the documentation for
+    /// [`CargoSet::new`](CargoSet::new).
+    pub fn new(
+        initials: FeatureSet<'g>,
+        features_only: FeatureSet<'g>,
+        opts: &CargoOptions<'_>,
+        target_platforms: impl IntoIterator<Item = impl Into<PlatformSpec>>,
+    ) -> Result<Self, Error> {
+        let platforms: Vec<_> = target_platforms.into_iter().map(Into::into).collect();
+        let build_state = CargoSetBuildState::new(initials.graph().package_graph, opts)?;
+        let cargo_sets = build_state.build_multi(&initials, &features_only, &platforms);
+        Ok(Self {
+            platforms,
+            cargo_sets,
+        })
+    }
+
+    /// Returns the number of target platforms.
+    pub fn len(&self) -> usize {
+        self.platforms.len()
+    }
+
+    /// Returns true if no target platforms were specified.
+    pub fn is_empty(&self) -> bool {
+        self.platforms.is_empty()
+    }
+
+    /// Returns the target platform and `CargoSet` at this index, in the order the platforms were
+    /// specified in.
+    pub fn get(&self, index: usize) -> Option<(&PlatformSpec, &CargoSet<'g>)> {
+        Some((self.platforms.get(index)?, self.cargo_sets.get(index)?))
+    }
+
+    /// Iterates over target platforms and their `CargoSet`s, in the order the platforms were
+    /// specified in.
+    pub fn iter<'a>(
+        &'a self,
+    ) -> impl ExactSizeIterator<Item = (&'a PlatformSpec, &'a CargoSet<'g>)> + 'a {
+        self.platforms.iter().zip(&self.cargo_sets)
+    }
+
+    /// Returns the features enabled on the specified build platform for every target platform.
+    ///
+    /// Returns `None` if no target platforms were specified.
+    pub fn common_features(&self, build_platform: BuildPlatform) -> Option<FeatureSet<'g>> {
+        let mut sets = self
+            .cargo_sets
+            .iter()
+            .map(|cargo_set| cargo_set.platform_features(build_platform));
+        let first = sets.next()?.clone();
+        Some(sets.fold(first, |acc, set| acc.intersection(set)))
+    }
+
+    /// Returns the features enabled on the specified build platform for any target platform.
+    ///
+    /// Returns `None` if no target platforms were specified.
+    pub fn union_features(&self, build_platform: BuildPlatform) -> Option<FeatureSet<'g>> {
+        let mut sets = self
+            .cargo_sets
+            .iter()
+            .map(|cargo_set| cargo_set.platform_features(build_platform));
+        let first = sets.next()?.clone();
+        Some(sets.fold(first, |acc, set| acc.union(set)))
+    }
+
+    /// Returns, for each target platform, the packages and features that aren't built for every
+    /// other target platform.
+    ///
+    /// Differences are returned in the order the platforms were specified in.
+    pub fn differences(&self) -> Vec<PlatformDifference<'g>> {
+        let (common_target, common_host) = match (
+            self.common_features(BuildPlatform::Target),
+            self.common_features(BuildPlatform::Host),
+        ) {
+            (Some(target), Some(host)) => (target, host),
+            _ => return vec![],
+        };
+        let common_target_packages = common_target.to_package_set();
+        let common_host_packages = common_host.to_package_set();
+
+        self.iter()
+            .map(|(platform, cargo_set)| {
+                let target_features = cargo_set.target_features().difference(&common_target);
+                let host_features = cargo_set.host_features().difference(&common_host);
+                let target_packages = cargo_set
+                    .target_features()
+                    .to_package_set()
+                    .difference(&common_target_packages);
+                let host_packages = cargo_set
+                    .host_features()
+                    .to_package_set()
+                    .difference(&common_host_packages);
+                PlatformDifference {
+                    platform: platform.clone(),
+                    target_features,
+                    host_features,
+                    target_packages,
+                    host_packages,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The packages and features built for one target platform but not for every other one.
+///
+/// Returned by `MultiPlatformCargoSet::differences`.
+#[derive(Clone, Debug)]
+pub struct PlatformDifference<'g> {
+    platform: PlatformSpec,
+    target_features: FeatureSet<'g>,
+    host_features: FeatureSet<'g>,
+    target_packages: PackageSet<'g>,
+    host_packages: PackageSet<'g>,
+}
+
+assert_covariant!(PlatformDifference);
+
+impl<'g> PlatformDifference<'g> {
+    /// Returns the target platform this difference is for.
+    pub fn platform(&self) -> &PlatformSpec {
+        &self.platform
+    }
+
+    /// Returns the features built on the specified build platform that aren't built for every
+    /// target platform.
+    ///
+    /// This includes additional features of packages built for every target platform.
+    pub fn extra_features(&self, build_platform: BuildPlatform) -> &FeatureSet<'g> {
+        match build_platform {
+            BuildPlatform::Target => &self.target_features,
+            BuildPlatform::Host => &self.host_features,
+        }
+    }
+
+    /// Returns the packages built on the specified build platform that aren't built for every
+    /// target platform.
+    pub fn extra_packages(&self, build_platform: BuildPlatform) -> &PackageSet<'g> {
+        match build_platform {
+            BuildPlatform::Target => &self.target_packages,
+            BuildPlatform::Host => &self.host_packages,
+        }
+    }
+
+    /// Returns true if this target platform builds exactly what every other one builds.
+    pub fn is_empty(&self) -> bool {
+        self.target_features.is_empty() && self.host_features.is_empty()
+    }
+}
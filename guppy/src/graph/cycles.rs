@@ -285,7 +285,9 @@ impl<'g> Cycles<'g> {
 
     /// Returns true if these two IDs are in the same cycle.
     ///
-    /// This is equivalent to checking if they're in the same Strongly Connected Component.
+    /// This is equivalent to checking if they're in the same Strongly Connected Component, and
+    /// takes constant time. To check whether one package depends on another without being in
+    /// the same cycle, use [`PackageGraph::reachability_index`].
     pub fn is_cyclic(&self, a: &PackageId, b: &PackageId) -> Result<bool, Error> {
         let a_ix = self.package_graph.package_ix(a)?;
         let b_ix = self.package_graph.package_ix(b)?;
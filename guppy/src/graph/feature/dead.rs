@@ -0,0 +1,201 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Detection of named features that are never enabled or have no effect.
+
+use crate::{
+    graph::{
+        feature::{FeatureEdge, FeatureGraph, FeatureId, FeatureLabel},
+        DependencyDirection, FeatureIx, PackageIx, PackageMetadata, PackageSet,
+    },
+    sorted_set::SortedSet,
+};
+use petgraph::prelude::*;
+
+/// Named features of workspace packages that are never enabled, or that don't do anything.
+///
+/// A named feature may be reported for several reasons at once. The `default` feature is never
+/// reported, since it is enabled unless a dependent opts out of it.
+///
+/// Constructed through `FeatureGraph::dead_features`.
+#[derive(Clone, Debug)]
+pub struct DeadFeatures<'g> {
+    never_enabled: Vec<FeatureId<'g>>,
+    enables_nothing: Vec<FeatureId<'g>>,
+    shadowed: Vec<FeatureId<'g>>,
+}
+
+assert_covariant!(DeadFeatures);
+
+impl<'g> DeadFeatures<'g> {
+    pub(super) fn new(feature_graph: FeatureGraph<'g>, packages: &PackageSet<'g>) -> Self {
+        let mut never_enabled = vec![];
+        let mut enables_nothing = vec![];
+        let mut shadowed = vec![];
+
+        for package in packages
+            .packages(DependencyDirection::Forward)
+            .filter(|package| package.in_workspace())
+        {
+            let always_on = AlwaysOn::new(feature_graph, package);
+
+            for feature_ix in feature_graph.feature_ixs_for_package_ix(package.package_ix()) {
+                let feature_id = FeatureId::from_node(
+                    feature_graph.package_graph,
+                    &feature_graph.dep_graph()[feature_ix],
+                );
+                match feature_id.label() {
+                    FeatureLabel::Named("default") => continue,
+                    FeatureLabel::Named(_) => {}
+                    FeatureLabel::Base | FeatureLabel::OptionalDependency(_) => continue,
+                }
+
+                if !Self::is_ever_enabled(feature_graph, package, feature_ix) {
+                    never_enabled.push(feature_id);
+                }
+                if Self::enables_nothing_impl(feature_graph, feature_ix) {
+                    enables_nothing.push(feature_id);
+                } else if always_on.shadows(feature_ix) {
+                    shadowed.push(feature_id);
+                }
+            }
+        }
+
+        Self {
+            never_enabled,
+            enables_nothing,
+            shadowed,
+        }
+    }
+
+    /// Returns named features that aren't enabled by any other workspace package or by the
+    /// `default` feature of their own package.
+    ///
+    /// Features enabled through the `[dev-dependencies]` section of another workspace package are
+    /// considered to be enabled.
+    pub fn never_enabled<'a>(&'a self) -> impl ExactSizeIterator<Item = FeatureId<'g>> + 'a {
+        self.never_enabled.iter().copied()
+    }
+
+    /// Returns named features that don't enable any other features or optional dependencies.
+    ///
+    /// Such features may still be used through `#[cfg(feature = "...")]` in the package's code.
+    pub fn enables_nothing<'a>(&'a self) -> impl ExactSizeIterator<Item = FeatureId<'g>> + 'a {
+        self.enables_nothing.iter().copied()
+    }
+
+    /// Returns named features where everything enabled in other packages is already enabled by
+    /// the non-optional dependencies of the package, on every platform.
+    ///
+    /// For example, with:
+    ///
+    /// ```toml
+    /// [dependencies]
+    /// serde = { version = "1", features = ["std"] }
+    ///
+    /// [features]
+    /// std = ["serde/std"]
+    /// ```
+    ///
+    /// the `std` feature is shadowed, since `serde/std` is always enabled.
+    pub fn shadowed<'a>(&'a self) -> impl ExactSizeIterator<Item = FeatureId<'g>> + 'a {
+        self.shadowed.iter().copied()
+    }
+
+    /// Returns true if no features were reported.
+    pub fn is_empty(&self) -> bool {
+        self.never_enabled.is_empty() && self.enables_nothing.is_empty() && self.shadowed.is_empty()
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn is_ever_enabled(
+        feature_graph: FeatureGraph<'g>,
+        package: PackageMetadata<'g>,
+        feature_ix: NodeIndex<FeatureIx>,
+    ) -> bool {
+        let package_graph = feature_graph.package_graph;
+        let dep_graph = feature_graph.dep_graph();
+        let default_ix = feature_graph
+            .feature_ix(package.default_feature_id())
+            .expect("default feature ID is valid");
+
+        // Anything that can reach this feature is something that enables it.
+        feature_graph
+            .query_from_parts(
+                SortedSet::new(vec![feature_ix]),
+                DependencyDirection::Reverse,
+            )
+            .resolve()
+            .ixs_unordered()
+            .any(|enabler_ix| {
+                let enabler_package_ix = dep_graph[enabler_ix].package_ix();
+                if enabler_package_ix == package.package_ix() {
+                    enabler_ix == default_ix
+                } else {
+                    package_graph
+                        .metadata(&package_graph.dep_graph[enabler_package_ix])
+                        .expect("valid package ID")
+                        .in_workspace()
+                }
+            })
+    }
+
+    fn enables_nothing_impl(
+        feature_graph: FeatureGraph<'g>,
+        feature_ix: NodeIndex<FeatureIx>,
+    ) -> bool {
+        // Every feature has an edge to its base feature, so ignore that.
+        feature_graph
+            .dep_graph()
+            .edges_directed(feature_ix, Outgoing)
+            .all(|edge| matches!(edge.weight(), FeatureEdge::FeatureToBase))
+    }
+}
+
+/// The features always enabled by a package's non-optional dependencies.
+struct AlwaysOn<'g> {
+    feature_graph: FeatureGraph<'g>,
+    package_ix: NodeIndex<PackageIx>,
+    ixs: SortedSet<NodeIndex<FeatureIx>>,
+}
+
+impl<'g> AlwaysOn<'g> {
+    fn new(feature_graph: FeatureGraph<'g>, package: PackageMetadata<'g>) -> Self {
+        let base_ix = feature_graph
+            .feature_ix(FeatureId::base(package.id()))
+            .expect("base feature ID is valid");
+        let ixs = feature_graph
+            .query_from_parts(SortedSet::new(vec![base_ix]), DependencyDirection::Forward)
+            .resolve_with_fn(|_, link| link.normal().is_always() || link.build().is_always())
+            .ixs_unordered()
+            .collect::<Vec<_>>();
+
+        Self {
+            feature_graph,
+            package_ix: package.package_ix(),
+            ixs: SortedSet::new(ixs),
+        }
+    }
+
+    /// Returns true if this feature enables something in another package, and everything it
+    /// enables there is always on.
+    fn shadows(&self, feature_ix: NodeIndex<FeatureIx>) -> bool {
+        let dep_graph = self.feature_graph.dep_graph();
+        let enabled = self
+            .feature_graph
+            .query_from_parts(
+                SortedSet::new(vec![feature_ix]),
+                DependencyDirection::Forward,
+            )
+            .resolve_with_fn(|_, link| !link.dev_only());
+        let mut external = enabled
+            .ixs_unordered()
+            .filter(|ix| dep_graph[*ix].package_ix() != self.package_ix)
+            .peekable();
+
+        external.peek().is_some() && external.all(|ix| self.ixs.contains(&ix))
+    }
+}
@@ -7,10 +7,11 @@ use crate::{
     graph::{
         feature::{
             build::{FeatureGraphBuildState, FeaturePetgraph},
-            Cycles, FeatureFilter, FeatureList, WeakDependencies, WeakIndex,
+            Cycles, DeadFeatures, FeatureFilter, FeatureList, FeaturePowerset,
+            FeaturePowersetOptions, WeakDependencies, WeakIndex,
         },
         DependencyDirection, FeatureIndexInPackage, FeatureIx, PackageGraph, PackageIx,
-        PackageLink, PackageMetadata,
+        PackageLink, PackageMetadata, PackageSet,
     },
     petgraph_support::{scc::Sccs, topo::TopoWithCycles},
     platform::{PlatformStatus, PlatformStatusImpl},
@@ -199,6 +200,33 @@ impl<'g> FeatureGraph<'g> {
         Cycles::new(*self)
     }
 
+    /// Returns named features of workspace packages in this set that are never enabled, or that
+    /// have no effect.
+    ///
+    /// Non-workspace packages in the set are ignored. To analyze the entire workspace, pass in
+    /// `PackageGraph::resolve_workspace()`.
+    ///
+    /// For more information, see the documentation for `DeadFeatures`.
+    pub fn dead_features(&self, packages: &PackageSet<'g>) -> DeadFeatures<'g> {
+        DeadFeatures::new(*self, packages)
+    }
+
+    /// Returns combinations of named features of this package, for feature-combination testing.
+    ///
+    /// Combinations that resolve to the same `FeatureSet` are deduplicated.
+    ///
+    /// Returns an error if the package ID is unknown, or if the options refer to a named feature
+    /// that doesn't exist.
+    ///
+    /// For more information, see the documentation for `FeaturePowerset`.
+    pub fn feature_powerset(
+        &self,
+        package_id: &PackageId,
+        opts: &FeaturePowersetOptions,
+    ) -> Result<FeaturePowerset<'g>, Error> {
+        FeaturePowerset::new(*self, package_id, opts)
+    }
+
     // ---
     // Helper methods
     // ---
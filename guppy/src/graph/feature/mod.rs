@@ -9,8 +9,10 @@
 
 mod build;
 mod cycles;
+mod dead;
 pub mod feature_list;
 mod graph_impl;
+mod powerset;
 #[cfg(feature = "proptest1")]
 mod proptest_helpers;
 mod query;
@@ -19,8 +21,10 @@ mod weak;
 
 use build::*;
 pub use cycles::*;
+pub use dead::*;
 pub use feature_list::FeatureList;
 pub use graph_impl::*;
+pub use powerset::*;
 pub use query::*;
 pub use resolve::*;
 pub use weak::*;
@@ -0,0 +1,396 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Generation of feature combinations for testing.
+
+use crate::{
+    graph::{
+        feature::{FeatureGraph, FeatureId, FeatureLabel, FeatureSet},
+        PackageMetadata,
+    },
+    Error, PackageId,
+};
+use ahash::AHashSet;
+use serde::{
+    de::{Error as _, Unexpected},
+    Deserialize, Deserializer,
+};
+
+/// Options for generating feature combinations of a package.
+///
+/// These options can be specified in code, or read from the `[package.metadata]` section of a
+/// package's `Cargo.toml`:
+///
+/// ```toml
+/// [package.metadata.guppy.feature-powerset]
+/// # Generate combinations of at most 3 features, not counting always-included features. This
+/// # defaults to 2, and can be set to "unlimited" to generate every combination.
+/// depth = 3
+/// # Never enable these features.
+/// exclude = ["nightly"]
+/// # Never enable more than one feature from each of these groups.
+/// mutually-exclusive = [["rustls", "native-tls"]]
+/// # Enable these features in every combination.
+/// always-include = ["std"]
+/// ```
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FeaturePowersetOptions {
+    #[serde(deserialize_with = "deserialize_depth")]
+    depth: Option<usize>,
+    exclude: Vec<String>,
+    mutually_exclusive: Vec<Vec<String>>,
+    always_include: Vec<String>,
+}
+
+impl FeaturePowersetOptions {
+    /// The default maximum number of features in each combination.
+    pub const DEFAULT_DEPTH: usize = 2;
+
+    /// Creates a new `FeaturePowersetOptions` that generates combinations of up to
+    /// [`DEFAULT_DEPTH`](Self::DEFAULT_DEPTH) named features.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads options from the `package.metadata.guppy.feature-powerset` section of this package's
+    /// `Cargo.toml`.
+    ///
+    /// Returns the default options if the section isn't present, and an error if it is invalid.
+    pub fn from_package_metadata(package: &PackageMetadata<'_>) -> Result<Self, Error> {
+        match package
+            .metadata_table()
+            .get("guppy")
+            .and_then(|guppy| guppy.get("feature-powerset"))
+        {
+            Some(value) => Self::deserialize(value)
+                .map_err(|err| Error::PackageMetadataTableError(package.id().clone(), err)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Sets the maximum number of features in each combination, not counting always-included
+    /// features.
+    ///
+    /// The default is [`DEFAULT_DEPTH`](Self::DEFAULT_DEPTH). `None` means that there is no
+    /// limit: the number of combinations grows exponentially with the number of features, so this
+    /// should only be used for packages with few features.
+    pub fn set_depth(&mut self, depth: Option<usize>) -> &mut Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Adds features that are never enabled in any combination.
+    ///
+    /// Features may still be enabled by other features in the combination.
+    pub fn add_exclude(
+        &mut self,
+        features: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.exclude.extend(features.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds a group of features, at most one of which is enabled in any combination.
+    pub fn add_mutually_exclusive(
+        &mut self,
+        group: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.mutually_exclusive
+            .push(group.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Adds features that are enabled in every combination.
+    pub fn add_always_include(
+        &mut self,
+        features: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.always_include
+            .extend(features.into_iter().map(Into::into));
+        self
+    }
+}
+
+impl Default for FeaturePowersetOptions {
+    fn default() -> Self {
+        Self {
+            depth: Some(Self::DEFAULT_DEPTH),
+            exclude: vec![],
+            mutually_exclusive: vec![],
+            always_include: vec![],
+        }
+    }
+}
+
+/// Deserializes a depth, which is either an integer or the string `"unlimited"`.
+fn deserialize_depth<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Depth {
+        Limited(usize),
+        Named(String),
+    }
+
+    match Depth::deserialize(deserializer)? {
+        Depth::Limited(depth) => Ok(Some(depth)),
+        Depth::Named(name) if name == "unlimited" => Ok(None),
+        Depth::Named(name) => Err(D::Error::invalid_value(
+            Unexpected::Str(&name),
+            &"an integer or \"unlimited\"",
+        )),
+    }
+}
+
+/// Combinations of named features of a package, for feature-combination testing.
+///
+/// Each combination is resolved to a `FeatureSet` through the feature graph. Combinations that
+/// resolve to the same `FeatureSet` as an earlier combination are dropped, so that each distinct
+/// build is only tested once.
+///
+/// Constructed through `FeatureGraph::feature_powerset`.
+#[derive(Clone, Debug)]
+pub struct FeaturePowerset<'g> {
+    package: PackageMetadata<'g>,
+    combinations: Vec<FeatureCombination<'g>>,
+    duplicate_count: usize,
+}
+
+assert_covariant!(FeaturePowerset);
+
+impl<'g> FeaturePowerset<'g> {
+    pub(super) fn new(
+        feature_graph: FeatureGraph<'g>,
+        package_id: &PackageId,
+        opts: &FeaturePowersetOptions,
+    ) -> Result<Self, Error> {
+        let package = feature_graph.package_graph().metadata(package_id)?;
+        let package_id = package.id();
+
+        // Look up every feature name mentioned in the options, so that typos are caught early.
+        let lookup = |name: &str| -> Result<&'g str, Error> {
+            package
+                .named_features()
+                .find(|feature| *feature == name)
+                .ok_or_else(|| Error::UnknownFeatureId(package_id.clone(), name.to_owned()))
+        };
+        let always_include = opts
+            .always_include
+            .iter()
+            .map(|name| lookup(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let exclude = opts
+            .exclude
+            .iter()
+            .map(|name| lookup(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let groups = opts
+            .mutually_exclusive
+            .iter()
+            .map(|group| group.iter().map(|name| lookup(name)).collect())
+            .collect::<Result<Vec<Vec<_>>, _>>()?;
+
+        let candidates: Vec<_> = package
+            .named_features()
+            .filter(|feature| !always_include.contains(feature) && !exclude.contains(feature))
+            .collect();
+        let max_depth = opts
+            .depth
+            .map_or(candidates.len(), |depth| depth.min(candidates.len()));
+
+        let mut combinations = vec![];
+        let mut seen: AHashSet<Vec<FeatureLabel<'g>>> = AHashSet::new();
+        let mut duplicate_count = 0;
+        for depth in 0..=max_depth {
+            for_each_subset(candidates.len(), depth, |indexes| {
+                let mut features = always_include.clone();
+                features.extend(indexes.iter().map(|&idx| candidates[idx]));
+                let conflicts = groups.iter().any(|group| {
+                    group
+                        .iter()
+                        .filter(|feature| features.contains(feature))
+                        .count()
+                        > 1
+                });
+                if conflicts {
+                    return;
+                }
+
+                let feature_set = feature_graph
+                    .query_forward(
+                        features
+                            .iter()
+                            .map(|feature| FeatureId::named(package_id, feature))
+                            .chain(std::iter::once(FeatureId::base(package_id))),
+                    )
+                    .expect("feature IDs are valid")
+                    .resolve();
+                // Every feature reachable from the combination is reachable from the features of
+                // this package in the resolved set, so comparing those is equivalent to comparing
+                // entire feature sets.
+                let key = feature_set
+                    .features_for(package_id)
+                    .expect("valid package ID")
+                    .expect("base feature is always included")
+                    .into_labels();
+                if !seen.insert(key) {
+                    duplicate_count += 1;
+                    return;
+                }
+
+                combinations.push(FeatureCombination {
+                    features,
+                    feature_set,
+                });
+            });
+        }
+
+        Ok(Self {
+            package,
+            combinations,
+            duplicate_count,
+        })
+    }
+
+    /// Returns the package these combinations are for.
+    pub fn package(&self) -> PackageMetadata<'g> {
+        self.package
+    }
+
+    /// Returns the number of distinct combinations.
+    pub fn len(&self) -> usize {
+        self.combinations.len()
+    }
+
+    /// Returns true if there are no combinations.
+    ///
+    /// This can only happen if always-included features are mutually exclusive.
+    pub fn is_empty(&self) -> bool {
+        self.combinations.is_empty()
+    }
+
+    /// Iterates over the distinct combinations.
+    ///
+    /// Combinations are returned in order of increasing size. Combinations of the same size are
+    /// returned in the order features are declared in.
+    pub fn iter<'a>(&'a self) -> impl ExactSizeIterator<Item = &'a FeatureCombination<'g>> + 'a {
+        self.combinations.iter()
+    }
+
+    /// Returns the number of combinations that were dropped because they resolved to the same
+    /// `FeatureSet` as an earlier combination.
+    pub fn duplicate_count(&self) -> usize {
+        self.duplicate_count
+    }
+}
+
+/// A single combination of named features of a package.
+///
+/// Returned by `FeaturePowerset`.
+#[derive(Clone, Debug)]
+pub struct FeatureCombination<'g> {
+    features: Vec<&'g str>,
+    feature_set: FeatureSet<'g>,
+}
+
+assert_covariant!(FeatureCombination);
+
+impl<'g> FeatureCombination<'g> {
+    /// Returns the named features enabled in this combination, including always-included
+    /// features.
+    ///
+    /// Default features are only enabled if `default` is in this list. This list can be passed in
+    /// to `cargo build --no-default-features --features`.
+    pub fn features(&self) -> &[&'g str] {
+        &self.features
+    }
+
+    /// Returns the features enabled by this combination, across all packages.
+    pub fn feature_set(&self) -> &FeatureSet<'g> {
+        &self.feature_set
+    }
+}
+
+/// Calls `f` with every subset of `0..n` of size `k`, in lexicographic order.
+fn for_each_subset(n: usize, k: usize, mut f: impl FnMut(&[usize])) {
+    let mut indexes: Vec<usize> = (0..k).collect();
+    loop {
+        f(&indexes);
+        // Find the rightmost index that can be incremented.
+        let pos = match (0..k).rev().find(|&pos| indexes[pos] < n - k + pos) {
+            Some(pos) => pos,
+            None => return,
+        };
+        indexes[pos] += 1;
+        for next in pos + 1..k {
+            indexes[next] = indexes[next - 1] + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subsets() {
+        let mut subsets = vec![];
+        for_each_subset(4, 2, |indexes| subsets.push(indexes.to_vec()));
+        assert_eq!(
+            subsets,
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3]
+            ]
+        );
+
+        let mut count = 0;
+        for_each_subset(3, 0, |indexes| {
+            assert!(indexes.is_empty(), "empty subset");
+            count += 1;
+        });
+        assert_eq!(count, 1, "exactly one empty subset");
+    }
+
+    #[test]
+    fn parse_options() {
+        let value = serde_json::json!({
+            "depth": 2,
+            "exclude": ["nightly"],
+            "mutually-exclusive": [["rustls", "native-tls"]],
+            "always-include": ["std"],
+        });
+        let opts = FeaturePowersetOptions::deserialize(&value).expect("options parsed");
+        let mut expected = FeaturePowersetOptions::new();
+        expected
+            .set_depth(Some(2))
+            .add_exclude(["nightly"])
+            .add_mutually_exclusive(["rustls", "native-tls"])
+            .add_always_include(["std"]);
+        assert_eq!(opts, expected);
+
+        let opts = FeaturePowersetOptions::deserialize(&serde_json::json!({}))
+            .expect("empty options parsed");
+        assert_eq!(
+            opts.depth,
+            Some(FeaturePowersetOptions::DEFAULT_DEPTH),
+            "depth is bounded by default"
+        );
+        let opts =
+            FeaturePowersetOptions::deserialize(&serde_json::json!({ "depth": "unlimited" }))
+                .expect("unlimited depth parsed");
+        assert_eq!(opts.depth, None, "unlimited depth");
+        FeaturePowersetOptions::deserialize(&serde_json::json!({ "depth": "deep" }))
+            .expect_err("invalid depth rejected");
+
+        let value = serde_json::json!({ "unknown-key": true });
+        FeaturePowersetOptions::deserialize(&value).expect_err("unknown keys rejected");
+    }
+}
@@ -8,7 +8,11 @@ use crate::{
         BuildTarget, BuildTargetId, BuildTargetImpl, BuildTargetKind, Cycles, DependencyDirection,
         OwnedBuildTargetId, PackageIx, PackageQuery, PackageSet,
     },
-    petgraph_support::{scc::Sccs, topo::TopoWithCycles, IxBitSet},
+    petgraph_support::{
+        scc::{SccReachability, Sccs},
+        topo::TopoWithCycles,
+        IxBitSet,
+    },
     platform::{EnabledTernary, PlatformSpec, PlatformStatus, PlatformStatusImpl},
     CargoMetadata, DependencyKind, Error, JsonValue, MetadataCommand, PackageId,
 };
@@ -45,6 +49,8 @@ pub struct PackageGraph {
     pub(super) dep_graph: Graph<PackageId, PackageLinkImpl, Directed, PackageIx>,
     // The strongly connected components of the graph, computed on demand.
     pub(super) sccs: OnceCell<Sccs<PackageIx>>,
+    // The transitive closure over the SCCs of the graph, computed on demand.
+    pub(super) reachability: OnceCell<SccReachability>,
     // Feature graph, computed on demand.
     pub(super) feature_graph: OnceCell<FeatureGraphImpl>,
     // XXX Should this be in an Arc for quick cloning? Not clear how this would work with node
@@ -289,12 +295,23 @@ impl PackageGraph {
     ///
     /// This also returns true if `package_a` is the same as `package_b`.
     ///
-    /// For repeated queries, consider using `new_depends_cache` to speed up queries.
+    /// For repeated queries, consider using `new_depends_cache` or `reachability_index` to speed up
+    /// queries.
     pub fn depends_on(&self, package_a: &PackageId, package_b: &PackageId) -> Result<bool, Error> {
         let mut depends_cache = self.new_depends_cache();
         depends_cache.depends_on(package_a, package_b)
     }
 
+    /// Returns an index that answers `depends_on` queries in constant time.
+    ///
+    /// The index is computed the first time this method is called, and is cached within this
+    /// graph. Computing it is more expensive than a single `depends_on` query, and it stores one
+    /// bit per pair of strongly connected components, so it's worth using if many queries are
+    /// going to be made.
+    pub fn reachability_index(&self) -> ReachabilityIndex<'_> {
+        ReachabilityIndex::new(self)
+    }
+
     /// Returns true if `package_a` directly depends on `package_b`.
     ///
     /// In other words, this returns true if `package_b` is a direct dependency of `package_a`.
@@ -363,6 +380,7 @@ impl PackageGraph {
     #[doc(hidden)]
     pub fn invalidate_caches(&mut self) {
         self.sccs.take();
+        self.reachability.take();
         self.feature_graph.take();
     }
 
@@ -480,6 +498,83 @@ impl<'g> DependsCache<'g> {
     }
 }
 
+/// An index used to answer `depends_on` queries in constant time.
+///
+/// The index is a transitive closure over the strongly connected components of the graph. Like
+/// `PackageGraph::depends_on`, it considers all dependency edges, including dev-only ones.
+///
+/// Created with `PackageGraph::reachability_index()`.
+#[derive(Clone, Copy, Debug)]
+pub struct ReachabilityIndex<'g> {
+    package_graph: &'g PackageGraph,
+    sccs: &'g Sccs<PackageIx>,
+    inner: &'g SccReachability,
+}
+
+assert_covariant!(ReachabilityIndex);
+
+impl<'g> ReachabilityIndex<'g> {
+    fn new(package_graph: &'g PackageGraph) -> Self {
+        let sccs = package_graph.sccs();
+        let inner = package_graph
+            .reachability
+            .get_or_init(|| sccs.reachability(&package_graph.dep_graph));
+        Self {
+            package_graph,
+            sccs,
+            inner,
+        }
+    }
+
+    /// Returns true if `package_a` depends (directly or indirectly) on `package_b`.
+    ///
+    /// In other words, this returns true if `package_b` is a (possibly transitive) dependency of
+    /// `package_a`.
+    ///
+    /// This also returns true if `package_a` is the same as `package_b`.
+    pub fn depends_on(&self, package_a: &PackageId, package_b: &PackageId) -> Result<bool, Error> {
+        let a_ix = self.package_graph.package_ix(package_a)?;
+        let b_ix = self.package_graph.package_ix(package_b)?;
+        Ok(self.depends_on_ix(a_ix, b_ix))
+    }
+
+    /// Returns the set of packages that this package depends on, directly or indirectly.
+    ///
+    /// The set includes the package itself.
+    pub fn dependencies(&self, package_id: &PackageId) -> Result<PackageSet<'g>, Error> {
+        let package_ix = self.package_graph.package_ix(package_id)?;
+        let scc_idx = self.inner.scc_idx(package_ix);
+        Ok(self.scc_idxs_to_set(self.inner.reachable_from(scc_idx)))
+    }
+
+    /// Returns the set of packages that depend on this package, directly or indirectly.
+    ///
+    /// The set includes the package itself.
+    pub fn dependents(&self, package_id: &PackageId) -> Result<PackageSet<'g>, Error> {
+        let package_ix = self.package_graph.package_ix(package_id)?;
+        let scc_idx = self.inner.scc_idx(package_ix);
+        Ok(self.scc_idxs_to_set(self.inner.reaching(scc_idx)))
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn depends_on_ix(&self, a_ix: NodeIndex<PackageIx>, b_ix: NodeIndex<PackageIx>) -> bool {
+        self.inner.can_reach(a_ix, b_ix)
+    }
+
+    fn scc_idxs_to_set(&self, scc_idxs: impl Iterator<Item = usize>) -> PackageSet<'g> {
+        let mut included = FixedBitSet::with_capacity(self.package_graph.package_count());
+        for scc_idx in scc_idxs {
+            for package_ix in self.sccs.scc_nodes(scc_idx) {
+                included.insert(package_ix.index());
+            }
+        }
+        PackageSet::from_included(self.package_graph, included)
+    }
+}
+
 /// Information about a workspace, parsed from metadata returned by `cargo metadata`.
 ///
 /// For more about workspaces, see
@@ -0,0 +1,211 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Centrality and risk metrics over package sets.
+
+use crate::{
+    graph::{DependencyDirection, PackageGraph, PackageIx, PackageMetadata, PackageSet},
+    PackageId,
+};
+use ahash::AHashMap;
+use fixedbitset::FixedBitSet;
+use petgraph::prelude::*;
+use std::collections::VecDeque;
+
+/// Metrics about how central each package is to a `PackageSet`.
+///
+/// These metrics are meant to help prioritize which third-party packages to review or vendor: a
+/// package that many workspace members depend on, or that many dependency paths go through, is
+/// a more significant part of the supply chain than one that is only pulled in by a single
+/// member.
+///
+/// All metrics only consider packages and links within the set.
+///
+/// Constructed through `PackageSet::metrics`.
+#[derive(Clone, Debug)]
+pub struct PackageMetrics<'g> {
+    metrics: Vec<PackageMetric<'g>>,
+    index: AHashMap<&'g PackageId, usize>,
+}
+
+assert_covariant!(PackageMetrics);
+
+impl<'g> PackageMetrics<'g> {
+    pub(super) fn new(graph: &'g PackageGraph, package_set: &PackageSet<'g>) -> Self {
+        let dep_graph = graph.dep_graph();
+        let packages: Vec<_> = package_set.packages(DependencyDirection::Forward).collect();
+
+        // The position of each package in topological order, for packages within the set.
+        let mut positions = vec![None; dep_graph.node_count()];
+        for (position, package) in packages.iter().enumerate() {
+            positions[package.package_ix().index()] = Some(position);
+        }
+        let position = |ix: NodeIndex<PackageIx>| positions[ix.index()];
+        let out_neighbors = |idx: usize| {
+            dep_graph
+                .neighbors_directed(packages[idx].package_ix(), Outgoing)
+                .filter_map(position)
+        };
+
+        let members: Vec<usize> = packages
+            .iter()
+            .enumerate()
+            .filter(|(_, package)| package.in_workspace())
+            .map(|(idx, _)| idx)
+            .collect();
+
+        // Fan-in: the number of packages in the set that directly depend on each package.
+        let mut fan_in = vec![0; packages.len()];
+        for idx in 0..packages.len() {
+            for neighbor_idx in out_neighbors(idx) {
+                fan_in[neighbor_idx] += 1;
+            }
+        }
+
+        // Depth: a breadth-first search starting from all workspace members at once.
+        let mut depth = vec![None; packages.len()];
+        let mut queue: VecDeque<_> = members.iter().map(|&idx| (idx, 0)).collect();
+        for &idx in &members {
+            depth[idx] = Some(0);
+        }
+        while let Some((idx, d)) = queue.pop_front() {
+            for neighbor_idx in out_neighbors(idx) {
+                if depth[neighbor_idx].is_none() {
+                    depth[neighbor_idx] = Some(d + 1);
+                    queue.push_back((neighbor_idx, d + 1));
+                }
+            }
+        }
+
+        // Workspace dependents: a separate search from each workspace member.
+        let mut workspace_dependents = vec![0; packages.len()];
+        let mut visited = FixedBitSet::with_capacity(packages.len());
+        let mut stack = vec![];
+        for &member_idx in &members {
+            visited.clear();
+            visited.insert(member_idx);
+            stack.push(member_idx);
+            while let Some(idx) = stack.pop() {
+                for neighbor_idx in out_neighbors(idx) {
+                    if !visited.put(neighbor_idx) {
+                        workspace_dependents[neighbor_idx] += 1;
+                        stack.push(neighbor_idx);
+                    }
+                }
+            }
+        }
+
+        // Betweenness: the number of paths from workspace members that reach each package,
+        // multiplied by the number of paths from each package that end at a third-party package.
+        // Links that go backwards in topological order (only possible within cycles) are ignored
+        // so that the number of paths is finite.
+        let mut paths_in = vec![0_u64; packages.len()];
+        for &member_idx in &members {
+            paths_in[member_idx] = 1;
+        }
+        for idx in 0..packages.len() {
+            for neighbor_idx in out_neighbors(idx).filter(|&neighbor_idx| neighbor_idx > idx) {
+                paths_in[neighbor_idx] = paths_in[neighbor_idx].saturating_add(paths_in[idx]);
+            }
+        }
+        let mut paths_out = vec![0_u64; packages.len()];
+        for idx in (0..packages.len()).rev() {
+            let mut count = u64::from(!packages[idx].in_workspace());
+            for neighbor_idx in out_neighbors(idx).filter(|&neighbor_idx| neighbor_idx > idx) {
+                count = count.saturating_add(paths_out[neighbor_idx]);
+            }
+            paths_out[idx] = count;
+        }
+
+        let metrics: Vec<_> = packages
+            .iter()
+            .enumerate()
+            .map(|(idx, package)| PackageMetric {
+                package: *package,
+                workspace_dependents: workspace_dependents[idx],
+                betweenness: paths_in[idx].saturating_mul(paths_out[idx]),
+                depth: depth[idx],
+                fan_in: fan_in[idx],
+            })
+            .collect();
+        let index = metrics
+            .iter()
+            .enumerate()
+            .map(|(idx, metric)| (metric.package.id(), idx))
+            .collect();
+
+        Self { metrics, index }
+    }
+
+    /// Returns the number of packages in this set.
+    pub fn len(&self) -> usize {
+        self.metrics.len()
+    }
+
+    /// Returns true if there are no packages in this set.
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+
+    /// Returns the metrics for this package, or `None` if the package isn't in the set.
+    pub fn get(&self, package_id: &PackageId) -> Option<&PackageMetric<'g>> {
+        self.index.get(package_id).map(|&idx| &self.metrics[idx])
+    }
+
+    /// Iterates over the metrics for each package, in forward topological order.
+    pub fn iter<'a>(&'a self) -> impl ExactSizeIterator<Item = &'a PackageMetric<'g>> + 'a {
+        self.metrics.iter()
+    }
+}
+
+/// Centrality and risk metrics for a single package.
+///
+/// Returned by `PackageMetrics`.
+#[derive(Clone, Copy, Debug)]
+pub struct PackageMetric<'g> {
+    package: PackageMetadata<'g>,
+    workspace_dependents: usize,
+    betweenness: u64,
+    depth: Option<usize>,
+    fan_in: usize,
+}
+
+assert_covariant!(PackageMetric);
+
+impl<'g> PackageMetric<'g> {
+    /// Returns the package these metrics are for.
+    pub fn package(&self) -> PackageMetadata<'g> {
+        self.package
+    }
+
+    /// Returns the number of workspace members that depend on this package, directly or
+    /// indirectly.
+    ///
+    /// A workspace member isn't counted as its own dependent.
+    pub fn workspace_dependents(&self) -> usize {
+        self.workspace_dependents
+    }
+
+    /// Returns the number of dependency paths from a workspace member to a third-party package
+    /// that go through or end at this package.
+    ///
+    /// Links that form part of a cycle are only followed in topological order. This number can
+    /// grow very quickly in large graphs, and saturates at `u64::MAX`.
+    pub fn betweenness(&self) -> u64 {
+        self.betweenness
+    }
+
+    /// Returns the length of the shortest dependency path from a workspace member to this
+    /// package.
+    ///
+    /// This is 0 for workspace members, and `None` if the package isn't reachable from any
+    /// workspace member within the set.
+    pub fn depth(&self) -> Option<usize> {
+        self.depth
+    }
+
+    /// Returns the number of packages in the set that directly depend on this package.
+    pub fn fan_in(&self) -> usize {
+        self.fan_in
+    }
+}
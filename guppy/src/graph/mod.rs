@@ -16,6 +16,7 @@ pub mod cargo;
 mod cycles;
 pub mod feature;
 mod graph_impl;
+mod metrics;
 #[cfg(feature = "proptest1")]
 mod proptest_helpers;
 mod query;
@@ -24,11 +25,13 @@ mod resolve;
 mod resolve_core;
 #[cfg(feature = "summaries")]
 pub mod summaries;
+mod version_reqs;
 
 pub use crate::petgraph_support::dot::DotWrite;
 pub use build_targets::*;
 pub use cycles::*;
 pub use graph_impl::*;
+pub use metrics::*;
 use once_cell::sync::Lazy;
 use petgraph::graph::IndexType;
 #[cfg(feature = "proptest1")]
@@ -36,6 +39,7 @@ pub use proptest_helpers::*;
 pub use query::*;
 pub use resolve::*;
 use semver::{Version, VersionReq};
+pub use version_reqs::*;
 
 /// The direction in which to follow dependencies.
 ///
@@ -7,7 +7,7 @@ use crate::{
         feature::{FeatureFilter, FeatureSet},
         resolve_core::{ResolveCore, Topo},
         DependencyDirection, PackageGraph, PackageIx, PackageLink, PackageLinkImpl,
-        PackageMetadata, PackageQuery,
+        PackageMetadata, PackageMetrics, PackageQuery, VersionReqLints,
     },
     petgraph_support::{
         dot::{DotFmt, DotVisitor, DotWrite},
@@ -528,6 +528,28 @@ impl<'g> PackageSet<'g> {
         DotFmt::new(node_filtered, VisitorWrap::new(self.graph.0, visitor))
     }
 
+    /// Computes centrality and risk metrics for each package in this set.
+    ///
+    /// For more, see the documentation for `PackageMetrics`.
+    pub fn metrics(&self) -> PackageMetrics<'g> {
+        PackageMetrics::new(self.graph.0, self)
+    }
+
+    /// Checks the version requirements that workspace packages in this set place on their
+    /// dependencies.
+    ///
+    /// `history` is a list of older package graphs for the same workspace, for example built from
+    /// earlier versions of `Cargo.lock`. It is used to find requirements whose minimum version is
+    /// too low, and may be empty.
+    ///
+    /// For more, see the documentation for `VersionReqLints`.
+    pub fn version_req_lints<'h>(
+        &self,
+        history: impl IntoIterator<Item = &'h PackageGraph>,
+    ) -> VersionReqLints<'g> {
+        VersionReqLints::new(self, history)
+    }
+
     // ---
     // Helper methods
     // ---
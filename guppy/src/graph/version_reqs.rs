@@ -0,0 +1,276 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Lints for version requirements in workspace manifests.
+
+use crate::graph::{DependencyDirection, NamedFeatureDep, PackageGraph, PackageLink, PackageSet};
+use ahash::AHashMap;
+use semver::{Op, Version};
+use std::collections::BTreeSet;
+
+/// Problems with the version requirements that workspace packages place on their dependencies.
+///
+/// The following problems are detected:
+/// * wildcard (`*`) requirements for dependencies from registries or Git repositories
+/// * exact (`=`) requirements, which make it hard for Cargo to unify versions
+/// * requirements whose minimum version is too low, because older versions that match the
+///   requirement don't have a feature the package uses (either in the dependency declaration, or
+///   through a `dep/feat` entry in its `[features]` section)
+/// * different requirements for the same dependency across workspace packages
+///
+/// Detecting a minimum version that's too low requires knowing which versions of a dependency
+/// have which features. The graph being linted only has the version that's currently resolved,
+/// so this check uses a list of older package graphs, typically built from earlier versions of
+/// `Cargo.lock`. Versions that don't appear in any of these graphs aren't considered.
+///
+/// Constructed through `PackageSet::version_req_lints`.
+#[derive(Clone, Debug)]
+pub struct VersionReqLints<'g> {
+    lints: Vec<VersionReqLint<'g>>,
+}
+
+assert_covariant!(VersionReqLints);
+
+impl<'g> VersionReqLints<'g> {
+    pub(super) fn new<'h>(
+        packages: &PackageSet<'g>,
+        history: impl IntoIterator<Item = &'h PackageGraph>,
+    ) -> Self {
+        let history = VersionHistory::new(history);
+        let mut lints = vec![];
+
+        let links: Vec<_> = packages
+            .packages(DependencyDirection::Forward)
+            .filter(|package| package.in_workspace())
+            .flat_map(|package| package.direct_links())
+            .collect();
+
+        for &link in &links {
+            let to = link.to();
+            let version_req = link.version_req();
+
+            if version_req.comparators.is_empty() {
+                // Path dependencies without a version are treated as wildcards by Cargo, and are
+                // common within workspaces.
+                if to.source().is_external() {
+                    lints.push(VersionReqLint {
+                        link,
+                        kind: VersionReqLintKind::Wildcard,
+                    });
+                }
+                continue;
+            }
+
+            if version_req
+                .comparators
+                .iter()
+                .any(|comparator| comparator.op == Op::Exact)
+            {
+                lints.push(VersionReqLint {
+                    link,
+                    kind: VersionReqLintKind::ExactPin,
+                });
+            }
+
+            if to.source().is_external() {
+                if let Some(kind) = history.check_minimum(link) {
+                    lints.push(VersionReqLint { link, kind });
+                }
+            }
+        }
+
+        // Group links by the package they resolve to, to find inconsistent requirements.
+        let mut by_dependency: AHashMap<_, Vec<PackageLink<'g>>> = AHashMap::new();
+        // Dependencies without a version requirement, such as path dependencies, are skipped.
+        let links: Vec<_> = links
+            .into_iter()
+            .filter(|link| !link.version_req().comparators.is_empty())
+            .collect();
+        for &link in &links {
+            by_dependency.entry(link.to().id()).or_default().push(link);
+        }
+        for &link in &links {
+            let others: Vec<_> = by_dependency[link.to().id()]
+                .iter()
+                .copied()
+                .filter(|other| other.version_req() != link.version_req())
+                .collect();
+            if !others.is_empty() {
+                lints.push(VersionReqLint {
+                    link,
+                    kind: VersionReqLintKind::Inconsistent { others },
+                });
+            }
+        }
+
+        Self { lints }
+    }
+
+    /// Returns the number of lints.
+    pub fn len(&self) -> usize {
+        self.lints.len()
+    }
+
+    /// Returns true if no problems were found.
+    pub fn is_empty(&self) -> bool {
+        self.lints.is_empty()
+    }
+
+    /// Iterates over the lints.
+    ///
+    /// Lints for inconsistent requirements are returned after all other lints.
+    pub fn iter<'a>(&'a self) -> impl ExactSizeIterator<Item = &'a VersionReqLint<'g>> + 'a {
+        self.lints.iter()
+    }
+}
+
+/// A problem with a single version requirement.
+///
+/// Returned by `VersionReqLints`.
+#[derive(Clone, Debug)]
+pub struct VersionReqLint<'g> {
+    link: PackageLink<'g>,
+    kind: VersionReqLintKind<'g>,
+}
+
+assert_covariant!(VersionReqLint);
+
+impl<'g> VersionReqLint<'g> {
+    /// Returns the link whose version requirement has a problem.
+    pub fn link(&self) -> PackageLink<'g> {
+        self.link
+    }
+
+    /// Returns the kind of problem.
+    pub fn kind(&self) -> &VersionReqLintKind<'g> {
+        &self.kind
+    }
+}
+
+/// The kind of problem found with a version requirement.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum VersionReqLintKind<'g> {
+    /// The requirement is `*`, so any version of the dependency is accepted.
+    Wildcard,
+
+    /// The requirement contains an exact `=` comparator.
+    ExactPin,
+
+    /// The requirement accepts an older version of the dependency that doesn't have a feature
+    /// this package uses.
+    MinimumTooLow {
+        /// The newest known version accepted by the requirement that is missing a used feature.
+        version: Version,
+
+        /// The features used by this package that `version` doesn't have.
+        missing_features: Vec<&'g str>,
+
+        /// The oldest known version newer than `version` that has all the used features.
+        suggested_minimum: Version,
+    },
+
+    /// Other workspace packages depend on the same package with different requirements.
+    Inconsistent {
+        /// Links from other workspace packages to the same dependency, with requirements different
+        /// from this one.
+        others: Vec<PackageLink<'g>>,
+    },
+}
+
+// ---
+// Helper structures
+// ---
+
+/// The named features of each known version of external packages, keyed by name and source.
+#[derive(Debug)]
+struct VersionHistory {
+    versions: AHashMap<(String, String), Vec<KnownVersion>>,
+}
+
+/// A version of a package, along with its named features.
+type KnownVersion = (Version, BTreeSet<String>);
+
+impl VersionHistory {
+    fn new<'h>(history: impl IntoIterator<Item = &'h PackageGraph>) -> Self {
+        let mut versions: AHashMap<_, Vec<KnownVersion>> = AHashMap::new();
+        for graph in history {
+            for package in graph
+                .packages()
+                .filter(|package| package.source().is_external())
+            {
+                let entry = versions
+                    .entry((package.name().to_owned(), package.source().to_string()))
+                    .or_default();
+                if entry
+                    .iter()
+                    .all(|(version, _)| version != package.version())
+                {
+                    let features = package.named_features().map(|f| f.to_owned()).collect();
+                    entry.push((package.version().clone(), features));
+                }
+            }
+        }
+        for entry in versions.values_mut() {
+            entry.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+        Self { versions }
+    }
+
+    fn check_minimum<'g>(&self, link: PackageLink<'g>) -> Option<VersionReqLintKind<'g>> {
+        let to = link.to();
+        let known = self
+            .versions
+            .get(&(to.name().to_owned(), to.source().to_string()))?;
+        let version_req = link.version_req();
+
+        // Features are used either directly through the dependency declaration, or through
+        // `dep/feat` or `dep?/feat` entries in the `[features]` section.
+        let feature_deps = link
+            .from()
+            .named_features_full()
+            .flat_map(|(_, _, feature_deps)| feature_deps)
+            .filter_map(|feature_dep| match feature_dep {
+                NamedFeatureDep::DependencyNamedFeature {
+                    dep_name, feature, ..
+                } if dep_name.as_ref() == link.dep_name() => Some(feature.as_ref()),
+                _ => None,
+            });
+        let used: BTreeSet<&'g str> = [link.normal(), link.build(), link.dev()]
+            .iter()
+            .filter(|req| req.is_present())
+            .flat_map(|req| req.features())
+            .chain(feature_deps)
+            .collect();
+        if used.is_empty() {
+            return None;
+        }
+
+        // Find the newest version accepted by the requirement that's missing a used feature.
+        let (idx, (version, features)) =
+            known
+                .iter()
+                .enumerate()
+                .rev()
+                .find(|(_, (version, features))| {
+                    version < to.version()
+                        && version_req.matches(version)
+                        && used.iter().any(|feature| !features.contains(*feature))
+                })?;
+        let missing_features = used
+            .iter()
+            .copied()
+            .filter(|feature| !features.contains(*feature))
+            .collect();
+        let suggested_minimum = known[idx + 1..]
+            .iter()
+            .find(|(_, features)| used.iter().all(|feature| features.contains(*feature)))
+            .map_or_else(|| to.version().clone(), |(version, _)| version.clone());
+
+        Some(VersionReqLintKind::MinimumTooLow {
+            version: version.clone(),
+            missing_features,
+            suggested_minimum,
+        })
+    }
+}
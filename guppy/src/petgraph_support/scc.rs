@@ -124,6 +124,88 @@ impl<Ix: IndexType> Sccs<Ix> {
             direction,
         }
     }
+
+    /// Computes the transitive closure of the condensation of this graph.
+    ///
+    /// This performs one bitset union per edge between SCCs, and stores one bit per pair of SCCs.
+    pub fn reachability<G>(&self, graph: G) -> SccReachability
+    where
+        G: IntoNeighborsDirected<NodeId = NodeIndex<Ix>>,
+    {
+        let node_count = self.sccs.data().len();
+        let scc_count = self.sccs.len();
+        let mut node_to_scc = vec![0; node_count];
+        for (idx, scc) in self.sccs.iter().enumerate() {
+            for ix in scc {
+                node_to_scc[ix.index()] = idx;
+            }
+        }
+
+        let mut reachable = vec![FixedBitSet::new(); scc_count];
+        // SCCs are stored in forward topological order, so iterating in reverse means that every
+        // SCC reachable from this one has already been computed.
+        for (idx, scc) in self.sccs.iter().enumerate().rev() {
+            let mut closure = FixedBitSet::with_capacity(scc_count);
+            closure.insert(idx);
+            for ix in scc {
+                for neighbor_ix in graph.neighbors_directed(*ix, Outgoing) {
+                    let neighbor_idx = node_to_scc[neighbor_ix.index()];
+                    if neighbor_idx != idx && !closure.contains(neighbor_idx) {
+                        debug_assert!(neighbor_idx > idx, "SCCs are in topological order");
+                        closure.union_with(&reachable[neighbor_idx]);
+                    }
+                }
+            }
+            reachable[idx] = closure;
+        }
+
+        SccReachability {
+            node_to_scc,
+            reachable,
+        }
+    }
+
+    /// Returns the nodes in the SCC with this index.
+    pub fn scc_nodes(&self, scc_idx: usize) -> &[NodeIndex<Ix>] {
+        &self.sccs[scc_idx]
+    }
+}
+
+/// The transitive closure of the condensation of a graph.
+#[derive(Clone, Debug)]
+pub(crate) struct SccReachability {
+    // The index of the SCC each node is in.
+    node_to_scc: Vec<usize>,
+    // For each SCC, the SCCs reachable from it (including itself).
+    reachable: Vec<FixedBitSet>,
+}
+
+impl SccReachability {
+    /// Returns the index of the SCC this node is in.
+    pub fn scc_idx<Ix: IndexType>(&self, ix: NodeIndex<Ix>) -> usize {
+        self.node_to_scc[ix.index()]
+    }
+
+    /// Returns true if there's a path from `a` to `b`.
+    ///
+    /// A node can always reach itself.
+    pub fn can_reach<Ix: IndexType>(&self, a: NodeIndex<Ix>, b: NodeIndex<Ix>) -> bool {
+        self.reachable[self.scc_idx(a)].contains(self.scc_idx(b))
+    }
+
+    /// Returns the indexes of the SCCs reachable from this SCC.
+    pub fn reachable_from(&self, scc_idx: usize) -> impl Iterator<Item = usize> + '_ {
+        self.reachable[scc_idx].ones()
+    }
+
+    /// Returns the indexes of the SCCs that can reach this SCC.
+    pub fn reaching(&self, scc_idx: usize) -> impl Iterator<Item = usize> + '_ {
+        self.reachable
+            .iter()
+            .enumerate()
+            .filter(move |(_, closure)| closure.contains(scc_idx))
+            .map(|(idx, _)| idx)
+    }
 }
 
 /// An iterator over the nodes of strongly connected components.
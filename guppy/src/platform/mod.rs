@@ -67,4 +67,4 @@ pub use platform_spec::*;
 pub use summaries::*;
 // These are inlined -- generally, treat target_spec as a private dependency so expose these types
 // as part of guppy's API.
-pub use target_spec::{Platform, TargetFeatures, Triple};
+pub use target_spec::{EvalTrace, Platform, PredicateEval, TargetFeatures, Triple};
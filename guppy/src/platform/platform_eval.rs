@@ -1,7 +1,7 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::platform::{Platform, PlatformSpec};
+use crate::platform::{EvalTrace, Platform, PlatformSpec};
 use std::ops::{BitAnd, BitOr};
 use target_spec::TargetSpec;
 
@@ -74,6 +74,17 @@ impl<'g> PlatformStatus<'g> {
             (PlatformStatus::PlatformDependent { eval }, PlatformSpec::Platform(platform)) => {
                 eval.eval(platform)
             }
+            (PlatformStatus::PlatformDependent { eval }, PlatformSpec::Platforms(platforms)) => {
+                let mut res = EnabledTernary::Disabled;
+                for platform in platforms {
+                    res = res | eval.eval(platform);
+                    // Short-circuit evaluation if possible.
+                    if res == EnabledTernary::Enabled {
+                        break;
+                    }
+                }
+                res
+            }
             (PlatformStatus::PlatformDependent { .. }, PlatformSpec::Always) => {
                 EnabledTernary::Disabled
             }
@@ -160,6 +171,10 @@ pub struct PlatformEval<'g> {
 assert_covariant!(PlatformEval);
 
 impl<'g> PlatformEval<'g> {
+    pub(crate) fn new(specs: &'g [TargetSpec]) -> Self {
+        Self { specs }
+    }
+
     /// Runs this evaluator against the given platform.
     pub fn eval(&self, platform: &Platform) -> EnabledTernary {
         let mut res = EnabledTernary::Disabled;
@@ -173,6 +188,21 @@ impl<'g> PlatformEval<'g> {
         }
         res
     }
+
+    /// Runs this evaluator against the given platform, and returns a trace for each target
+    /// specification.
+    ///
+    /// This can be used to explain why [`Self::eval`] returned [`EnabledTernary::Unknown`]: the
+    /// result is unknown if no specification evaluated to true and at least one of them was
+    /// unknown.
+    pub fn eval_explain<'a>(&self, platform: &'a Platform) -> impl Iterator<Item = EvalTrace> + 'a
+    where
+        'g: 'a,
+    {
+        self.specs
+            .iter()
+            .map(move |spec| spec.eval_explain(platform))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -191,6 +221,14 @@ impl PlatformStatusImpl {
         }
     }
 
+    /// Returns the target specs this status is made up of. This is empty for `Always`.
+    pub(crate) fn specs(&self) -> &[TargetSpec] {
+        match self {
+            PlatformStatusImpl::Always => &[],
+            PlatformStatusImpl::Specs(specs) => specs,
+        }
+    }
+
     pub(crate) fn extend(&mut self, other: &PlatformStatusImpl) {
         // &mut *self is a reborrow to allow *self to work below.
         match (&mut *self, other) {
@@ -35,6 +35,15 @@ pub enum PlatformSpec {
     /// [`EnabledTernary::Unknown`] if a platform is not enabled.
     Platform(Arc<Platform>),
 
+    /// A set of platforms, evaluated as their union.
+    ///
+    /// Dependency queries performed against this variant will return [`EnabledTernary::Enabled`] if
+    /// a dependency is enabled on any platform in the set, and [`EnabledTernary::Disabled`] if it
+    /// is disabled on all of them. Otherwise, they return [`EnabledTernary::Unknown`].
+    ///
+    /// Platform-dependent dependencies are never enabled on an empty set.
+    Platforms(Vec<Arc<Platform>>),
+
     /// The union of all platforms.
     ///
     /// Dependency queries performed against this variant will return [`EnabledTernary::Enabled`] if
@@ -54,6 +63,23 @@ impl PlatformSpec {
     pub fn current() -> Result<Self, TargetSpecError> {
         Ok(PlatformSpec::Platform(Arc::new(Platform::current()?)))
     }
+
+    /// Returns a `PlatformSpec` corresponding to the union of the given platforms.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use guppy::platform::{Platform, PlatformSpec, TargetFeatures};
+    ///
+    /// let spec = PlatformSpec::platforms([
+    ///     Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap(),
+    ///     Platform::new("aarch64-apple-darwin", TargetFeatures::Unknown).unwrap(),
+    /// ]);
+    /// assert!(matches!(spec, PlatformSpec::Platforms(platforms) if platforms.len() == 2));
+    /// ```
+    pub fn platforms<T: Into<Arc<Platform>>>(platforms: impl IntoIterator<Item = T>) -> Self {
+        PlatformSpec::Platforms(platforms.into_iter().map(Into::into).collect())
+    }
 }
 
 impl<T: Into<Arc<Platform>>> From<T> for PlatformSpec {
@@ -17,7 +17,13 @@ impl PlatformSpec {
         prop_oneof![
             1 => Just(PlatformSpec::Any),
             1 => Just(PlatformSpec::Always),
-            2 => platform.prop_map(PlatformSpec::from),
+            3 => prop::collection::vec(platform, 1..4).prop_map(|mut platforms| {
+                if platforms.len() == 1 {
+                    PlatformSpec::from(platforms.pop().expect("one platform"))
+                } else {
+                    PlatformSpec::platforms(platforms)
+                }
+            }),
         ]
     }
 }
@@ -9,6 +9,7 @@ pub use target_spec::summaries::{PlatformSummary, TargetFeaturesSummary};
 ///
 /// Requires the `summaries` feature to be enabled.
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
+#[non_exhaustive]
 pub enum PlatformSpecSummary {
     /// The intersection of all platforms.
     ///
@@ -76,6 +77,30 @@ pub enum PlatformSpecSummary {
     /// ```
     Platform(PlatformSummary),
 
+    /// A set of platforms.
+    ///
+    /// This is converted to and from [`PlatformSpec::Platforms`], and is serialized as a map with
+    /// a `platforms` key.
+    ///
+    /// # Examples
+    ///
+    /// Deserialize a set of target triples.
+    ///
+    /// ```
+    /// # use guppy::platform::{PlatformSummary, PlatformSpecSummary};
+    /// let spec: PlatformSpecSummary = toml::from_str(r#"
+    /// platforms = ["x86_64-unknown-linux-gnu", "aarch64-apple-darwin"]
+    /// "#).unwrap();
+    /// assert_eq!(
+    ///     spec,
+    ///     PlatformSpecSummary::Platforms(vec![
+    ///         PlatformSummary::new("x86_64-unknown-linux-gnu"),
+    ///         PlatformSummary::new("aarch64-apple-darwin"),
+    ///     ]),
+    /// );
+    /// ```
+    Platforms(Vec<PlatformSummary>),
+
     /// The union of all platforms.
     ///
     /// This is converted to and from [`PlatformSpec::Any`], and is serialized as the string
@@ -113,6 +138,12 @@ impl PlatformSpecSummary {
             PlatformSpec::Platform(platform) => {
                 PlatformSpecSummary::Platform(platform.to_summary())
             }
+            PlatformSpec::Platforms(platforms) => PlatformSpecSummary::Platforms(
+                platforms
+                    .iter()
+                    .map(|platform| platform.to_summary())
+                    .collect(),
+            ),
             PlatformSpec::Any => PlatformSpecSummary::Any,
         }
     }
@@ -126,6 +157,13 @@ impl PlatformSpecSummary {
             PlatformSpecSummary::Platform(platform) => {
                 Ok(PlatformSpec::Platform(Arc::new(platform.to_platform()?)))
             }
+            PlatformSpecSummary::Platforms(platforms) => {
+                let platforms = platforms
+                    .iter()
+                    .map(|platform| platform.to_platform().map(Arc::new))
+                    .collect::<Result<_, _>>()?;
+                Ok(PlatformSpec::Platforms(platforms))
+            }
             PlatformSpecSummary::Any => Ok(PlatformSpec::Any),
         }
     }
@@ -139,8 +177,6 @@ impl PlatformSpecSummary {
 mod serde_impl {
     use super::*;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
-    use std::collections::BTreeSet;
-    use target_spec::summaries::TargetFeaturesSummary;
 
     impl Serialize for PlatformSpecSummary {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -151,6 +187,9 @@ mod serde_impl {
                 PlatformSpecSummary::Always => Spec { spec: "always" }.serialize(serializer),
                 PlatformSpecSummary::Any => Spec { spec: "any" }.serialize(serializer),
                 PlatformSpecSummary::Platform(platform) => platform.serialize(serializer),
+                PlatformSpecSummary::Platforms(platforms) => {
+                    Platforms { platforms }.serialize(serializer)
+                }
             }
         }
     }
@@ -162,6 +201,11 @@ mod serde_impl {
         spec: &'static str,
     }
 
+    #[derive(Serialize)]
+    struct Platforms<'a> {
+        platforms: &'a [PlatformSummary],
+    }
+
     impl<'de> Deserialize<'de> for PlatformSpecSummary {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
@@ -179,17 +223,11 @@ mod serde_impl {
                         }
                     }
                 }
-                PlatformSpecSummaryDeserialize::PlatformFull {
-                    triple,
-                    custom_json,
-                    target_features,
-                    flags,
-                } => {
-                    let mut summary = PlatformSummary::new(triple);
-                    summary.custom_json = custom_json;
-                    summary.target_features = target_features;
-                    summary.flags = flags;
-                    Ok(PlatformSpecSummary::Platform(summary))
+                PlatformSpecSummaryDeserialize::Platforms { platforms } => {
+                    Ok(PlatformSpecSummary::Platforms(platforms))
+                }
+                PlatformSpecSummaryDeserialize::PlatformFull(platform) => {
+                    Ok(PlatformSpecSummary::Platform(platform))
                 }
             }
         }
@@ -199,29 +237,102 @@ mod serde_impl {
     #[serde(untagged)]
     enum PlatformSpecSummaryDeserialize {
         String(String),
-        Spec {
-            spec: String,
-        },
-        #[serde(rename_all = "kebab-case")]
-        PlatformFull {
-            // TODO: there doesn't appear to be any way to defer to the PlatformSummary
-            // deserializer, so copy-paste its logic here. Find a better way?
-            triple: String,
-            #[serde(skip_serializing_if = "Option::is_none", default)]
-            custom_json: Option<String>,
-            #[serde(default)]
-            target_features: TargetFeaturesSummary,
-            #[serde(skip_serializing_if = "BTreeSet::is_empty", default)]
-            flags: BTreeSet<String>,
-        },
+        Spec { spec: String },
+        Platforms { platforms: Vec<PlatformSummary> },
+        PlatformFull(PlatformSummary),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::{Platform, TargetFeatures};
+    use target_spec::{summaries::TargetMetadataSummary, TargetSpec};
+
+    fn assert_roundtrip(summary: PlatformSummary) -> PlatformSpec {
+        let spec_summary = PlatformSpecSummary::Platform(summary);
+        let serialized = toml::ser::to_string(&spec_summary).expect("serialization succeeded");
+        let deserialized: PlatformSpecSummary =
+            toml::from_str(&serialized).expect("deserialization succeeded");
+        assert_eq!(
+            spec_summary, deserialized,
+            "summary and deserialized should match"
+        );
+        deserialized
+            .to_platform_spec()
+            .expect("conversion to PlatformSpec succeeded")
+    }
+
+    #[test]
+    fn platform_rustc_cfg_roundtrip() {
+        let summary = PlatformSummary::new("armv7-unknown-myos").with_rustc_cfg(
+            "target_arch=\"arm\"\ntarget_endian=\"little\"\ntarget_os=\"myos\"\n\
+             target_pointer_width=\"32\"\n",
+        );
+        let platform_spec = assert_roundtrip(summary);
+        match platform_spec {
+            PlatformSpec::Platform(platform) => {
+                assert!(platform.is_rustc_cfg(), "platform created from rustc cfg");
+                assert_eq!(platform.triple_str(), "armv7-unknown-myos");
+            }
+            other => panic!("expected a single platform, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn platform_key_values_roundtrip() {
+        let summary = PlatformSummary::new("x86_64-unknown-linux-gnu").with_added_key_values([
+            ("foo", "bar"),
+            ("foo", "baz"),
+            ("qux", "quux"),
+        ]);
+        let platform_spec = assert_roundtrip(summary);
+        match platform_spec {
+            PlatformSpec::Platform(platform) => {
+                assert!(platform.has_key_value("foo", "bar"), "foo = bar is set");
+                assert!(platform.has_key_value("foo", "baz"), "foo = baz is set");
+                assert!(platform.has_key_value("qux", "quux"), "qux = quux is set");
+            }
+            other => panic!("expected a single platform, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn platform_strict_target_features_roundtrip() {
+        let summary = PlatformSummary::new("x86_64-unknown-linux-gnu")
+            .with_target_features(TargetFeaturesSummary::Features(
+                ["avx2".to_owned()].into_iter().collect(),
+            ))
+            .with_strict_target_features(true);
+        let platform_spec = assert_roundtrip(summary);
+        match platform_spec {
+            PlatformSpec::Platform(platform) => {
+                assert!(platform.strict_target_features(), "strict mode is kept");
+                // In strict mode, features implied by avx2 aren't considered enabled.
+                let spec = TargetSpec::new("cfg(target_feature = \"sse2\")").unwrap();
+                assert_eq!(spec.eval(&platform), Some(false));
+            }
+            other => panic!("expected a single platform, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn platform_metadata_roundtrip() {
+        let platform = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        let metadata = platform.metadata().expect("builtin platform has metadata");
+        let summary = platform
+            .to_summary()
+            .with_metadata(TargetMetadataSummary::new(&metadata));
+        // assert_roundtrip checks that the metadata is deserialized.
+        assert_roundtrip(summary);
     }
 }
 
 #[cfg(all(test, feature = "proptest1"))]
 mod proptests {
     use super::*;
+    use crate::platform::Platform;
     use proptest::prelude::*;
-    use std::collections::HashSet;
 
     proptest! {
         #[test]
@@ -237,12 +348,24 @@ mod proptests {
                 (PlatformSpec::Any, PlatformSpec::Any)
                 | (PlatformSpec::Always, PlatformSpec::Always) => {},
                 (PlatformSpec::Platform(platform), PlatformSpec::Platform(platform2)) => {
-                    assert_eq!(platform.triple_str(), platform2.triple_str(), "triples match");
-                    assert_eq!(platform.target_features(), platform2.target_features(), "target features match");
-                    assert_eq!(platform.flags().collect::<HashSet<_>>(), platform2.flags().collect::<HashSet<_>>(), "flags match");
+                    assert_platforms_match(&platform, &platform2);
+                }
+                (PlatformSpec::Platforms(platforms), PlatformSpec::Platforms(platforms2)) => {
+                    assert_eq!(platforms.len(), platforms2.len(), "number of platforms match");
+                    for (platform, platform2) in platforms.iter().zip(&platforms2) {
+                        assert_platforms_match(platform, platform2);
+                    }
                 }
                 (other, other2) => panic!("platform specs do not match: original: {:?}, roundtrip: {:?}", other, other2),
             }
         }
     }
+
+    fn assert_platforms_match(platform: &Platform, platform2: &Platform) {
+        assert_eq!(
+            platform.to_summary(),
+            platform2.to_summary(),
+            "platform summaries match"
+        );
+    }
 }
@@ -5,10 +5,15 @@ use fixtures::{
     json::{self, JsonFixture},
     package_id,
 };
-use guppy::graph::{
-    feature::{named_feature_filter, FeatureId, FeatureLabel, StandardFeatures},
-    BuildTargetId, BuildTargetKind, DependencyDirection, DotWrite, PackageDotVisitor, PackageLink,
-    PackageMetadata,
+use guppy::{
+    graph::{
+        cargo::{BuildPlatform, CargoOptions, DefeatedDefaults, FeatureSurplus, RedundantReason},
+        feature::{named_feature_filter, FeatureId, FeatureLabel, StandardFeatures},
+        BuildTargetId, BuildTargetKind, DependencyDirection, DotWrite, PackageDotVisitor,
+        PackageLink, PackageMetadata, VersionReqLintKind,
+    },
+    platform::{EnabledTernary, Platform, PlatformSpec, PlatformStatus, TargetFeatures},
+    DependencyKind,
 };
 use std::{fmt, iter};
 
@@ -137,6 +142,63 @@ mod small {
 
     proptest_suite!(metadata1);
 
+    #[test]
+    fn metadata1_metrics() {
+        let graph = JsonFixture::metadata1().graph();
+        let package_set = graph.query_workspace().resolve();
+        let metrics = package_set.metrics();
+        assert_eq!(
+            metrics.len(),
+            package_set.len(),
+            "all packages have metrics"
+        );
+
+        let testcrate = metrics
+            .get(&package_id(json::METADATA1_TESTCRATE))
+            .expect("testcrate is in the set");
+        assert_eq!(testcrate.depth(), Some(0), "workspace member has depth 0");
+        assert_eq!(
+            testcrate.workspace_dependents(),
+            0,
+            "testcrate has no dependents"
+        );
+        assert_eq!(testcrate.fan_in(), 0, "testcrate has no fan-in");
+
+        let datatest = metrics
+            .get(&package_id(json::METADATA1_DATATEST))
+            .expect("datatest is in the set");
+        assert_eq!(datatest.depth(), Some(1), "datatest is a direct dependency");
+        assert_eq!(
+            datatest.workspace_dependents(),
+            1,
+            "testcrate depends on datatest"
+        );
+        assert_eq!(datatest.fan_in(), 1, "only testcrate depends on datatest");
+        // testcrate only depends on datatest, so every path goes through it.
+        assert_eq!(
+            datatest.betweenness(),
+            testcrate.betweenness(),
+            "every path from testcrate goes through datatest"
+        );
+        assert!(
+            datatest.betweenness() > 0,
+            "there are paths through datatest"
+        );
+
+        for metric in metrics.iter() {
+            assert!(
+                metric.depth().is_some(),
+                "{} is reachable from the workspace",
+                metric.package().name()
+            );
+            assert!(
+                metric.betweenness() <= testcrate.betweenness(),
+                "{} is on at most every path",
+                metric.package().name()
+            );
+        }
+    }
+
     #[test]
     fn metadata2() {
         let metadata2 = JsonFixture::metadata2();
@@ -181,6 +243,72 @@ mod small {
 
     proptest_suite!(metadata_cycle2);
 
+    #[test]
+    fn reachability_index() {
+        let fixtures = [
+            JsonFixture::metadata1(),
+            JsonFixture::metadata2(),
+            JsonFixture::metadata_dups(),
+            JsonFixture::metadata_cycle1(),
+            JsonFixture::metadata_cycle2(),
+            JsonFixture::metadata_cycle_features(),
+        ];
+        for fixture in fixtures {
+            let graph = fixture.graph();
+            let reachability = graph.reachability_index();
+            let mut depends_cache = graph.new_depends_cache();
+
+            for package in graph.packages() {
+                let dependencies = reachability
+                    .dependencies(package.id())
+                    .expect("valid package ID");
+                let expected = graph
+                    .query_forward(iter::once(package.id()))
+                    .expect("valid package ID")
+                    .resolve();
+                assert_eq!(
+                    dependencies,
+                    expected,
+                    "{}: dependencies of {} match forward query",
+                    fixture.name(),
+                    package.id()
+                );
+
+                for other in graph.packages() {
+                    assert_eq!(
+                        reachability
+                            .depends_on(package.id(), other.id())
+                            .expect("valid package IDs"),
+                        depends_cache
+                            .depends_on(package.id(), other.id())
+                            .expect("valid package IDs"),
+                        "{}: {} -> {}: reachability index matches depends cache",
+                        fixture.name(),
+                        package.id(),
+                        other.id(),
+                    );
+                }
+            }
+
+            // Every package in a cycle depends on every other package in it.
+            for cycle in graph.cycles().all_cycles() {
+                for &id1 in &cycle {
+                    for &id2 in &cycle {
+                        assert!(
+                            reachability
+                                .depends_on(id1, id2)
+                                .expect("valid package IDs"),
+                            "{}: within cycle, {} depends on {}",
+                            fixture.name(),
+                            id1,
+                            id2
+                        );
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     fn metadata_cycle_features() {
         let metadata_cycle_features = JsonFixture::metadata_cycle_features();
@@ -311,6 +439,61 @@ mod small {
         );
     }
 
+    #[test]
+    fn metadata_targets1_explain() {
+        let package_graph = JsonFixture::metadata_targets1().graph();
+        let platform = Platform::new("x86_64-pc-windows-msvc", TargetFeatures::Unknown).unwrap();
+        let platform_spec = PlatformSpec::from(platform.clone());
+
+        let mut unknown_count = 0;
+        for link in package_graph
+            .resolve_all()
+            .links(DependencyDirection::Forward)
+        {
+            let statuses = [link.normal(), link.build(), link.dev()]
+                .into_iter()
+                .flat_map(|req| {
+                    [
+                        req.status().required_status(),
+                        req.status().optional_status(),
+                    ]
+                });
+            for status in statuses {
+                let PlatformStatus::PlatformDependent { eval } = status else {
+                    continue;
+                };
+                if status.enabled_on(&platform_spec) != EnabledTernary::Unknown {
+                    continue;
+                }
+                unknown_count += 1;
+                let traces: Vec<_> = eval.eval_explain(&platform).collect();
+
+                // An unknown result means that no spec matched, and at least one was unknown
+                // because of target features.
+                assert!(
+                    traces.iter().all(|trace| trace.result() != Some(true)),
+                    "for link {} -> {}, no spec matched",
+                    link.from().name(),
+                    link.to().name(),
+                );
+                let unknown_traces: Vec<_> = traces
+                    .iter()
+                    .filter(|trace| trace.result().is_none())
+                    .collect();
+                assert!(!unknown_traces.is_empty(), "at least one spec is unknown");
+                for trace in unknown_traces {
+                    assert!(
+                        trace
+                            .unknown_predicates()
+                            .all(|pred| pred.predicate().starts_with("target_feature")),
+                        "unknown predicates are all target features: {trace}",
+                    );
+                }
+            }
+        }
+        assert!(unknown_count > 0, "at least one unknown dependency");
+    }
+
     proptest_suite!(metadata_targets1);
 
     #[test]
@@ -445,6 +628,123 @@ mod large {
 
     proptest_suite!(metadata_libra_9ffd93b);
 
+    #[test]
+    fn metadata_libra_9ffd93b_reachability() {
+        let graph = JsonFixture::metadata_libra_9ffd93b().graph();
+        let reachability = graph.reachability_index();
+        let mut depends_cache = graph.new_depends_cache();
+
+        for package in graph.workspace().iter() {
+            let dependencies = reachability
+                .dependencies(package.id())
+                .expect("valid package ID");
+            let expected = graph
+                .query_forward(iter::once(package.id()))
+                .expect("valid package ID")
+                .resolve();
+            assert_eq!(
+                dependencies,
+                expected,
+                "dependencies of {} match forward query",
+                package.name()
+            );
+
+            let dependents = reachability
+                .dependents(package.id())
+                .expect("valid package ID");
+            let expected = graph
+                .query_reverse(iter::once(package.id()))
+                .expect("valid package ID")
+                .resolve();
+            assert_eq!(
+                dependents,
+                expected,
+                "dependents of {} match reverse query",
+                package.name()
+            );
+
+            for other in graph.packages() {
+                assert_eq!(
+                    reachability
+                        .depends_on(package.id(), other.id())
+                        .expect("valid package IDs"),
+                    depends_cache
+                        .depends_on(package.id(), other.id())
+                        .expect("valid package IDs"),
+                    "{} -> {}: reachability index matches depends cache",
+                    package.name(),
+                    other.name(),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn metadata_libra_9ffd93b_version_req_lints() {
+        let graph = JsonFixture::metadata_libra_9ffd93b().graph();
+        let lints = graph.resolve_workspace().version_req_lints(iter::empty());
+
+        let find = |from: &str, to: &str| {
+            lints
+                .iter()
+                .find(|lint| lint.link().from().name() == from && lint.link().to().name() == to)
+                .unwrap_or_else(|| panic!("{} -> {} is linted", from, to))
+        };
+
+        let lint = find("network", "criterion");
+        assert!(
+            matches!(lint.kind(), VersionReqLintKind::ExactPin),
+            "=0.3.1 is an exact pin"
+        );
+
+        let lint = find("schemadb", "rocksdb");
+        assert!(
+            matches!(lint.kind(), VersionReqLintKind::Wildcard),
+            "* is a wildcard"
+        );
+
+        let lint = find("cluster-test", "tokio");
+        match lint.kind() {
+            VersionReqLintKind::Inconsistent { others } => {
+                assert!(
+                    others.iter().any(|other| other.from().name() == "executor"),
+                    "executor requires a different version of tokio"
+                );
+                for other in others {
+                    assert_ne!(
+                        other.version_req(),
+                        lint.link().version_req(),
+                        "other requirements are different"
+                    );
+                    assert_eq!(other.to().id(), lint.link().to().id(), "same dependency");
+                }
+            }
+            other => panic!("expected inconsistent lint, found {:?}", other),
+        }
+
+        // Path dependencies without a version aren't linted.
+        for lint in lints.iter() {
+            assert!(
+                !lint.link().version_req().comparators.is_empty()
+                    || lint.link().to().source().is_external(),
+                "{} -> {}: path dependency without a version is not linted",
+                lint.link().from().name(),
+                lint.link().to().name(),
+            );
+        }
+
+        // A graph is its own history: no version older than the resolved one is known.
+        let lints = graph
+            .resolve_workspace()
+            .version_req_lints(iter::once(graph));
+        assert!(
+            !lints
+                .iter()
+                .any(|lint| matches!(lint.kind(), VersionReqLintKind::MinimumTooLow { .. })),
+            "no older versions known"
+        );
+    }
+
     #[test]
     fn mnemos_b3b4da9() {
         let metadata = JsonFixture::mnemos_b3b4da9();
@@ -478,6 +778,160 @@ mod guppy_tests {
     }
 
     proptest_suite!(metadata_guppy_44b62fa);
+
+    #[test]
+    fn metadata_guppy_869476c_defeated_defaults() {
+        let graph = JsonFixture::metadata_guppy_869476c().graph();
+        let cargo_set = graph
+            .feature_graph()
+            .query_workspace(StandardFeatures::Default)
+            .resolve()
+            .into_cargo_set(&CargoOptions::new())
+            .expect("resolving cargo should work");
+        let defeated = DefeatedDefaults::new(&cargo_set);
+
+        for entry in defeated.iter() {
+            let (from, to) = entry.link().endpoints();
+            assert!(
+                entry
+                    .link()
+                    .req_for_kind(entry.kind())
+                    .default_features()
+                    .is_never(),
+                "{} -> {}: default-features = false declared",
+                from.name(),
+                to.name()
+            );
+            assert!(
+                !entry.enabled_by().is_empty(),
+                "{} -> {}: at least one link enables default features",
+                from.name(),
+                to.name()
+            );
+            for link in entry.enabled_by() {
+                assert_eq!(
+                    link.to().feature_id(),
+                    to.default_feature_id(),
+                    "{} -> {}: link enables the default feature",
+                    from.name(),
+                    to.name()
+                );
+            }
+        }
+
+        // structopt depends on clap with default-features = false, but cargo-guppy enables
+        // clap's default features.
+        let structopt_clap = defeated
+            .iter()
+            .find(|entry| {
+                entry.link().from().name() == "structopt" && entry.link().to().name() == "clap"
+            })
+            .expect("structopt -> clap is defeated");
+        assert_eq!(structopt_clap.kind(), DependencyKind::Normal);
+        assert_eq!(structopt_clap.build_platform(), BuildPlatform::Target);
+        assert!(
+            structopt_clap
+                .enabled_by()
+                .iter()
+                .any(|link| link.from().package().name() == "cargo-guppy"
+                    && link.from().feature_id().is_base()),
+            "cargo-guppy re-enables clap's default features"
+        );
+    }
+
+    #[test]
+    fn metadata_guppy_869476c_defeated_defaults_platform() {
+        let graph = JsonFixture::metadata_guppy_869476c().graph();
+        let defeated_on = |triple: &'static str| {
+            let mut cargo_options = CargoOptions::new();
+            cargo_options.set_platform(
+                Platform::new(triple, TargetFeatures::Unknown).expect("known triple"),
+            );
+            let cargo_set = graph
+                .feature_graph()
+                .query_workspace(StandardFeatures::Default)
+                .resolve()
+                .into_cargo_set(&cargo_options)
+                .expect("resolving cargo should work");
+            DefeatedDefaults::new(&cargo_set)
+                .iter()
+                .map(|entry| {
+                    let (from, to) = entry.link().endpoints();
+                    (from.name(), to.name())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // atty depends on libc with default-features = false, but only on cfg(unix).
+        assert!(
+            defeated_on("x86_64-unknown-linux-gnu").contains(&("atty", "libc")),
+            "atty -> libc is defeated on Linux"
+        );
+        assert!(
+            !defeated_on("x86_64-pc-windows-msvc").contains(&("atty", "libc")),
+            "atty -> libc doesn't apply on Windows"
+        );
+    }
+
+    #[test]
+    fn metadata_guppy_869476c_feature_surplus() {
+        let graph = JsonFixture::metadata_guppy_869476c().graph();
+        let cargo_set = graph
+            .feature_graph()
+            .query_workspace(StandardFeatures::Default)
+            .resolve()
+            .into_cargo_set(&CargoOptions::new())
+            .expect("resolving cargo should work");
+        let surplus = FeatureSurplus::new(&cargo_set);
+
+        let entry = |from: &str, to: &str| {
+            surplus
+                .iter()
+                .find(|entry| entry.link().from().name() == from && entry.link().to().name() == to)
+                .unwrap_or_else(|| panic!("entry for {} -> {} found", from, to))
+        };
+
+        // guppy only asks for serde_json's default features, but raw_value is turned on elsewhere.
+        let guppy_serde_json = entry("guppy", "serde_json");
+        assert_eq!(guppy_serde_json.requested(), ["default"]);
+        assert_eq!(guppy_serde_json.built(), ["default", "raw_value", "std"]);
+        assert_eq!(guppy_serde_json.free(), ["raw_value"]);
+        assert!(guppy_serde_json.redundant().is_empty());
+
+        // fixture-manager asks for features of guppy that other workspace packages also enable.
+        let fixture_manager_guppy = entry("fixture-manager", "guppy");
+        assert_eq!(
+            fixture_manager_guppy.requested(),
+            ["proptest010", "summaries"]
+        );
+        assert!(fixture_manager_guppy.free().is_empty());
+        assert_eq!(
+            fixture_manager_guppy.redundant(),
+            [
+                ("proptest010", RedundantReason::OtherEdges),
+                ("summaries", RedundantReason::OtherEdges),
+            ]
+        );
+
+        for entry in surplus.iter() {
+            for feature in entry.requested() {
+                assert!(
+                    entry.built().contains(feature),
+                    "{} -> {}: requested feature {} is built",
+                    entry.link().from().name(),
+                    entry.link().to().name(),
+                    feature
+                );
+            }
+            assert_eq!(
+                entry.is_exact(),
+                surplus
+                    .surplus()
+                    .all(|other| other.link().endpoints() != entry.link().endpoints()),
+                "surplus() matches is_exact()"
+            );
+        }
+    }
 }
 
 struct NameVisitor;
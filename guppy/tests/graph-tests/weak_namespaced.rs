@@ -7,9 +7,17 @@ use fixtures::{
     package_id,
 };
 use guppy::graph::{
-    cargo::{CargoOptions, CargoResolverVersion, CargoSet},
-    feature::{named_feature_filter, FeatureLabel, FeatureSet, StandardFeatures},
+    cargo::{
+        BuildPlatform, CargoOptions, CargoResolverVersion, CargoSet, FeatureCosts,
+        MultiPlatformCargoSet,
+    },
+    feature::{
+        named_feature_filter, FeatureLabel, FeaturePowersetOptions, FeatureSet, StandardFeatures,
+    },
+    DependencyDirection, PackageGraph, VersionReqLintKind,
 };
+use guppy::platform::PlatformSpec;
+use semver::Version;
 use target_spec::Platform;
 
 #[test]
@@ -410,6 +418,369 @@ fn test_edge_upgrades() {
     }
 }
 
+#[test]
+fn dead_features() {
+    let graph = JsonFixture::metadata_weak_namespaced_features().graph();
+    let feature_graph = graph.feature_graph();
+
+    let dead_features = feature_graph.dead_features(&graph.resolve_workspace());
+    // There's only one package in the workspace and it has no default features, so all its named
+    // features are never enabled.
+    let never_enabled: Vec<_> = dead_features
+        .never_enabled()
+        .map(|feature_id| feature_id.label())
+        .collect();
+    assert_eq!(
+        never_enabled.len(),
+        20,
+        "all named features are never enabled"
+    );
+    assert!(
+        never_enabled.contains(&FeatureLabel::Named("windows-weak")),
+        "windows-weak is never enabled"
+    );
+
+    let enables_nothing: Vec<_> = dead_features
+        .enables_nothing()
+        .map(|feature_id| feature_id.label())
+        .collect();
+    assert_eq!(
+        enables_nothing,
+        [FeatureLabel::Named("arrayvec")],
+        "only arrayvec enables nothing"
+    );
+    // All dependencies are optional, so nothing is shadowed.
+    assert_eq!(
+        dead_features.shadowed().len(),
+        0,
+        "no features are shadowed"
+    );
+
+    // Third-party packages are ignored.
+    let third_party = graph
+        .resolve_ids([&package_id(json::METADATA_WEAK_NAMESPACED_SMALLVEC)])
+        .expect("valid package ID");
+    assert!(
+        feature_graph.dead_features(&third_party).is_empty(),
+        "third-party features aren't reported"
+    );
+}
+
+#[test]
+fn feature_costs() {
+    let graph = JsonFixture::metadata_weak_namespaced_features().graph();
+    let mut cargo_options = CargoOptions::new();
+    cargo_options
+        .set_resolver(CargoResolverVersion::V2)
+        .set_target_platform(
+            Platform::new(
+                "x86_64-unknown-linux-gnu",
+                target_spec::TargetFeatures::Unknown,
+            )
+            .unwrap(),
+        );
+
+    let costs = FeatureCosts::new(
+        feature_set_fn(&[]),
+        graph.feature_graph().resolve_none(),
+        &package_id(json::METADATA_WEAK_NAMESPACED_ID),
+        &cargo_options,
+    )
+    .expect("feature costs computed");
+    assert_eq!(costs.iter().len(), 20, "one cost per named feature");
+
+    let cost_for = |feature_name: &str| {
+        costs
+            .iter()
+            .find(|cost| cost.feature_id().label() == FeatureLabel::Named(feature_name))
+            .expect("feature is present")
+    };
+
+    // foo pulls in arrayvec on the target.
+    let foo = cost_for("foo");
+    let added: Vec<_> = foo
+        .added_packages(BuildPlatform::Target)
+        .package_ids(DependencyDirection::Forward)
+        .collect();
+    assert_eq!(
+        added,
+        [&package_id(json::METADATA_WEAK_NAMESPACED_ARRAYVEC)],
+        "foo adds arrayvec"
+    );
+    assert!(
+        foo.added_packages(BuildPlatform::Host).is_empty(),
+        "foo adds nothing on the host"
+    );
+
+    // The arrayvec named feature only adds itself.
+    let arrayvec = cost_for("arrayvec");
+    assert_eq!(
+        arrayvec.added_package_count(),
+        0,
+        "arrayvec adds no packages"
+    );
+    assert_eq!(
+        arrayvec.added_feature_count(),
+        1,
+        "arrayvec adds one feature"
+    );
+
+    // tinyvec is a Windows-only dependency, so windows-weak only adds itself on Linux.
+    let windows_weak = cost_for("windows-weak");
+    assert_eq!(
+        windows_weak.added_package_count(),
+        0,
+        "windows-weak adds no packages on Linux"
+    );
+    assert!(!windows_weak.is_free(), "windows-weak adds itself");
+
+    // Features that add packages are sorted first.
+    let sorted = costs.sorted_by_cost();
+    assert!(
+        sorted[0].added_package_count() > 0,
+        "most expensive feature adds packages"
+    );
+    assert_eq!(
+        sorted
+            .last()
+            .expect("at least one feature")
+            .added_package_count(),
+        0,
+        "cheapest feature adds no packages"
+    );
+}
+
+#[test]
+fn feature_costs_per_platform() {
+    let graph = JsonFixture::metadata_weak_namespaced_features().graph();
+    let triples = ["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc"];
+    let platforms = triples
+        .iter()
+        .map(|triple| Platform::new(*triple, target_spec::TargetFeatures::Unknown).unwrap());
+    let mut cargo_options = CargoOptions::new();
+    cargo_options.set_resolver(CargoResolverVersion::V2);
+
+    let per_platform = FeatureCosts::new_per_platform(
+        feature_set_fn(&[]),
+        graph.feature_graph().resolve_none(),
+        &package_id(json::METADATA_WEAK_NAMESPACED_ID),
+        &cargo_options,
+        platforms.clone(),
+    )
+    .expect("feature costs computed");
+    assert_eq!(per_platform.len(), 2, "one set of costs per platform");
+
+    // Each set of costs matches the costs computed for that platform on its own.
+    for ((costs, platform), triple) in per_platform.iter().zip(platforms).zip(triples) {
+        match costs.target_platform() {
+            PlatformSpec::Platform(platform) => {
+                assert_eq!(platform.triple_str(), triple, "target platform matches")
+            }
+            other => panic!(
+                "expected a single platform for {}, found {:?}",
+                triple, other
+            ),
+        }
+        let mut single_options = cargo_options.clone();
+        single_options.set_target_platform(platform);
+        let expected = FeatureCosts::new(
+            feature_set_fn(&[]),
+            graph.feature_graph().resolve_none(),
+            &package_id(json::METADATA_WEAK_NAMESPACED_ID),
+            &single_options,
+        )
+        .expect("feature costs computed");
+        for (cost, expected) in costs.iter().zip(expected.iter()) {
+            assert_eq!(
+                cost.feature_id(),
+                expected.feature_id(),
+                "same feature order"
+            );
+            for build_platform in BuildPlatform::VALUES {
+                assert_eq!(
+                    cost.added_packages(*build_platform),
+                    expected.added_packages(*build_platform),
+                    "for {} on {}, {:?} added packages match",
+                    cost.feature_id(),
+                    triple,
+                    build_platform,
+                );
+            }
+        }
+    }
+
+    // tinyvec is a Windows-only dependency, so windows-dep only adds packages on Windows.
+    let windows_dep_packages = |costs: &FeatureCosts<'_>| {
+        costs
+            .iter()
+            .find(|cost| cost.feature_id().label() == FeatureLabel::Named("windows-dep"))
+            .expect("feature is present")
+            .added_package_count()
+    };
+    assert_eq!(
+        windows_dep_packages(&per_platform[0]),
+        0,
+        "windows-dep adds no packages on Linux"
+    );
+    assert!(
+        windows_dep_packages(&per_platform[1]) > 0,
+        "windows-dep adds packages on Windows"
+    );
+}
+
+#[test]
+fn multi_platform_cargo_set() {
+    // The two Linux platforms evaluate every platform-specific dependency the same way, so they
+    // share a resolution.
+    let triples = [
+        "x86_64-unknown-linux-gnu",
+        "x86_64-pc-windows-msvc",
+        "aarch64-unknown-linux-gnu",
+    ];
+    let platforms = triples
+        .iter()
+        .map(|triple| Platform::new(*triple, target_spec::TargetFeatures::Unknown).unwrap());
+    let feature_set = feature_set_fn(&["windows-dep"]);
+
+    for resolver in [CargoResolverVersion::V1, CargoResolverVersion::V2] {
+        let mut cargo_options = CargoOptions::new();
+        cargo_options.set_resolver(resolver);
+        let multi = MultiPlatformCargoSet::new(
+            feature_set.clone(),
+            feature_set.graph().resolve_none(),
+            &cargo_options,
+            platforms.clone(),
+        )
+        .expect("resolving cargo should work");
+        assert_eq!(multi.len(), 3, "three target platforms");
+
+        // Each per-platform set matches a CargoSet computed on its own.
+        for ((platform, cargo_set), triple) in multi.iter().zip(triples) {
+            let mut single_options = cargo_options.clone();
+            single_options.set_target_platform(platform.clone());
+            let expected = feature_set
+                .clone()
+                .into_cargo_set(&single_options)
+                .expect("resolving cargo should work");
+            for build_platform in BuildPlatform::VALUES {
+                assert_eq!(
+                    cargo_set.platform_features(*build_platform),
+                    expected.platform_features(*build_platform),
+                    "for {:?} on {}, {:?} features match",
+                    resolver,
+                    triple,
+                    build_platform,
+                );
+            }
+        }
+
+        // tinyvec is only built on Windows.
+        let tinyvec_id = package_id(json::METADATA_WEAK_NAMESPACED_TINYVEC);
+        let differences = multi.differences();
+        assert!(
+            !differences[0]
+                .extra_packages(BuildPlatform::Target)
+                .contains(&tinyvec_id)
+                .unwrap(),
+            "{:?}: tinyvec not built on Linux",
+            resolver
+        );
+        assert!(
+            differences[1]
+                .extra_packages(BuildPlatform::Target)
+                .contains(&tinyvec_id)
+                .unwrap(),
+            "{:?}: tinyvec only built on Windows",
+            resolver
+        );
+        assert!(
+            !differences[2]
+                .extra_packages(BuildPlatform::Target)
+                .contains(&tinyvec_id)
+                .unwrap(),
+            "{:?}: tinyvec not built on aarch64 Linux",
+            resolver
+        );
+        assert!(
+            !multi
+                .common_features(BuildPlatform::Target)
+                .expect("platforms specified")
+                .contains_package(&tinyvec_id)
+                .unwrap(),
+            "{:?}: tinyvec not common to all platforms",
+            resolver
+        );
+    }
+}
+
+#[test]
+fn platform_set_cargo_set() {
+    let linux = Platform::new(
+        "x86_64-unknown-linux-gnu",
+        target_spec::TargetFeatures::Unknown,
+    )
+    .unwrap();
+    let windows = Platform::new(
+        "x86_64-pc-windows-msvc",
+        target_spec::TargetFeatures::Unknown,
+    )
+    .unwrap();
+    let feature_set = feature_set_fn(&["windows-dep"]);
+    let tinyvec_id = package_id(json::METADATA_WEAK_NAMESPACED_TINYVEC);
+
+    for resolver in [CargoResolverVersion::V1, CargoResolverVersion::V2] {
+        let mut cargo_options = CargoOptions::new();
+        cargo_options
+            .set_resolver(resolver)
+            .set_target_platform(PlatformSpec::platforms([linux.clone(), windows.clone()]));
+        let cargo_set = feature_set
+            .clone()
+            .into_cargo_set(&cargo_options)
+            .expect("resolving cargo should work");
+
+        // The set of platforms includes everything that's built on any one of them.
+        for platform in [&linux, &windows] {
+            let mut single_options = cargo_options.clone();
+            single_options.set_target_platform(platform.clone());
+            let single = feature_set
+                .clone()
+                .into_cargo_set(&single_options)
+                .expect("resolving cargo should work");
+            let single_features = single.target_features();
+            assert_eq!(
+                &single_features.union(cargo_set.target_features()),
+                cargo_set.target_features(),
+                "{:?}: features on {} are a subset of the union",
+                resolver,
+                platform.triple_str(),
+            );
+        }
+        assert!(
+            cargo_set
+                .target_features()
+                .contains_package(&tinyvec_id)
+                .unwrap(),
+            "{:?}: tinyvec is built on Windows, so it's built for the set",
+            resolver
+        );
+
+        // An empty set of platforms disables all platform-specific dependencies.
+        cargo_options.set_target_platform(PlatformSpec::platforms(Vec::<Platform>::new()));
+        let cargo_set = feature_set
+            .clone()
+            .into_cargo_set(&cargo_options)
+            .expect("resolving cargo should work");
+        assert!(
+            !cargo_set
+                .target_features()
+                .contains_package(&tinyvec_id)
+                .unwrap(),
+            "{:?}: tinyvec is not built for an empty set",
+            resolver
+        );
+    }
+}
+
 fn feature_set_fn(named_features: &[&str]) -> FeatureSet<'static> {
     JsonFixture::metadata_weak_namespaced_features()
         .graph()
@@ -439,3 +810,192 @@ fn make_cargo_set(feature_set: FeatureSet<'static>, triple: &'static str) -> Car
         .into_cargo_set(&cargo_options)
         .expect("resolving cargo should work")
 }
+
+#[test]
+fn feature_powerset() {
+    let graph = JsonFixture::metadata_weak_namespaced_features().graph();
+    let feature_graph = graph.feature_graph();
+    let package_id = package_id(json::METADATA_WEAK_NAMESPACED_ID);
+
+    // With a depth of 1, each feature is tried on its own.
+    let mut opts = FeaturePowersetOptions::new();
+    opts.set_depth(Some(1));
+    let powerset = feature_graph
+        .feature_powerset(&package_id, &opts)
+        .expect("valid options");
+    let package = graph.metadata(&package_id).unwrap();
+    assert_eq!(
+        powerset.len(),
+        package.named_features().count() + 1,
+        "one combination per feature, plus the empty combination"
+    );
+    assert_eq!(powerset.duplicate_count(), 0, "no duplicates at depth 1");
+    assert_eq!(
+        powerset.iter().next().unwrap().features(),
+        &[] as &[&str],
+        "empty combination is first"
+    );
+
+    // With a depth of 2, combinations that resolve to the same feature set are deduplicated.
+    // For example, `smallvec` enables `foo`, so `foo` + `smallvec` is the same as `smallvec`.
+    opts.set_depth(Some(2));
+    let powerset = feature_graph
+        .feature_powerset(&package_id, &opts)
+        .expect("valid options");
+    assert!(
+        powerset.duplicate_count() > 0,
+        "some combinations deduplicated"
+    );
+    assert!(
+        powerset
+            .iter()
+            .all(|combination| combination.features() != ["foo", "smallvec"]),
+        "foo + smallvec is deduplicated"
+    );
+
+    // Every pair of distinct combinations resolves to different feature sets.
+    let combinations: Vec<_> = powerset.iter().collect();
+    for (idx, a) in combinations.iter().enumerate() {
+        for b in &combinations[idx + 1..] {
+            assert_ne!(
+                a.feature_set(),
+                b.feature_set(),
+                "{:?} and {:?} resolve to different feature sets",
+                a.features(),
+                b.features()
+            );
+        }
+    }
+
+    // Exclusions, mutually exclusive groups and always-included features.
+    let mut opts = FeaturePowersetOptions::new();
+    opts.set_depth(Some(2))
+        .add_exclude(["baz"])
+        .add_mutually_exclusive(["bar", "smallvec"])
+        .add_always_include(["foo"]);
+    let powerset = feature_graph
+        .feature_powerset(&package_id, &opts)
+        .expect("valid options");
+    for combination in powerset.iter() {
+        let features = combination.features();
+        assert_eq!(features[0], "foo", "foo always included");
+        assert!(!features.contains(&"baz"), "baz excluded");
+        assert!(
+            !(features.contains(&"bar") && features.contains(&"smallvec")),
+            "bar and smallvec are mutually exclusive"
+        );
+        assert!(
+            features.len() <= 3,
+            "depth excludes always-included features"
+        );
+    }
+
+    // Unknown features are rejected.
+    let mut opts = FeaturePowersetOptions::new();
+    opts.add_exclude(["does-not-exist"]);
+    feature_graph
+        .feature_powerset(&package_id, &opts)
+        .expect_err("unknown feature rejected");
+}
+
+#[test]
+fn version_req_minimum_too_low() {
+    // namespaced-weak depends on smallvec ^1.8.0. Resolve it to 1.9.0, and use the `write` feature
+    // directly. In history, 1.8.5 doesn't have `write` but 1.8.8 does.
+    let graph = smallvec_graph("1.9.0", None, Some("write"));
+    let history = [
+        smallvec_graph("1.8.5", Some("write"), None),
+        smallvec_graph("1.8.8", None, None),
+    ];
+    let lints = graph.resolve_workspace().version_req_lints(&history);
+
+    let lint = lints
+        .iter()
+        .find(|lint| lint.link().to().name() == "smallvec")
+        .expect("namespaced-weak -> smallvec is linted");
+    match lint.kind() {
+        VersionReqLintKind::MinimumTooLow {
+            version,
+            missing_features,
+            suggested_minimum,
+        } => {
+            assert_eq!(version, &Version::new(1, 8, 5), "1.8.5 is too low");
+            assert_eq!(missing_features, &["write"], "write is missing in 1.8.5");
+            assert_eq!(
+                suggested_minimum,
+                &Version::new(1, 8, 8),
+                "1.8.8 has all used features"
+            );
+        }
+        other => panic!("expected minimum too low lint, found {:?}", other),
+    }
+}
+
+#[test]
+fn version_req_minimum_too_low_feature_dep() {
+    // namespaced-weak enables smallvec/union through its [features] section, and doesn't use any
+    // features through the dependency declaration. In history, 1.8.5 doesn't have `union`.
+    let graph = smallvec_graph("1.9.0", None, None);
+    let history = [smallvec_graph("1.8.5", Some("union"), None)];
+    let lints = graph.resolve_workspace().version_req_lints(&history);
+
+    let lint = lints
+        .iter()
+        .find(|lint| lint.link().to().name() == "smallvec")
+        .expect("namespaced-weak -> smallvec is linted");
+    match lint.kind() {
+        VersionReqLintKind::MinimumTooLow {
+            version,
+            missing_features,
+            suggested_minimum,
+        } => {
+            assert_eq!(version, &Version::new(1, 8, 5), "1.8.5 is too low");
+            assert_eq!(missing_features, &["union"], "union is missing in 1.8.5");
+            assert_eq!(
+                suggested_minimum,
+                &Version::new(1, 9, 0),
+                "no known older version has union, so the resolved version is suggested"
+            );
+        }
+        other => panic!("expected minimum too low lint, found {:?}", other),
+    }
+}
+
+/// Builds a variant of the weak-namespaced fixture, with smallvec at `version`, optionally missing
+/// the feature `remove_feature`, and with `use_feature` enabled on the dependency from
+/// namespaced-weak.
+fn smallvec_graph(
+    version: &str,
+    remove_feature: Option<&str>,
+    use_feature: Option<&str>,
+) -> PackageGraph {
+    let mut json: serde_json::Value =
+        serde_json::from_str(JsonFixture::metadata_weak_namespaced_features().json())
+            .expect("fixture is valid JSON");
+    for package in json["packages"]
+        .as_array_mut()
+        .expect("packages is an array")
+    {
+        if package["name"] == "smallvec" {
+            package["version"] = version.into();
+            if let Some(feature) = remove_feature {
+                package["features"]
+                    .as_object_mut()
+                    .expect("features is an object")
+                    .remove(feature);
+            }
+        } else if package["name"] == "namespaced-weak" {
+            if let Some(feature) = use_feature {
+                for dep in package["dependencies"]
+                    .as_array_mut()
+                    .expect("dependencies is an array")
+                {
+                    if dep["name"] == "smallvec" {
+                        dep["features"] = serde_json::json!([feature]);
+                    }
+                }
+            }
+        }
+    }
+    PackageGraph::from_json(json.to_string()).expect("modified fixture is valid")
+}
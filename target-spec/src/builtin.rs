@@ -0,0 +1,467 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{Platform, TargetFeatures, TargetSpec, Triple};
+use cfg_expr::targets::{get_builtin_target_by_triple, ALL_BUILTINS};
+use std::fmt;
+
+/// The support tier of a builtin target, as defined by the [Rust target tier
+/// policy](https://doc.rust-lang.org/nightly/rustc/target-tier-policy.html).
+///
+/// Tiers are ordered from best to least supported, so `TargetTier::Tier1 < TargetTier::Tier2`.
+///
+/// The tiers known to `target-spec` match those listed on the [platform support
+/// page](https://doc.rust-lang.org/nightly/rustc/platform-support.html) for the Rust version its
+/// builtin target table is based on. Builtin targets not listed as tier 1 or tier 2 are tier 3.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum TargetTier {
+    /// Tier 1 targets are guaranteed to work.
+    Tier1,
+
+    /// Tier 2 targets are guaranteed to build.
+    Tier2,
+
+    /// Tier 3 targets are supported by the Rust codebase, but aren't built or tested
+    /// automatically.
+    Tier3,
+}
+
+impl fmt::Display for TargetTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetTier::Tier1 => write!(f, "tier 1"),
+            TargetTier::Tier2 => write!(f, "tier 2"),
+            TargetTier::Tier3 => write!(f, "tier 3"),
+        }
+    }
+}
+
+/// Metadata about a builtin target, as listed on the [platform support
+/// page](https://doc.rust-lang.org/nightly/rustc/platform-support.html).
+///
+/// Returned by [`Triple::metadata`] and [`Platform::metadata`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TargetMetadata {
+    tier: TargetTier,
+    std: Option<bool>,
+    host_tools: bool,
+}
+
+impl TargetMetadata {
+    /// Returns the support tier of this target.
+    pub fn tier(&self) -> TargetTier {
+        self.tier
+    }
+
+    /// Returns whether this target supports `std`.
+    ///
+    /// Returns `Some(false)` for targets that only support `core` (and possibly `alloc`), and
+    /// `None` for tier 3 targets, for which `std` support isn't tracked.
+    pub fn has_std(&self) -> Option<bool> {
+        self.std
+    }
+
+    /// Returns true if official builds of host tools (`rustc`, `cargo` and so on) are available
+    /// for this target.
+    ///
+    /// This is true for tier 1 targets and some tier 2 targets.
+    pub fn has_host_tools(&self) -> bool {
+        self.host_tools
+    }
+
+    fn new(triple_str: &str, std: bool) -> Self {
+        let tier = builtin_tier(triple_str);
+        Self {
+            tier,
+            std: (tier != TargetTier::Tier3).then_some(std),
+            host_tools: tier == TargetTier::Tier1 || HOST_TOOLS.contains(&triple_str),
+        }
+    }
+}
+
+impl Triple {
+    /// Returns metadata about this triple, or `None` if this isn't a builtin triple.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use target_spec::{TargetTier, Triple};
+    ///
+    /// let metadata = Triple::new("thumbv7em-none-eabihf").unwrap().metadata().unwrap();
+    /// assert_eq!(metadata.tier(), TargetTier::Tier2);
+    /// assert_eq!(metadata.has_std(), Some(false));
+    /// assert!(!metadata.has_host_tools());
+    /// ```
+    pub fn metadata(&self) -> Option<TargetMetadata> {
+        if !self.is_builtin() {
+            return None;
+        }
+        let target_info = get_builtin_target_by_triple(self.as_str())?;
+        // Targets without an operating system only support core, as do the UEFI and CUDA
+        // targets.
+        let std = !matches!(
+            target_info.os.as_ref().map(|os| os.as_str()),
+            None | Some("none" | "uefi" | "cuda")
+        );
+        Some(TargetMetadata::new(self.as_str(), std))
+    }
+
+    /// Returns the support tier of this triple, or `None` if this isn't a builtin triple.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use target_spec::{TargetTier, Triple};
+    ///
+    /// let triple = Triple::new("x86_64-unknown-linux-gnu").unwrap();
+    /// assert_eq!(triple.tier(), Some(TargetTier::Tier1));
+    /// ```
+    pub fn tier(&self) -> Option<TargetTier> {
+        if self.is_builtin() {
+            Some(builtin_tier(self.as_str()))
+        } else {
+            None
+        }
+    }
+}
+
+impl Platform {
+    /// Returns metadata about this platform's triple, or `None` if it isn't a builtin triple.
+    ///
+    /// For more, see [`Triple::metadata`].
+    pub fn metadata(&self) -> Option<TargetMetadata> {
+        self.triple().metadata()
+    }
+}
+
+impl TargetSpec {
+    /// Evaluates this specification against every builtin target known to `target-spec`.
+    ///
+    /// If `max_tier` is specified, only targets with that tier or better are considered. For
+    /// example, `Some(TargetTier::Tier2)` considers tier 1 and tier 2 targets.
+    ///
+    /// Target features are treated as unknown, so specifications that use `target_feature` may
+    /// have an unknown result for some targets. Flags and key-value cfgs are treated as unset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use target_spec::{TargetSpec, TargetTier};
+    ///
+    /// let spec = TargetSpec::new("cfg(all(windows, target_arch = \"aarch64\"))").unwrap();
+    /// let matches = spec.builtin_matches(Some(TargetTier::Tier2));
+    /// assert!(matches.matching().any(|triple| triple.as_str() == "aarch64-pc-windows-msvc"));
+    /// assert!(matches.matches_any());
+    /// ```
+    pub fn builtin_matches(&self, max_tier: Option<TargetTier>) -> BuiltinMatches {
+        let mut matching = vec![];
+        let mut unknown = vec![];
+        for target_info in ALL_BUILTINS {
+            let triple_str = target_info.triple.as_str();
+            if let Some(max_tier) = max_tier {
+                if builtin_tier(triple_str) > max_tier {
+                    continue;
+                }
+            }
+
+            let platform = Platform::new_strict(triple_str.to_owned(), TargetFeatures::Unknown)
+                .expect("builtin triples are known");
+            match self.eval(&platform) {
+                Some(true) => matching.push(platform.triple().clone()),
+                Some(false) => {}
+                None => unknown.push(platform.triple().clone()),
+            }
+        }
+
+        BuiltinMatches { matching, unknown }
+    }
+}
+
+/// The builtin targets a [`TargetSpec`] matches.
+///
+/// Returned by [`TargetSpec::builtin_matches`].
+#[derive(Clone, Debug)]
+pub struct BuiltinMatches {
+    matching: Vec<Triple>,
+    unknown: Vec<Triple>,
+}
+
+impl BuiltinMatches {
+    /// Returns the builtin triples the specification matches, in alphabetical order.
+    pub fn matching(&self) -> impl ExactSizeIterator<Item = &Triple> {
+        self.matching.iter()
+    }
+
+    /// Returns the builtin triples for which the result of evaluation is unknown, in alphabetical
+    /// order.
+    ///
+    /// This is typically because the specification depends on target features.
+    pub fn unknown(&self) -> impl ExactSizeIterator<Item = &Triple> {
+        self.unknown.iter()
+    }
+
+    /// Returns true if the specification matches, or may match, at least one builtin triple.
+    ///
+    /// A specification for which this returns false is likely to be a mistake, for example a typo
+    /// in a `target_os` value, or a combination of predicates that can never be true together.
+    pub fn matches_any(&self) -> bool {
+        !self.matching.is_empty() || !self.unknown.is_empty()
+    }
+}
+
+fn builtin_tier(triple_str: &str) -> TargetTier {
+    if TIER_1.contains(&triple_str) {
+        TargetTier::Tier1
+    } else if TIER_2.contains(&triple_str) {
+        TargetTier::Tier2
+    } else {
+        TargetTier::Tier3
+    }
+}
+
+// Per https://doc.rust-lang.org/1.75.0/rustc/platform-support.html, matching the version of the
+// builtin target table.
+
+static TIER_1: &[&str] = &[
+    "aarch64-unknown-linux-gnu",
+    "i686-pc-windows-gnu",
+    "i686-pc-windows-msvc",
+    "i686-unknown-linux-gnu",
+    "x86_64-apple-darwin",
+    "x86_64-pc-windows-gnu",
+    "x86_64-pc-windows-msvc",
+    "x86_64-unknown-linux-gnu",
+];
+
+static TIER_2: &[&str] = &[
+    "aarch64-apple-darwin",
+    "aarch64-apple-ios",
+    "aarch64-apple-ios-sim",
+    "aarch64-fuchsia",
+    "aarch64-linux-android",
+    "aarch64-pc-windows-msvc",
+    "aarch64-unknown-fuchsia",
+    "aarch64-unknown-linux-musl",
+    "aarch64-unknown-none",
+    "aarch64-unknown-none-softfloat",
+    "aarch64-unknown-uefi",
+    "arm-linux-androideabi",
+    "arm-unknown-linux-gnueabi",
+    "arm-unknown-linux-gnueabihf",
+    "arm-unknown-linux-musleabi",
+    "arm-unknown-linux-musleabihf",
+    "armebv7r-none-eabi",
+    "armebv7r-none-eabihf",
+    "armv5te-unknown-linux-gnueabi",
+    "armv5te-unknown-linux-musleabi",
+    "armv7-linux-androideabi",
+    "armv7-unknown-linux-gnueabi",
+    "armv7-unknown-linux-gnueabihf",
+    "armv7-unknown-linux-musleabi",
+    "armv7-unknown-linux-musleabihf",
+    "armv7a-none-eabi",
+    "armv7r-none-eabi",
+    "armv7r-none-eabihf",
+    "i586-pc-windows-msvc",
+    "i586-unknown-linux-gnu",
+    "i586-unknown-linux-musl",
+    "i686-linux-android",
+    "i686-unknown-freebsd",
+    "i686-unknown-linux-musl",
+    "i686-unknown-uefi",
+    "loongarch64-unknown-linux-gnu",
+    "loongarch64-unknown-none",
+    "loongarch64-unknown-none-softfloat",
+    "nvptx64-nvidia-cuda",
+    "powerpc-unknown-linux-gnu",
+    "powerpc64-unknown-linux-gnu",
+    "powerpc64le-unknown-linux-gnu",
+    "riscv32i-unknown-none-elf",
+    "riscv32imac-unknown-none-elf",
+    "riscv32imc-unknown-none-elf",
+    "riscv64gc-unknown-linux-gnu",
+    "riscv64gc-unknown-none-elf",
+    "riscv64imac-unknown-none-elf",
+    "s390x-unknown-linux-gnu",
+    "sparc64-unknown-linux-gnu",
+    "sparcv9-sun-solaris",
+    "thumbv6m-none-eabi",
+    "thumbv7em-none-eabi",
+    "thumbv7em-none-eabihf",
+    "thumbv7m-none-eabi",
+    "thumbv7neon-linux-androideabi",
+    "thumbv7neon-unknown-linux-gnueabihf",
+    "thumbv8m.base-none-eabi",
+    "thumbv8m.main-none-eabi",
+    "thumbv8m.main-none-eabihf",
+    "wasm32-unknown-emscripten",
+    "wasm32-unknown-unknown",
+    "wasm32-wasi",
+    "wasm32-wasi-preview1-threads",
+    "x86_64-apple-ios",
+    "x86_64-fortanix-unknown-sgx",
+    "x86_64-fuchsia",
+    "x86_64-linux-android",
+    "x86_64-pc-solaris",
+    "x86_64-unknown-freebsd",
+    "x86_64-unknown-fuchsia",
+    "x86_64-unknown-illumos",
+    "x86_64-unknown-linux-gnux32",
+    "x86_64-unknown-linux-musl",
+    "x86_64-unknown-netbsd",
+    "x86_64-unknown-none",
+    "x86_64-unknown-redox",
+    "x86_64-unknown-uefi",
+];
+
+// Tier 2 targets with host tools, per the same page.
+static HOST_TOOLS: &[&str] = &[
+    "aarch64-apple-darwin",
+    "aarch64-pc-windows-msvc",
+    "aarch64-unknown-linux-musl",
+    "arm-unknown-linux-gnueabi",
+    "arm-unknown-linux-gnueabihf",
+    "armv7-unknown-linux-gnueabihf",
+    "loongarch64-unknown-linux-gnu",
+    "powerpc-unknown-linux-gnu",
+    "powerpc64-unknown-linux-gnu",
+    "powerpc64le-unknown-linux-gnu",
+    "riscv64gc-unknown-linux-gnu",
+    "s390x-unknown-linux-gnu",
+    "x86_64-unknown-freebsd",
+    "x86_64-unknown-illumos",
+    "x86_64-unknown-linux-musl",
+    "x86_64-unknown-netbsd",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tier_tables_are_builtin() {
+        for triple_str in TIER_1.iter().chain(TIER_2).chain(HOST_TOOLS) {
+            assert!(
+                get_builtin_target_by_triple(triple_str).is_some(),
+                "{triple_str} is a builtin target"
+            );
+        }
+        for triple_str in HOST_TOOLS {
+            assert!(TIER_2.contains(triple_str), "{triple_str} is tier 2");
+        }
+        for table in [TIER_1, TIER_2, HOST_TOOLS] {
+            assert!(
+                table.windows(2).all(|pair| pair[0] < pair[1]),
+                "tier tables are sorted"
+            );
+        }
+    }
+
+    #[test]
+    fn tiers() {
+        let tier = |triple_str: &str| Triple::new(triple_str.to_owned()).unwrap().tier();
+        assert_eq!(tier("x86_64-pc-windows-msvc"), Some(TargetTier::Tier1));
+        assert_eq!(tier("wasm32-unknown-unknown"), Some(TargetTier::Tier2));
+        assert_eq!(tier("x86_64-unknown-haiku"), Some(TargetTier::Tier3));
+        // Heuristically determined triples don't have a tier.
+        assert_eq!(tier("armv5te-apple-darwin"), None);
+    }
+
+    #[test]
+    fn metadata() {
+        let metadata = |triple_str: &str| {
+            let metadata = Triple::new(triple_str.to_owned()).unwrap().metadata()?;
+            Some((
+                metadata.tier(),
+                metadata.has_std(),
+                metadata.has_host_tools(),
+            ))
+        };
+        assert_eq!(
+            metadata("x86_64-unknown-linux-gnu"),
+            Some((TargetTier::Tier1, Some(true), true))
+        );
+        assert_eq!(
+            metadata("aarch64-apple-darwin"),
+            Some((TargetTier::Tier2, Some(true), true))
+        );
+        assert_eq!(
+            metadata("wasm32-unknown-unknown"),
+            Some((TargetTier::Tier2, Some(true), false))
+        );
+        assert_eq!(
+            metadata("x86_64-unknown-uefi"),
+            Some((TargetTier::Tier2, Some(false), false))
+        );
+        assert_eq!(
+            metadata("riscv32imc-unknown-none-elf"),
+            Some((TargetTier::Tier2, Some(false), false))
+        );
+        assert_eq!(
+            metadata("x86_64-unknown-haiku"),
+            Some((TargetTier::Tier3, None, false))
+        );
+        assert_eq!(metadata("armv5te-apple-darwin"), None);
+
+        let platform = Platform::new("i686-pc-windows-msvc", TargetFeatures::Unknown).unwrap();
+        assert_eq!(
+            platform.metadata().map(|metadata| metadata.tier()),
+            Some(TargetTier::Tier1)
+        );
+    }
+
+    #[test]
+    fn builtin_matches() {
+        let spec = TargetSpec::new("cfg(target_os = \"linux\")").unwrap();
+        let tier_1 = spec.builtin_matches(Some(TargetTier::Tier1));
+        assert_eq!(
+            tier_1
+                .matching()
+                .map(|triple| triple.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "aarch64-unknown-linux-gnu",
+                "i686-unknown-linux-gnu",
+                "x86_64-unknown-linux-gnu",
+            ],
+        );
+        assert_eq!(tier_1.unknown().len(), 0);
+        let all = spec.builtin_matches(None);
+        assert!(all.matching().len() > tier_1.matching().len());
+        assert!(all
+            .matching()
+            .any(|triple| triple.as_str() == "x86_64-unknown-linux-musl"));
+
+        // A plain string matches only itself.
+        let spec = TargetSpec::new("x86_64-pc-windows-gnu").unwrap();
+        assert_eq!(spec.builtin_matches(None).matching().len(), 1);
+
+        // Target features are unknown.
+        let spec = TargetSpec::new("cfg(all(unix, target_feature = \"avx2\"))").unwrap();
+        let matches = spec.builtin_matches(Some(TargetTier::Tier1));
+        assert_eq!(matches.matching().len(), 0);
+        assert_eq!(matches.unknown().len(), 4, "4 tier 1 unix targets");
+        assert!(matches.matches_any());
+    }
+
+    #[test]
+    fn matches_none() {
+        for spec in [
+            // A typo.
+            "cfg(target_os = \"linuz\")",
+            // Contradictory predicates.
+            "cfg(all(windows, unix))",
+            "cfg(all(target_os = \"macos\", target_env = \"msvc\"))",
+            // Unset flags and key-value cfgs.
+            "cfg(tokio_unstable)",
+            // Unknown triples.
+            "x86_64-unknown-linux-gnuu",
+        ] {
+            let spec = TargetSpec::new(spec).unwrap();
+            assert!(
+                !spec.builtin_matches(None).matches_any(),
+                "{spec} matches no builtins"
+            );
+        }
+    }
+}
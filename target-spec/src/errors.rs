@@ -20,6 +20,13 @@ pub enum Error {
     CustomTripleCreate(CustomTripleCreateError),
     /// An error occurred while creating a custom platform.
     CustomPlatformCreate(CustomTripleCreateError),
+    /// An error occurred while running `rustc --print cfg`, or while creating a platform from its
+    /// output.
+    RustcCfg(RustcCfgError),
+    /// An error occurred while loading a custom target for a
+    /// [`TargetRegistry`](crate::TargetRegistry).
+    #[cfg(feature = "custom")]
+    TargetRegistry(TargetRegistryError),
 }
 
 impl fmt::Display for Error {
@@ -36,6 +43,9 @@ impl fmt::Display for Error {
             Error::CustomPlatformCreate(_) => {
                 write!(f, "error creating custom platform")
             }
+            Error::RustcCfg(_) => write!(f, "error creating platform from rustc cfg"),
+            #[cfg(feature = "custom")]
+            Error::TargetRegistry(_) => write!(f, "error loading custom target"),
         }
     }
 }
@@ -48,6 +58,9 @@ impl error::Error for Error {
             Error::UnknownPlatformTriple(err) => Some(err),
             Error::CustomTripleCreate(err) => Some(err),
             Error::CustomPlatformCreate(err) => Some(err),
+            Error::RustcCfg(err) => Some(err),
+            #[cfg(feature = "custom")]
+            Error::TargetRegistry(err) => Some(err),
         }
     }
 }
@@ -354,6 +367,208 @@ impl error::Error for CustomTripleCreateError {
     }
 }
 
+/// An error that occurred while discovering custom targets for a
+/// [`TargetRegistry`](crate::TargetRegistry).
+///
+/// Requires the `custom` feature to be enabled.
+#[cfg(feature = "custom")]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum TargetRegistryError {
+    /// A directory could not be read.
+    ReadDir {
+        /// The directory that was being read.
+        dir: std::path::PathBuf,
+
+        /// The error that occurred.
+        error: std::sync::Arc<std::io::Error>,
+    },
+
+    /// A target JSON file could not be read.
+    ReadFile {
+        /// The file that was being read.
+        path: std::path::PathBuf,
+
+        /// The error that occurred.
+        error: std::sync::Arc<std::io::Error>,
+    },
+
+    /// The name of a target JSON file wasn't valid UTF-8.
+    NonUtf8Name {
+        /// The file with the invalid name.
+        path: std::path::PathBuf,
+    },
+
+    /// A target definition was invalid.
+    InvalidTarget {
+        /// The file the definition was read from, or `None` if it was added directly.
+        path: Option<std::path::PathBuf>,
+
+        /// The error that occurred while validating the definition.
+        error: CustomTripleCreateError,
+    },
+}
+
+#[cfg(feature = "custom")]
+impl fmt::Display for TargetRegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReadDir { dir, .. } => {
+                write!(f, "error reading target directory `{}`", dir.display())
+            }
+            Self::ReadFile { path, .. } => {
+                write!(f, "error reading target JSON `{}`", path.display())
+            }
+            Self::NonUtf8Name { path } => {
+                write!(
+                    f,
+                    "target JSON name `{}` is not valid UTF-8",
+                    path.display()
+                )
+            }
+            Self::InvalidTarget {
+                path: Some(path), ..
+            } => write!(f, "invalid target JSON `{}`", path.display()),
+            Self::InvalidTarget { path: None, error } => match error {
+                CustomTripleCreateError::Deserialize { triple, .. } => {
+                    write!(f, "invalid target JSON for `{triple}`")
+                }
+                _ => write!(f, "invalid target JSON"),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "custom")]
+impl error::Error for TargetRegistryError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::ReadDir { error, .. } | Self::ReadFile { error, .. } => Some(error),
+            Self::NonUtf8Name { .. } => None,
+            Self::InvalidTarget { error, .. } => Some(error),
+        }
+    }
+}
+
+/// An error that occurred while running `rustc --print cfg`, or while parsing or interpreting its
+/// output.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum RustcCfgError {
+    /// The `rustc` process could not be started.
+    Spawn {
+        /// The command that was run.
+        command: String,
+
+        /// The error that occurred.
+        error: std::sync::Arc<std::io::Error>,
+    },
+
+    /// The `rustc` process exited with a non-zero status.
+    CommandFailed {
+        /// The command that was run.
+        command: String,
+
+        /// The exit code of the process, if it exited normally.
+        status: Option<i32>,
+
+        /// The standard error output of the process.
+        stderr: String,
+    },
+
+    /// The output of the `rustc` process wasn't valid UTF-8.
+    NonUtf8Output {
+        /// The command that was run.
+        command: String,
+    },
+
+    /// The host triple could not be found in the output of `rustc -vV`.
+    HostTripleNotFound,
+
+    /// A line in the output was neither a flag nor a key-value pair.
+    InvalidLine {
+        /// The line number, starting from 1.
+        line_number: usize,
+
+        /// The contents of the line.
+        line: String,
+    },
+
+    /// A key required to create a platform was missing.
+    MissingKey {
+        /// The key that was missing.
+        key: &'static str,
+    },
+
+    /// A key that takes a single value was specified more than once.
+    MultipleValues {
+        /// The key that was specified more than once.
+        key: &'static str,
+    },
+
+    /// A key had a value that couldn't be interpreted.
+    InvalidValue {
+        /// The key.
+        key: &'static str,
+
+        /// The value that couldn't be interpreted.
+        value: String,
+    },
+}
+
+impl fmt::Display for RustcCfgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawn { command, .. } => write!(f, "failed to run `{command}`"),
+            Self::CommandFailed {
+                command,
+                status,
+                stderr,
+            } => {
+                match status {
+                    Some(code) => write!(f, "`{command}` exited with code {code}")?,
+                    None => write!(f, "`{command}` was terminated by a signal")?,
+                }
+                if !stderr.trim().is_empty() {
+                    write!(f, ":\n{}", stderr.trim_end())?;
+                }
+                Ok(())
+            }
+            Self::NonUtf8Output { command } => {
+                write!(f, "output of `{command}` was not valid UTF-8")
+            }
+            Self::HostTripleNotFound => {
+                write!(f, "host triple not found in output of `rustc -vV`")
+            }
+            Self::InvalidLine { line_number, line } => {
+                write!(f, "invalid cfg at line {line_number}: `{line}`")
+            }
+            Self::MissingKey { key } => write!(f, "required cfg `{key}` not found"),
+            Self::MultipleValues { key } => {
+                write!(f, "cfg `{key}` was specified more than once")
+            }
+            Self::InvalidValue { key, value } => {
+                write!(f, "invalid value for cfg `{key}`: \"{value}\"")
+            }
+        }
+    }
+}
+
+impl error::Error for RustcCfgError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Spawn { error, .. } => Some(error),
+            Self::CommandFailed { .. }
+            | Self::NonUtf8Output { .. }
+            | Self::HostTripleNotFound
+            | Self::InvalidLine { .. }
+            | Self::MissingKey { .. }
+            | Self::MultipleValues { .. }
+            | Self::InvalidValue { .. } => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{TargetSpecExpression, TargetSpecPlainString};
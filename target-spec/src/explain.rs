@@ -0,0 +1,249 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    normalize::Pred,
+    spec::{eval_predicate, PredicateKind},
+    Platform, TargetSpec, TargetSpecExpression,
+};
+use std::fmt;
+
+impl TargetSpec {
+    /// Evaluates this specification against the given platform, and returns the result along with
+    /// a trace of how each predicate evaluated.
+    ///
+    /// The result is the same as that of [`Self::eval`]. The trace can be used to explain why a
+    /// result was unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use target_spec::{Platform, TargetFeatures, TargetSpec};
+    ///
+    /// let spec: TargetSpec = "cfg(all(unix, target_feature = \"avx2\"))".parse().unwrap();
+    /// let platform = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+    ///
+    /// let trace = spec.eval_explain(&platform);
+    /// assert_eq!(trace.result(), None);
+    /// let unknown: Vec<_> = trace.unknown_predicates().map(|pred| pred.predicate()).collect();
+    /// assert_eq!(unknown, ["target_feature = \"avx2\""]);
+    /// ```
+    pub fn eval_explain(&self, platform: &Platform) -> EvalTrace {
+        match self {
+            TargetSpec::PlainString(plain_str) => {
+                let result = platform.triple_str() == plain_str.as_str();
+                EvalTrace {
+                    spec: plain_str.as_str().to_owned(),
+                    result: Some(result),
+                    predicates: vec![PredicateEval {
+                        predicate: plain_str.as_str().to_owned(),
+                        result: Some(result),
+                        kind: PredicateKind::Triple,
+                    }],
+                }
+            }
+            TargetSpec::Expression(expr) => expr.eval_explain(platform),
+        }
+    }
+}
+
+impl TargetSpecExpression {
+    /// Evaluates this expression against the given platform, and returns the result along with a
+    /// trace of how each predicate evaluated.
+    ///
+    /// For more, see [`TargetSpec::eval_explain`].
+    pub fn eval_explain(&self, platform: &Platform) -> EvalTrace {
+        let mut predicates: Vec<PredicateEval> = Vec::new();
+        for pred in self.inner.predicates() {
+            let predicate = Pred::new(&pred).to_string();
+            // A predicate may be repeated within an expression, but it always evaluates the same
+            // way, so only record it once.
+            if predicates.iter().any(|eval| eval.predicate == predicate) {
+                continue;
+            }
+            let (result, kind) = eval_predicate(&pred, platform);
+            predicates.push(PredicateEval {
+                predicate,
+                result,
+                kind,
+            });
+        }
+        EvalTrace {
+            spec: format!("cfg({})", self.expression_str()),
+            result: self.eval(platform),
+            predicates,
+        }
+    }
+}
+
+/// The result of evaluating a [`TargetSpec`] against a platform, along with how each predicate
+/// within it evaluated.
+///
+/// Returned by [`TargetSpec::eval_explain`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EvalTrace {
+    spec: String,
+    result: Option<bool>,
+    predicates: Vec<PredicateEval>,
+}
+
+impl EvalTrace {
+    /// Returns the target specification that was evaluated, in the form it would be written in
+    /// `Cargo.toml`.
+    pub fn spec(&self) -> &str {
+        &self.spec
+    }
+
+    /// Returns the overall result of the evaluation.
+    ///
+    /// This is `Some(true)` if there's a match, `Some(false)` if there's none, or `None` if the
+    /// result is unknown.
+    pub fn result(&self) -> Option<bool> {
+        self.result
+    }
+
+    /// Returns the predicates that were evaluated, each listed once.
+    ///
+    /// Predicates are listed in the order `cfg-expr` evaluates them, which may differ from the
+    /// order in the source expression. For a plain triple string, this is the triple itself.
+    pub fn predicates(&self) -> &[PredicateEval] {
+        &self.predicates
+    }
+
+    /// Returns the predicates whose results were unknown.
+    ///
+    /// If the overall result is unknown, this is non-empty.
+    pub fn unknown_predicates(&self) -> impl Iterator<Item = &PredicateEval> + '_ {
+        self.predicates.iter().filter(|pred| pred.result.is_none())
+    }
+}
+
+impl fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: {}", self.spec, ResultDisplay(self.result))?;
+        for pred in &self.predicates {
+            writeln!(f, "  {pred}")?;
+        }
+        Ok(())
+    }
+}
+
+/// How a single predicate within a [`TargetSpec`] evaluated.
+///
+/// Part of an [`EvalTrace`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PredicateEval {
+    predicate: String,
+    result: Option<bool>,
+    kind: PredicateKind,
+}
+
+impl PredicateEval {
+    /// Returns the predicate, formatted as it would be within a `cfg()` expression.
+    ///
+    /// For a plain triple string, this is the triple.
+    pub fn predicate(&self) -> &str {
+        &self.predicate
+    }
+
+    /// Returns the result of evaluating this predicate, or `None` if it was unknown.
+    pub fn result(&self) -> Option<bool> {
+        self.result
+    }
+
+    /// Returns a human-readable reason for this result.
+    pub fn reason(&self) -> &'static str {
+        match (self.kind, self.result) {
+            (PredicateKind::Triple, _) => "compared against the target triple",
+            (PredicateKind::Target, _) => "determined by the target triple",
+            (PredicateKind::TargetFeature, Some(true)) => "target feature is enabled",
+            (PredicateKind::TargetFeature, Some(false)) => "target feature is not enabled",
+            (PredicateKind::TargetFeature, None) => "target features for the platform are unknown",
+            (PredicateKind::AlwaysFalse, _) => "always false when evaluated by Cargo",
+            (PredicateKind::Flag, Some(true)) => "flag is set on the platform",
+            (PredicateKind::Flag, _) => "flag is not set on the platform",
+            (PredicateKind::KeyValue, Some(true)) => "key-value pair is set on the platform",
+            (PredicateKind::KeyValue, _) => "key-value pair is not set on the platform",
+        }
+    }
+}
+
+impl fmt::Display for PredicateEval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} ({})",
+            self.predicate,
+            ResultDisplay(self.result),
+            self.reason()
+        )
+    }
+}
+
+struct ResultDisplay(Option<bool>);
+
+impl fmt::Display for ResultDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(true) => f.write_str("true"),
+            Some(false) => f.write_str("false"),
+            None => f.write_str("unknown"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TargetFeatures;
+
+    #[test]
+    fn explain() {
+        let spec: TargetSpec =
+            "cfg(any(windows, all(target_os = \"linux\", target_feature = \"sse2\", foo)))"
+                .parse()
+                .unwrap();
+
+        let platform = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        let trace = spec.eval_explain(&platform);
+        assert_eq!(
+            trace.spec(),
+            "cfg(any(windows, all(target_os = \"linux\", target_feature = \"sse2\", foo)))"
+        );
+        assert_eq!(trace.result(), spec.eval(&platform));
+        assert_eq!(trace.result(), Some(false));
+        let results: Vec<_> = trace
+            .predicates()
+            .iter()
+            .map(|pred| (pred.predicate(), pred.result()))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("target_os = \"linux\"", Some(true)),
+                ("target_feature = \"sse2\"", None),
+                ("foo", Some(false)),
+                ("windows", Some(false)),
+            ]
+        );
+
+        let mut platform = platform;
+        platform.add_flags(["foo"]);
+        let trace = spec.eval_explain(&platform);
+        assert_eq!(trace.result(), None);
+        let unknown: Vec<_> = trace.unknown_predicates().collect();
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(
+            unknown[0].to_string(),
+            "target_feature = \"sse2\": unknown (target features for the platform are unknown)"
+        );
+
+        let spec: TargetSpec = "x86_64-unknown-linux-gnu".parse().unwrap();
+        let trace = spec.eval_explain(&platform);
+        assert_eq!(trace.result(), Some(true));
+        assert_eq!(
+            trace.to_string(),
+            "x86_64-unknown-linux-gnu: true\n  x86_64-unknown-linux-gnu: true (compared against the target triple)\n"
+        );
+    }
+}
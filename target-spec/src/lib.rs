@@ -34,13 +34,14 @@
 //! assert_eq!(eval("x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc").unwrap(), Some(false));
 //! ```
 //!
-//! For more advanced usage, see [`Platform`] and [`TargetSpec`].
+//! For more advanced usage, see [`Platform`] and [`TargetSpec`]. To evaluate specifications
+//! against exactly what a toolchain reports, see [`RustcCfg`].
 //!
 //! ## Optional features
 //!
 //! * **`custom`**: Adds support for [custom
 //!   targets](https://docs.rust-embedded.org/embedonomicon/custom-target.html) via
-//!   [`Platform::new_custom`].
+//!   [`Platform::new_custom`], and discovery of target JSON files via [`TargetRegistry`].
 //! * **`summaries`**: Adds the [`summaries`] module to enable serialization of [`Platform`] and
 //!   [`TargetFeatures`].
 //! * **`proptest1`**: Enables support for property-based testing of [`Platform`] and
@@ -63,20 +64,32 @@
 #![forbid(unsafe_code)]
 #![cfg_attr(doc_cfg, feature(doc_cfg, doc_auto_cfg))]
 
+mod builtin;
 #[cfg(feature = "custom")]
 mod custom;
 pub mod errors;
+mod explain;
+mod normalize;
 mod platform;
 #[cfg(feature = "proptest1")]
 mod proptest_helpers;
+mod rustc_cfg;
 mod simple_eval;
 mod spec;
 #[cfg(feature = "summaries")]
 pub mod summaries;
+mod target_features;
+#[cfg(feature = "custom")]
+mod target_registry;
 mod triple;
 
+pub use builtin::*;
 pub use errors::Error;
+pub use explain::*;
 pub use platform::*;
+pub use rustc_cfg::*;
 pub use simple_eval::*;
 pub use spec::*;
+#[cfg(feature = "custom")]
+pub use target_registry::*;
 pub use triple::*;
@@ -0,0 +1,544 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Normalization of and reasoning about `cfg()` expressions.
+
+use crate::{TargetSpec, TargetSpecExpression};
+use cfg_expr::{
+    expr::{Logic, TargetMatcher},
+    targets::{Endian, TargetInfo, ALL_BUILTINS},
+    Predicate, TargetPredicate,
+};
+use std::fmt;
+
+impl TargetSpec {
+    /// Returns a normalized version of this specification.
+    ///
+    /// Plain strings are returned as is. For expressions, see
+    /// [`TargetSpecExpression::normalize`].
+    pub fn normalize(&self) -> TargetSpec {
+        match self {
+            TargetSpec::Expression(expr) => TargetSpec::Expression(expr.normalize()),
+            TargetSpec::PlainString(_) => self.clone(),
+        }
+    }
+
+    /// Returns true if every platform this specification matches is also matched by `other`.
+    ///
+    /// Platforms are drawn from the builtin target table, so two specifications that only differ
+    /// for custom or heuristically determined targets are considered to be the same. For each
+    /// builtin target, every combination of target features, flags and key-value cfgs is
+    /// considered. The `test`, `debug_assertions`, `proc_macro` and `feature` predicates always
+    /// evaluate to false, as with [`TargetSpec::eval`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use target_spec::TargetSpec;
+    ///
+    /// let linux = TargetSpec::new("cfg(target_os = \"linux\")").unwrap();
+    /// let unix = TargetSpec::new("cfg(unix)").unwrap();
+    /// assert!(linux.implies(&unix));
+    /// assert!(!unix.implies(&linux));
+    ///
+    /// // Plain strings are supported as well.
+    /// let triple = TargetSpec::new("x86_64-unknown-linux-gnu").unwrap();
+    /// assert!(triple.implies(&linux));
+    /// ```
+    pub fn implies(&self, other: &TargetSpec) -> bool {
+        // self implies other iff self && !other is never satisfied.
+        let node = Node::All(vec![self.to_node(), Node::Not(Box::new(other.to_node()))]);
+        !ALL_BUILTINS
+            .iter()
+            .any(|target_info| node.bind_target(target_info).is_satisfiable())
+    }
+
+    /// Returns true if this specification matches exactly the same platforms as `other`.
+    ///
+    /// For more about how this is determined, see [`Self::implies`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use target_spec::TargetSpec;
+    ///
+    /// let a = TargetSpec::new("cfg(not(any(windows, unix)))").unwrap();
+    /// let b = TargetSpec::new("cfg(all(not(unix), not(windows)))").unwrap();
+    /// assert!(a.is_equivalent(&b));
+    /// ```
+    pub fn is_equivalent(&self, other: &TargetSpec) -> bool {
+        self.implies(other) && other.implies(self)
+    }
+
+    fn to_node(&self) -> Node {
+        match self {
+            TargetSpec::Expression(expr) => expr.to_node(),
+            TargetSpec::PlainString(plain_str) => {
+                Node::Pred(Pred::Triple(plain_str.as_str().to_owned()))
+            }
+        }
+    }
+}
+
+impl TargetSpecExpression {
+    /// Returns a normalized version of this expression.
+    ///
+    /// Normalization:
+    ///
+    /// * flattens nested `all()` and `any()` operators, e.g. `all(a, all(b, c))` becomes
+    ///   `all(a, b, c)`
+    /// * removes duplicate operands, e.g. `any(a, a)` becomes `a`
+    /// * removes double negations, e.g. `not(not(a))` becomes `a`
+    /// * folds tautologies and contradictions: `all(a, not(a))` becomes `any()` (always false),
+    ///   and `any(a, not(a))` becomes `all()` (always true)
+    /// * unwraps `all()` and `any()` operators with a single operand
+    /// * sorts the operands of `all()` and `any()`
+    ///
+    /// Predicates are printed in their canonical form: for example, `target_family = "unix"`
+    /// becomes `unix`. Two expressions that normalize to the same string are equivalent, though
+    /// the converse doesn't hold: see [`TargetSpec::is_equivalent`] for a semantic comparison.
+    /// Normalization does not depend on the builtin target table.
+    ///
+    /// As with other expressions, [`TargetSpecExpression::expression_str`] returns the result
+    /// without the surrounding `cfg()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use target_spec::TargetSpecExpression;
+    ///
+    /// let expr = TargetSpecExpression::new(
+    ///     "cfg(all(unix, all(target_arch = \"x86_64\", not(not(target_os = \"linux\")))))",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(
+    ///     expr.normalize().expression_str(),
+    ///     "all(target_arch = \"x86_64\", target_os = \"linux\", unix)",
+    /// );
+    /// ```
+    pub fn normalize(&self) -> TargetSpecExpression {
+        let normalized = format!("cfg({})", self.to_node().normalize());
+        TargetSpecExpression::new(&normalized).expect("normalized expressions are valid")
+    }
+
+    fn to_node(&self) -> Node {
+        self.inner.eval(|pred| Node::Pred(Pred::new(pred)))
+    }
+}
+
+/// A `cfg()` expression as a tree.
+///
+/// `all()` represents true, and `any()` represents false.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Node {
+    All(Vec<Node>),
+    Any(Vec<Node>),
+    Not(Box<Node>),
+    Pred(Pred),
+}
+
+/// An owned version of `cfg_expr::Predicate`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Pred {
+    Target(TargetPredicate),
+    TargetFeature(String),
+    Test,
+    DebugAssertions,
+    ProcMacro,
+    Feature(String),
+    Flag(String),
+    KeyValue(String, String),
+    // A plain string rather than a predicate, used for implication checks.
+    Triple(String),
+}
+
+impl Pred {
+    pub(crate) fn new(pred: &Predicate<'_>) -> Self {
+        match pred {
+            Predicate::Target(target) => Pred::Target(target.clone()),
+            Predicate::TargetFeature(feature) => Pred::TargetFeature((*feature).to_owned()),
+            Predicate::Test => Pred::Test,
+            Predicate::DebugAssertions => Pred::DebugAssertions,
+            Predicate::ProcMacro => Pred::ProcMacro,
+            Predicate::Feature(feature) => Pred::Feature((*feature).to_owned()),
+            Predicate::Flag(flag) => Pred::Flag((*flag).to_owned()),
+            Predicate::KeyValue { key, val } => {
+                Pred::KeyValue((*key).to_owned(), (*val).to_owned())
+            }
+        }
+    }
+
+    /// Returns the value of this predicate on a builtin target, or `None` if it doesn't only
+    /// depend on the target.
+    fn eval_target(&self, target_info: &TargetInfo) -> Option<bool> {
+        match self {
+            Pred::Target(target) => Some(target_info.matches(target)),
+            Pred::Triple(triple_str) => Some(target_info.triple.as_str() == triple_str),
+            // These always evaluate to false, matching TargetSpecExpression::eval.
+            Pred::Test | Pred::DebugAssertions | Pred::ProcMacro | Pred::Feature(_) => Some(false),
+            Pred::TargetFeature(_) | Pred::Flag(_) | Pred::KeyValue(..) => None,
+        }
+    }
+}
+
+impl Node {
+    const TRUE: Node = Node::All(Vec::new());
+    const FALSE: Node = Node::Any(Vec::new());
+
+    fn constant(value: bool) -> Self {
+        if value {
+            Self::TRUE
+        } else {
+            Self::FALSE
+        }
+    }
+
+    fn normalize(&self) -> Self {
+        match self {
+            Node::All(operands) => Self::normalize_operands(operands, true),
+            Node::Any(operands) => Self::normalize_operands(operands, false),
+            Node::Not(operand) => match operand.normalize() {
+                Node::Not(inner) => *inner,
+                Node::All(operands) if operands.is_empty() => Self::FALSE,
+                Node::Any(operands) if operands.is_empty() => Self::TRUE,
+                other => Node::Not(Box::new(other)),
+            },
+            Node::Pred(pred) => Node::Pred(pred.clone()),
+        }
+    }
+
+    /// Normalizes the operands of `all()` (if `is_all` is true) or `any()`.
+    fn normalize_operands(operands: &[Node], is_all: bool) -> Self {
+        // The identity element is all() for all(), and any() for any(). The absorbing element is
+        // the other one.
+        let absorbing = Self::constant(!is_all);
+
+        let mut out: Vec<Node> = Vec::with_capacity(operands.len());
+        let push = |node: Node, out: &mut Vec<Node>| {
+            if !out.contains(&node) {
+                out.push(node);
+            }
+        };
+        for operand in operands {
+            match (operand.normalize(), is_all) {
+                (Node::All(inner), true) | (Node::Any(inner), false) => {
+                    for node in inner {
+                        push(node, &mut out);
+                    }
+                }
+                (node, _) if node == absorbing => return absorbing,
+                (node, _) => push(node, &mut out),
+            }
+        }
+
+        // a and not(a) together are absorbing.
+        let has_complement = out.iter().any(|node| match node {
+            Node::Not(inner) => out.contains(inner),
+            _ => false,
+        });
+        if has_complement {
+            return absorbing;
+        }
+
+        // Operands are sorted by how they're printed, which is cheap enough for typical
+        // expressions.
+        out.sort_by_cached_key(|node| node.to_string());
+        if out.len() == 1 {
+            out.pop().expect("length is 1")
+        } else if is_all {
+            Node::All(out)
+        } else {
+            Node::Any(out)
+        }
+    }
+
+    /// Replaces predicates that only depend on the target with their values.
+    fn bind_target(&self, target_info: &TargetInfo) -> Self {
+        self.map_preds(&mut |pred| pred.eval_target(target_info).map(Self::constant))
+    }
+
+    /// Returns true if some assignment of the remaining predicates makes this node true.
+    fn is_satisfiable(&self) -> bool {
+        let node = self.normalize();
+        if node == Self::TRUE {
+            return true;
+        }
+        if node == Self::FALSE {
+            return false;
+        }
+
+        let pred = node
+            .first_pred()
+            .expect("non-constant node has a predicate")
+            .clone();
+        [true, false].into_iter().any(|value| {
+            node.map_preds(&mut |other| (*other == pred).then(|| Self::constant(value)))
+                .is_satisfiable()
+        })
+    }
+
+    fn first_pred(&self) -> Option<&Pred> {
+        match self {
+            Node::All(operands) | Node::Any(operands) => operands.iter().find_map(Node::first_pred),
+            Node::Not(operand) => operand.first_pred(),
+            Node::Pred(pred) => Some(pred),
+        }
+    }
+
+    fn map_preds(&self, f: &mut impl FnMut(&Pred) -> Option<Node>) -> Self {
+        match self {
+            Node::All(operands) => {
+                Node::All(operands.iter().map(|node| node.map_preds(f)).collect())
+            }
+            Node::Any(operands) => {
+                Node::Any(operands.iter().map(|node| node.map_preds(f)).collect())
+            }
+            Node::Not(operand) => Node::Not(Box::new(operand.map_preds(f))),
+            Node::Pred(pred) => f(pred).unwrap_or_else(|| Node::Pred(pred.clone())),
+        }
+    }
+}
+
+// Builds a tree out of an expression.
+impl Logic for Node {
+    fn top() -> Self {
+        Self::TRUE
+    }
+
+    fn bottom() -> Self {
+        Self::FALSE
+    }
+
+    fn and(self, other: Self) -> Self {
+        match self {
+            Node::All(mut operands) => {
+                operands.push(other);
+                Node::All(operands)
+            }
+            node => Node::All(vec![other, node]),
+        }
+    }
+
+    fn or(self, other: Self) -> Self {
+        match self {
+            Node::Any(mut operands) => {
+                operands.push(other);
+                Node::Any(operands)
+            }
+            node => Node::Any(vec![other, node]),
+        }
+    }
+
+    fn not(self) -> Self {
+        Node::Not(Box::new(self))
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_operands = |f: &mut fmt::Formatter<'_>, name: &str, operands: &[Node]| {
+            write!(f, "{name}(")?;
+            for (idx, operand) in operands.iter().enumerate() {
+                if idx > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{operand}")?;
+            }
+            write!(f, ")")
+        };
+
+        match self {
+            Node::All(operands) => write_operands(f, "all", operands),
+            Node::Any(operands) => write_operands(f, "any", operands),
+            Node::Not(operand) => write!(f, "not({operand})"),
+            Node::Pred(pred) => write!(f, "{pred}"),
+        }
+    }
+}
+
+impl fmt::Display for Pred {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pred::Target(target) => match target {
+                TargetPredicate::Abi(abi) => write!(f, "target_abi = \"{abi}\""),
+                TargetPredicate::Arch(arch) => write!(f, "target_arch = \"{arch}\""),
+                TargetPredicate::Endian(Endian::big) => write!(f, "target_endian = \"big\""),
+                TargetPredicate::Endian(Endian::little) => {
+                    write!(f, "target_endian = \"little\"")
+                }
+                TargetPredicate::Env(env) => write!(f, "target_env = \"{env}\""),
+                TargetPredicate::Family(family) => match family.as_str() {
+                    "unix" | "windows" => write!(f, "{family}"),
+                    _ => write!(f, "target_family = \"{family}\""),
+                },
+                TargetPredicate::HasAtomic(has_atomic) => {
+                    write!(f, "target_has_atomic = \"{has_atomic}\"")
+                }
+                TargetPredicate::Os(os) => write!(f, "target_os = \"{os}\""),
+                TargetPredicate::Panic(panic) => write!(f, "panic = \"{panic}\""),
+                TargetPredicate::PointerWidth(width) => {
+                    write!(f, "target_pointer_width = \"{width}\"")
+                }
+                TargetPredicate::Vendor(vendor) => write!(f, "target_vendor = \"{vendor}\""),
+            },
+            Pred::TargetFeature(feature) => write!(f, "target_feature = \"{feature}\""),
+            Pred::Test => write!(f, "test"),
+            Pred::DebugAssertions => write!(f, "debug_assertions"),
+            Pred::ProcMacro => write!(f, "proc_macro"),
+            Pred::Feature(feature) => write!(f, "feature = \"{feature}\""),
+            Pred::Flag(flag) => write!(f, "{flag}"),
+            Pred::KeyValue(key, value) => write!(f, "{key} = \"{value}\""),
+            // Only used while checking implication, to order operands.
+            Pred::Triple(triple_str) => write!(f, "{triple_str}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(input: &str) -> String {
+        let normalized = TargetSpecExpression::new(input)
+            .unwrap_or_else(|err| panic!("{input} is valid: {err:?}"))
+            .normalize();
+        format!("cfg({})", normalized.expression_str())
+    }
+
+    fn spec(input: &str) -> TargetSpec {
+        TargetSpec::new(input.to_owned()).unwrap_or_else(|err| panic!("{input} is valid: {err}"))
+    }
+
+    #[test]
+    fn normalize_expressions() {
+        for (input, expected) in [
+            ("cfg(unix)", "cfg(unix)"),
+            ("cfg(target_family = \"unix\")", "cfg(unix)"),
+            ("cfg(target_family = \"wasm\")", "cfg(target_family = \"wasm\")"),
+            // Sorting.
+            (
+                "cfg(all(target_os = \"linux\", target_arch = \"x86_64\", unix))",
+                "cfg(all(target_arch = \"x86_64\", target_os = \"linux\", unix))",
+            ),
+            (
+                "cfg(all(unix, target_os = \"linux\", target_arch = \"x86_64\"))",
+                "cfg(all(target_arch = \"x86_64\", target_os = \"linux\", unix))",
+            ),
+            // Flattening.
+            (
+                "cfg(any(windows, any(unix, any(target_os = \"none\"))))",
+                "cfg(any(target_os = \"none\", unix, windows))",
+            ),
+            (
+                "cfg(all(windows, any(unix, foo)))",
+                "cfg(all(any(foo, unix), windows))",
+            ),
+            // Duplicates, including duplicates spelled differently.
+            ("cfg(any(unix, target_family = \"unix\"))", "cfg(unix)"),
+            ("cfg(all(foo, bar, foo))", "cfg(all(bar, foo))"),
+            // Double negations and single operands.
+            ("cfg(not(not(foo)))", "cfg(foo)"),
+            ("cfg(all(any(not(not(windows)))))", "cfg(windows)"),
+            ("cfg(not(all(unix)))", "cfg(not(unix))"),
+            // Tautologies and contradictions.
+            ("cfg(any(unix, not(unix)))", "cfg(all())"),
+            ("cfg(all(unix, not(unix)))", "cfg(any())"),
+            ("cfg(all(foo, all(), bar))", "cfg(all(bar, foo))"),
+            ("cfg(any(foo, all()))", "cfg(all())"),
+            ("cfg(all(foo, any()))", "cfg(any())"),
+            ("cfg(not(any()))", "cfg(all())"),
+            ("cfg(any(foo, not(any(bar, not(bar)))))", "cfg(foo)"),
+            // Other predicates.
+            (
+                "cfg(all(target_feature = \"avx2\", key = \"value\", debug_assertions, feature = \"foo\"))",
+                "cfg(all(debug_assertions, feature = \"foo\", key = \"value\", target_feature = \"avx2\"))",
+            ),
+            (
+                "cfg(all(target_endian = \"big\", target_has_atomic = \"ptr\", target_pointer_width = \"32\", panic = \"abort\"))",
+                "cfg(all(panic = \"abort\", target_endian = \"big\", target_has_atomic = \"ptr\", target_pointer_width = \"32\"))",
+            ),
+        ] {
+            assert_eq!(normalize(input), expected, "for input {input}");
+            // Normalization is idempotent.
+            assert_eq!(normalize(expected), expected, "for expected {expected}");
+        }
+    }
+
+    #[test]
+    fn implication() {
+        for (a, b, a_implies_b, b_implies_a) in [
+            ("cfg(target_os = \"linux\")", "cfg(unix)", true, false),
+            ("cfg(unix)", "cfg(not(windows))", true, false),
+            ("cfg(all(unix, foo))", "cfg(unix)", true, false),
+            ("cfg(foo)", "cfg(any(foo, bar))", true, false),
+            ("cfg(foo)", "cfg(bar)", false, false),
+            ("cfg(k = \"v\")", "cfg(k = \"w\")", false, false),
+            (
+                "cfg(target_feature = \"avx2\")",
+                "cfg(target_feature = \"sse2\")",
+                false,
+                false,
+            ),
+            // Equivalences.
+            (
+                "cfg(not(any(unix, windows)))",
+                "cfg(all(not(unix), not(windows)))",
+                true,
+                true,
+            ),
+            // macOS is always Apple. (This depends on the builtin target table.)
+            (
+                "cfg(target_os = \"macos\")",
+                "cfg(all(target_os = \"macos\", target_vendor = \"apple\"))",
+                true,
+                true,
+            ),
+            // Always false on every builtin target.
+            (
+                "cfg(all(windows, unix))",
+                "cfg(target_os = \"linuz\")",
+                true,
+                true,
+            ),
+            ("cfg(debug_assertions)", "cfg(any())", true, true),
+            // Plain strings.
+            ("x86_64-pc-windows-msvc", "cfg(windows)", true, false),
+            (
+                "x86_64-unknown-linux-gnu",
+                "cfg(all(target_arch = \"x86_64\", target_os = \"linux\", target_env = \"gnu\"))",
+                true,
+                false,
+            ),
+            (
+                "x86_64-unknown-linux-gnu",
+                "i686-unknown-linux-gnu",
+                false,
+                false,
+            ),
+        ] {
+            let (a_spec, b_spec) = (spec(a), spec(b));
+            assert_eq!(a_spec.implies(&b_spec), a_implies_b, "{a} implies {b}");
+            assert_eq!(b_spec.implies(&a_spec), b_implies_a, "{b} implies {a}");
+            assert_eq!(
+                a_spec.is_equivalent(&b_spec),
+                a_implies_b && b_implies_a,
+                "{a} is equivalent to {b}"
+            );
+        }
+    }
+
+    #[test]
+    fn normalize_preserves_meaning() {
+        for input in [
+            "cfg(any(unix, all(target_os = \"windows\", not(not(target_env = \"gnu\")))))",
+            "cfg(all(foo, any(bar, all(foo, not(bar)))))",
+            "cfg(not(any(windows, all(unix, target_feature = \"avx2\"))))",
+        ] {
+            let original = spec(input);
+            assert!(
+                original.is_equivalent(&original.normalize()),
+                "normalizing {input} preserves meaning"
+            );
+        }
+    }
+}
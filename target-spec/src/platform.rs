@@ -1,8 +1,12 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{Error, Triple};
-use std::{borrow::Cow, collections::BTreeSet, ops::Deref};
+use crate::{target_features::is_enabled_or_implied, Error, RustcCfg, Triple};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    ops::Deref,
+};
 
 // This is generated by the build script.
 include!(concat!(env!("OUT_DIR"), "/current_platform.rs"));
@@ -11,7 +15,7 @@ include!(concat!(env!("OUT_DIR"), "/current_platform.rs"));
 ///
 /// # Standard and custom platforms
 ///
-/// `target-spec` recognizes two kinds of platforms:
+/// `target-spec` recognizes three kinds of platforms:
 ///
 /// * **Standard platforms:** These platforms are only specified by their triple string. For
 ///   example, the platform `x86_64-unknown-linux-gnu` is a standard platform since it is recognized
@@ -28,12 +32,18 @@ include!(concat!(env!("OUT_DIR"), "/current_platform.rs"));
 ///   in the format [defined by
 ///   Rust](https://docs.rust-embedded.org/embedonomicon/custom-target.html). Custom platforms are
 ///   used for targets not recognized by Rust.
+///
+/// * **Platforms from `rustc --print cfg`:** These platforms are specified via a triple string and
+///   the output of `rustc --print cfg`, which reflects the exact toolchain in use, as well as
+///   options like `-C target-feature`. See [`RustcCfg`] for more.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 #[must_use]
 pub struct Platform {
     triple: Triple,
     target_features: TargetFeatures,
     flags: BTreeSet<Cow<'static, str>>,
+    key_values: BTreeMap<Cow<'static, str>, BTreeSet<Cow<'static, str>>>,
+    strict_target_features: bool,
 }
 
 impl Platform {
@@ -76,6 +86,8 @@ impl Platform {
             triple,
             target_features,
             flags: BTreeSet::new(),
+            key_values: BTreeMap::new(),
+            strict_target_features: false,
         })
     }
 
@@ -85,6 +97,8 @@ impl Platform {
             triple,
             target_features,
             flags: BTreeSet::new(),
+            key_values: BTreeMap::new(),
+            strict_target_features: false,
         }
     }
 
@@ -100,6 +114,42 @@ impl Platform {
             triple,
             target_features,
             flags: BTreeSet::new(),
+            key_values: BTreeMap::new(),
+            strict_target_features: false,
+        })
+    }
+
+    /// Creates a new `Platform` from the given triple string and the output of `rustc --print cfg`
+    /// for it.
+    ///
+    /// The target features, flags and key-value cfgs are taken from `cfg`. Target predicates like
+    /// `target_os` are evaluated against `cfg` rather than the builtin platform table, so the
+    /// triple string does not have to be known to `target-spec`.
+    ///
+    /// For an example, see [`RustcCfg`].
+    pub fn from_rustc_cfg(
+        triple_str: impl Into<Cow<'static, str>>,
+        cfg: RustcCfg,
+    ) -> Result<Self, Error> {
+        let target_features = cfg.target_features();
+        let flags = cfg
+            .flags()
+            .map(|flag| Cow::Owned(flag.to_owned()))
+            .collect();
+        let mut key_values: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        for (key, value) in cfg.extra_key_values() {
+            key_values
+                .entry(Cow::Owned(key.to_owned()))
+                .or_default()
+                .insert(Cow::Owned(value.to_owned()));
+        }
+        let triple = Triple::from_rustc_cfg(triple_str, cfg).map_err(Error::RustcCfg)?;
+        Ok(Self {
+            triple,
+            target_features,
+            flags,
+            key_values,
+            strict_target_features: false,
         })
     }
 
@@ -114,6 +164,89 @@ impl Platform {
         self.flags.extend(flags.into_iter().map(|s| s.into()));
     }
 
+    /// Adds a set of key-value cfgs to accept.
+    ///
+    /// A key-value cfg is a pair like the `foo = "bar"` in `cfg(foo = "bar")`, as set by
+    /// `--cfg 'foo="bar"'` in `RUSTFLAGS`. A key may be set to several values, in which case
+    /// `cfg(key = "value")` evaluates to true for each of them.
+    ///
+    /// Key-value cfgs that aren't set always evaluate to false. Keys that `target-spec` interprets
+    /// itself, such as `target_os` or `target_feature`, are not affected by this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use target_spec::{Platform, TargetFeatures, TargetSpec};
+    ///
+    /// let mut platform = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+    /// platform.add_key_values([("tokio_backend", "io-uring")]);
+    ///
+    /// let spec = TargetSpec::new("cfg(tokio_backend = \"io-uring\")".to_owned()).unwrap();
+    /// assert_eq!(spec.eval(&platform), Some(true));
+    /// let spec = TargetSpec::new("cfg(tokio_backend = \"epoll\")".to_owned()).unwrap();
+    /// assert_eq!(spec.eval(&platform), Some(false));
+    /// ```
+    pub fn add_key_values(
+        &mut self,
+        key_values: impl IntoIterator<
+            Item = (impl Into<Cow<'static, str>>, impl Into<Cow<'static, str>>),
+        >,
+    ) {
+        for (key, value) in key_values {
+            self.key_values
+                .entry(key.into())
+                .or_default()
+                .insert(value.into());
+        }
+    }
+
+    /// Sets whether target features are matched strictly.
+    ///
+    /// By default, a target feature is considered enabled if it's in this platform's
+    /// [`TargetFeatures`], or if it's implied by one that is. For example, on x86_64,
+    /// `cfg(target_feature = "sse2")` evaluates to true if `avx2` is enabled. (See
+    /// [`TargetFeatures::with_implied`] for more.)
+    ///
+    /// In strict mode, only the target features specified are considered enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use target_spec::{Platform, TargetFeatures, TargetSpec};
+    ///
+    /// let mut platform =
+    ///     Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::features(["avx2"])).unwrap();
+    /// let spec = TargetSpec::new("cfg(target_feature = \"sse2\")").unwrap();
+    /// assert_eq!(spec.eval(&platform), Some(true));
+    ///
+    /// platform.set_strict_target_features(true);
+    /// assert_eq!(spec.eval(&platform), Some(false));
+    /// ```
+    pub fn set_strict_target_features(&mut self, strict: bool) {
+        self.strict_target_features = strict;
+    }
+
+    /// Returns true if target features are matched strictly.
+    ///
+    /// For more, see [`Self::set_strict_target_features`].
+    pub fn strict_target_features(&self) -> bool {
+        self.strict_target_features
+    }
+
+    /// Returns `Some(true)` if this target feature is enabled, `Some(false)` if it isn't, and
+    /// `None` if the set of target features is unknown.
+    ///
+    /// Unless [strict mode](Self::set_strict_target_features) is enabled, features implied by
+    /// enabled ones are also considered enabled.
+    pub fn has_target_feature(&self, feature: &str) -> Option<bool> {
+        match &self.target_features {
+            TargetFeatures::Features(features) if !self.strict_target_features => {
+                Some(is_enabled_or_implied(&self.triple, features, feature))
+            }
+            target_features => target_features.matches(feature),
+        }
+    }
+
     /// Returns the target triple string for this platform.
     pub fn triple_str(&self) -> &str {
         self.triple.as_str()
@@ -129,6 +262,21 @@ impl Platform {
         self.flags.contains(flag.as_ref())
     }
 
+    /// Returns the set of key-value cfgs enabled for this platform, sorted by key and then by
+    /// value.
+    pub fn key_values(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.key_values
+            .iter()
+            .flat_map(|(key, values)| values.iter().map(move |value| (key.deref(), value.deref())))
+    }
+
+    /// Returns true if this key-value cfg was set with `add_key_values`.
+    pub fn has_key_value(&self, key: impl AsRef<str>, value: impl AsRef<str>) -> bool {
+        self.key_values
+            .get(key.as_ref())
+            .is_some_and(|values| values.contains(value.as_ref()))
+    }
+
     /// Returns true if this is a standard platform.
     ///
     /// A standard platform can be either builtin, or heuristically determined.
@@ -191,6 +339,16 @@ impl Platform {
         self.triple.is_custom()
     }
 
+    /// Returns true if this platform was created from the output of `rustc --print cfg`.
+    pub fn is_rustc_cfg(&self) -> bool {
+        self.triple.is_rustc_cfg()
+    }
+
+    /// Returns the output of `rustc --print cfg` this platform was created from, if any.
+    pub fn rustc_cfg(&self) -> Option<&RustcCfg> {
+        self.triple.rustc_cfg()
+    }
+
     /// Returns the underlying [`Triple`].
     pub fn triple(&self) -> &Triple {
         &self.triple
@@ -0,0 +1,636 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{errors::RustcCfgError, Error, Platform, TargetFeatures};
+use cfg_expr::targets::{
+    Abi, Arch, Endian, Env, Families, Family, HasAtomic, HasAtomics, Os, Panic, TargetInfo,
+    Triple as TargetInfoTriple, Vendor,
+};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    ffi::{OsStr, OsString},
+    fmt,
+    process::Command,
+    str::FromStr,
+    sync::Arc,
+};
+
+/// The configuration of a target, as printed by `rustc --print cfg`.
+///
+/// `rustc --print cfg` lists every `cfg` that is set when compiling for a target, one per line.
+/// Each line is either a bare flag like `unix`, or a key and a quoted value like
+/// `target_os="linux"`. Keys such as `target_feature` and `target_family` may appear more than
+/// once.
+///
+/// Unlike builtin targets, which are looked up in a table bundled with `target-spec`, a `RustcCfg`
+/// reflects exactly what a particular toolchain reports, including the effects of `--target`,
+/// `-C target-cpu` and `-C target-feature`.
+///
+/// A `RustcCfg` can be parsed from captured output with [`RustcCfg::parse`], or obtained by
+/// running `rustc` through [`RustcCfgCommand`]. To evaluate target specifications against it,
+/// convert it to a [`Platform`] with [`Platform::from_rustc_cfg`].
+///
+/// # Examples
+///
+/// ```
+/// use target_spec::{Platform, RustcCfg, TargetSpec};
+///
+/// let output = r#"
+/// panic="unwind"
+/// target_arch="x86_64"
+/// target_endian="little"
+/// target_env="gnu"
+/// target_family="unix"
+/// target_feature="sse2"
+/// target_os="linux"
+/// target_pointer_width="64"
+/// target_vendor="unknown"
+/// unix
+/// "#;
+///
+/// let cfg = RustcCfg::parse(output).unwrap();
+/// assert_eq!(cfg.values("target_os").collect::<Vec<_>>(), ["linux"]);
+///
+/// let platform = Platform::from_rustc_cfg("x86_64-unknown-linux-gnu", cfg).unwrap();
+/// let spec = TargetSpec::new("cfg(all(unix, target_feature = \"sse2\"))".to_owned()).unwrap();
+/// assert_eq!(spec.eval(&platform), Some(true));
+/// ```
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RustcCfg {
+    flags: BTreeSet<String>,
+    key_values: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl RustcCfg {
+    /// Parses the output of `rustc --print cfg`.
+    ///
+    /// Blank lines are ignored. Returns an error if a line isn't a flag or a key-value pair.
+    pub fn parse(input: &str) -> Result<Self, RustcCfgError> {
+        let mut cfg = Self::default();
+        for (idx, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || RustcCfgError::InvalidLine {
+                line_number: idx + 1,
+                line: line.to_owned(),
+            };
+            match line.split_once('=') {
+                Some((key, value)) => {
+                    let key = key.trim();
+                    let value = unquote(value.trim()).ok_or_else(invalid)?;
+                    if !is_cfg_name(key) {
+                        return Err(invalid());
+                    }
+                    cfg.key_values
+                        .entry(key.to_owned())
+                        .or_default()
+                        .insert(value);
+                }
+                None => {
+                    if !is_cfg_name(line) {
+                        return Err(invalid());
+                    }
+                    cfg.flags.insert(line.to_owned());
+                }
+            }
+        }
+        Ok(cfg)
+    }
+
+    /// Returns the bare flags set for this target, such as `unix` or `debug_assertions`.
+    pub fn flags(&self) -> impl ExactSizeIterator<Item = &str> {
+        self.flags.iter().map(|flag| flag.as_str())
+    }
+
+    /// Returns true if this bare flag is set.
+    pub fn has_flag(&self, flag: impl AsRef<str>) -> bool {
+        self.flags.contains(flag.as_ref())
+    }
+
+    /// Returns all key-value pairs set for this target, sorted by key and then by value.
+    pub fn key_values(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.key_values.iter().flat_map(|(key, values)| {
+            values
+                .iter()
+                .map(move |value| (key.as_str(), value.as_str()))
+        })
+    }
+
+    /// Returns the values set for this key, in sorted order.
+    ///
+    /// Returns an empty iterator if the key isn't set.
+    pub fn values(&self, key: impl AsRef<str>) -> impl Iterator<Item = &str> {
+        self.key_values
+            .get(key.as_ref())
+            .into_iter()
+            .flat_map(|values| values.iter().map(|value| value.as_str()))
+    }
+
+    /// Returns the target features enabled for this target, as listed by `target_feature` keys.
+    pub fn target_features(&self) -> TargetFeatures {
+        TargetFeatures::features(
+            self.values("target_feature")
+                .map(|feature| Cow::Owned(feature.to_owned())),
+        )
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    /// Returns the key-value pairs that aren't interpreted as part of the target or its features.
+    pub(crate) fn extra_key_values(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.key_values()
+            .filter(|(key, _)| !TARGET_KEYS.contains(key))
+    }
+
+    fn single_value(&self, key: &'static str) -> Result<Option<&str>, RustcCfgError> {
+        let mut values = self.values(key);
+        match (values.next(), values.next()) {
+            (None, _) => Ok(None),
+            (Some(value), None) => Ok(Some(value)),
+            (Some(_), Some(_)) => Err(RustcCfgError::MultipleValues { key }),
+        }
+    }
+
+    fn required_value(&self, key: &'static str) -> Result<&str, RustcCfgError> {
+        self.single_value(key)?
+            .ok_or(RustcCfgError::MissingKey { key })
+    }
+
+    pub(crate) fn to_target_info(
+        &self,
+        triple_str: Cow<'static, str>,
+    ) -> Result<TargetInfo, RustcCfgError> {
+        let owned = |value: &str| Cow::<'static, str>::Owned(value.to_owned());
+
+        let arch = Arch::new(owned(self.required_value("target_arch")?));
+        let pointer_width = self.required_value("target_pointer_width")?;
+        let pointer_width =
+            pointer_width
+                .parse::<u8>()
+                .map_err(|_| RustcCfgError::InvalidValue {
+                    key: "target_pointer_width",
+                    value: pointer_width.to_owned(),
+                })?;
+        let endian = match self.single_value("target_endian")? {
+            None | Some("little") => Endian::little,
+            Some("big") => Endian::big,
+            Some(other) => {
+                return Err(RustcCfgError::InvalidValue {
+                    key: "target_endian",
+                    value: other.to_owned(),
+                })
+            }
+        };
+        let has_atomics = self
+            .values("target_has_atomic")
+            .map(|value| {
+                value
+                    .parse::<HasAtomic>()
+                    .map_err(|_| RustcCfgError::InvalidValue {
+                        key: "target_has_atomic",
+                        value: value.to_owned(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(TargetInfo {
+            triple: TargetInfoTriple::new(triple_str),
+            os: self.single_value("target_os")?.map(|os| Os::new(owned(os))),
+            abi: self
+                .single_value("target_abi")?
+                .map(|abi| Abi::new(owned(abi))),
+            arch,
+            env: self
+                .single_value("target_env")?
+                .map(|env| Env::new(owned(env))),
+            vendor: self
+                .single_value("target_vendor")?
+                .map(|vendor| Vendor::new(owned(vendor))),
+            families: Families::new(
+                self.values("target_family")
+                    .map(|family| Family::new(owned(family))),
+            ),
+            pointer_width,
+            endian,
+            has_atomics: HasAtomics::new(has_atomics),
+            panic: Panic::new(owned(self.single_value("panic")?.unwrap_or("unwind"))),
+        })
+    }
+}
+
+impl FromStr for RustcCfg {
+    type Err = RustcCfgError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::parse(input)
+    }
+}
+
+/// Prints `self` in the same format as `rustc --print cfg`, with one line per flag or key-value
+/// pair, sorted by name.
+impl fmt::Display for RustcCfg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = self.flags.iter().map(|flag| (flag.as_str(), None));
+        let key_values = self.key_values().map(|(key, value)| (key, Some(value)));
+        let mut lines: Vec<_> = flags.chain(key_values).collect();
+        lines.sort_unstable();
+        for (name, value) in lines {
+            match value {
+                Some(value) => writeln!(f, "{name}=\"{}\"", value.escape_debug())?,
+                None => writeln!(f, "{name}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Keys that are interpreted as part of the target or its features.
+static TARGET_KEYS: &[&str] = &[
+    "panic",
+    "target_abi",
+    "target_arch",
+    "target_endian",
+    "target_env",
+    "target_family",
+    "target_feature",
+    "target_has_atomic",
+    "target_os",
+    "target_pointer_width",
+    "target_vendor",
+];
+
+/// A builder for running `rustc --print cfg`.
+///
+/// By default, this runs the `rustc` specified by the `RUSTC` environment variable, or `rustc` on
+/// the `PATH`, for the host platform.
+///
+/// # Examples
+///
+/// ```no_run
+/// use target_spec::RustcCfgCommand;
+///
+/// // Equivalent to `rustc --print cfg --target aarch64-unknown-linux-gnu -C target-feature=+sve`.
+/// let platform = RustcCfgCommand::new()
+///     .set_target("aarch64-unknown-linux-gnu")
+///     .add_target_features(["+sve"])
+///     .to_platform()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct RustcCfgCommand {
+    rustc: OsString,
+    target: Option<String>,
+    target_features: Vec<String>,
+    args: Vec<OsString>,
+}
+
+impl RustcCfgCommand {
+    /// Creates a new `RustcCfgCommand` for the host platform.
+    pub fn new() -> Self {
+        let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+        Self {
+            rustc,
+            target: None,
+            target_features: vec![],
+            args: vec![],
+        }
+    }
+
+    /// Sets the path to the `rustc` binary to run.
+    pub fn set_rustc(&mut self, rustc: impl Into<OsString>) -> &mut Self {
+        self.rustc = rustc.into();
+        self
+    }
+
+    /// Sets the target triple to pass in as `--target`.
+    pub fn set_target(&mut self, target: impl Into<String>) -> &mut Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Adds target features to pass in as `-C target-feature`, for example `+avx2` or `-sse3`.
+    pub fn add_target_features(
+        &mut self,
+        target_features: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.target_features
+            .extend(target_features.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds other arguments to pass in to `rustc`, for example `-C target-cpu=native` or
+    /// `--cfg tokio_unstable`.
+    pub fn add_args(&mut self, args: impl IntoIterator<Item = impl Into<OsString>>) -> &mut Self {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Runs `rustc --print cfg` and parses its output.
+    pub fn run(&self) -> Result<RustcCfg, RustcCfgError> {
+        let mut command = Command::new(&self.rustc);
+        command.args(["--print", "cfg"]);
+        if let Some(target) = &self.target {
+            command.args(["--target", target]);
+        }
+        if !self.target_features.is_empty() {
+            command
+                .arg("-C")
+                .arg(format!("target-feature={}", self.target_features.join(",")));
+        }
+        command.args(&self.args);
+
+        let stdout = self.output(command)?;
+        RustcCfg::parse(&stdout)
+    }
+
+    /// Runs `rustc --print cfg` and converts its output to a `Platform`.
+    ///
+    /// If a target wasn't set, the host triple is determined by running `rustc -vV`.
+    pub fn to_platform(&self) -> Result<Platform, Error> {
+        let cfg = self.run().map_err(Error::RustcCfg)?;
+        let triple_str = match &self.target {
+            Some(target) => target.clone(),
+            None => self.host_triple().map_err(Error::RustcCfg)?,
+        };
+        Platform::from_rustc_cfg(triple_str, cfg)
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn host_triple(&self) -> Result<String, RustcCfgError> {
+        let mut command = Command::new(&self.rustc);
+        command.arg("-vV");
+        let stdout = self.output(command)?;
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix("host: "))
+            .map(|host| host.trim().to_owned())
+            .ok_or(RustcCfgError::HostTripleNotFound)
+    }
+
+    fn output(&self, mut command: Command) -> Result<String, RustcCfgError> {
+        let command_str = display_command(&command);
+        let output = command.output().map_err(|error| RustcCfgError::Spawn {
+            command: command_str.clone(),
+            error: Arc::new(error),
+        })?;
+        if !output.status.success() {
+            return Err(RustcCfgError::CommandFailed {
+                command: command_str,
+                status: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            });
+        }
+        String::from_utf8(output.stdout).map_err(|_| RustcCfgError::NonUtf8Output {
+            command: command_str,
+        })
+    }
+}
+
+impl Default for RustcCfgCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn display_command(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(OsStr::to_string_lossy)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns true if `name` is a valid cfg name: an identifier, possibly a raw one.
+fn is_cfg_name(name: &str) -> bool {
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if unicode_ident::is_xid_start(first) || first == '_' => {
+            chars.all(unicode_ident::is_xid_continue)
+        }
+        _ => false,
+    }
+}
+
+/// Removes the quotes around a value printed by rustc, processing the escapes rustc may emit.
+fn unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                'r' => out.push('\r'),
+                '0' => out.push('\0'),
+                c @ ('\\' | '"' | '\'') => out.push(c),
+                _ => return None,
+            },
+            '"' => return None,
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TargetSpec;
+
+    static X86_64_LINUX_AVX2: &str =
+        include_str!("../testdata/rustc-cfg/x86_64-unknown-linux-gnu-avx2.txt");
+    static AARCH64_DARWIN: &str = include_str!("../testdata/rustc-cfg/aarch64-apple-darwin.txt");
+    static WASM32_UNKNOWN: &str = include_str!("../testdata/rustc-cfg/wasm32-unknown-unknown.txt");
+    static THUMBV7EM_NONE: &str = include_str!("../testdata/rustc-cfg/thumbv7em-none-eabihf.txt");
+
+    fn platform(triple_str: &'static str, input: &str) -> Platform {
+        let cfg = RustcCfg::parse(input).expect("rustc output parsed");
+        Platform::from_rustc_cfg(triple_str, cfg).expect("platform created")
+    }
+
+    fn eval(spec: &str, platform: &Platform) -> Option<bool> {
+        TargetSpec::new(spec.to_owned())
+            .expect("valid spec")
+            .eval(platform)
+    }
+
+    #[test]
+    fn parse_fixtures() {
+        let cfg = RustcCfg::parse(X86_64_LINUX_AVX2).expect("parsed");
+        assert!(cfg.has_flag("unix"));
+        assert!(cfg.has_flag("debug_assertions"));
+        assert_eq!(cfg.values("target_arch").collect::<Vec<_>>(), ["x86_64"]);
+        assert_eq!(cfg.values("target_abi").collect::<Vec<_>>(), [""]);
+        assert!(cfg
+            .values("target_feature")
+            .any(|feature| feature == "avx2"));
+        assert_eq!(cfg.values("nonexistent").count(), 0);
+
+        // Printing and parsing again produces the same result.
+        let reparsed = RustcCfg::parse(&cfg.to_string()).expect("reparsed");
+        assert_eq!(cfg, reparsed, "round trip");
+
+        for input in [AARCH64_DARWIN, WASM32_UNKNOWN, THUMBV7EM_NONE] {
+            let cfg = RustcCfg::parse(input).expect("parsed");
+            assert_eq!(cfg, cfg.to_string().parse().expect("reparsed"));
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (input, line_number) in [
+            ("unix\ntarget_os=linux", 2),
+            ("target_os=\"linux", 1),
+            ("\n\nnot a flag", 3),
+            ("=\"value\"", 1),
+            ("target_os=\"li\"nux\"", 1),
+        ] {
+            match RustcCfg::parse(input) {
+                Err(RustcCfgError::InvalidLine {
+                    line_number: actual,
+                    ..
+                }) => assert_eq!(actual, line_number, "for input {input:?}"),
+                other => panic!("for input {input:?}, expected invalid line, got {other:?}"),
+            }
+        }
+
+        let cfg = RustcCfg::parse("target_os=\"linux\"").expect("parsed");
+        assert_eq!(
+            cfg.extra_key_values().count(),
+            0,
+            "target_os is a target key"
+        );
+        let err = Platform::from_rustc_cfg("custom-triple", cfg).expect_err("no arch");
+        assert!(matches!(
+            err,
+            Error::RustcCfg(RustcCfgError::MissingKey { key: "target_arch" })
+        ));
+
+        let cfg = RustcCfg::parse("target_arch=\"x86_64\"\ntarget_arch=\"x86\"").expect("parsed");
+        let err = Platform::from_rustc_cfg("custom-triple", cfg).expect_err("two arches");
+        assert!(matches!(
+            err,
+            Error::RustcCfg(RustcCfgError::MultipleValues { key: "target_arch" })
+        ));
+    }
+
+    #[test]
+    fn eval_fixtures() {
+        let linux = platform("x86_64-unknown-linux-gnu", X86_64_LINUX_AVX2);
+        assert!(linux.is_rustc_cfg());
+        assert!(!linux.is_standard());
+        assert_eq!(linux.triple_str(), "x86_64-unknown-linux-gnu");
+        assert_eq!(linux.target_features().matches("avx2"), Some(true));
+        assert_eq!(linux.target_features().matches("avx512f"), Some(false));
+        assert_eq!(
+            eval(
+                "cfg(all(unix, target_os = \"linux\", target_env = \"gnu\"))",
+                &linux
+            ),
+            Some(true)
+        );
+        assert_eq!(eval("cfg(target_feature = \"sse4.2\")", &linux), Some(true));
+        assert_eq!(eval("cfg(target_has_atomic = \"64\")", &linux), Some(true));
+        assert_eq!(eval("cfg(target_abi = \"\")", &linux), Some(true));
+        assert_eq!(eval("x86_64-unknown-linux-gnu", &linux), Some(true));
+        // debug_assertions is printed by rustc, but Cargo always evaluates it to false.
+        assert_eq!(eval("cfg(debug_assertions)", &linux), Some(false));
+
+        let darwin = platform("aarch64-apple-darwin", AARCH64_DARWIN);
+        assert_eq!(
+            eval(
+                "cfg(all(target_vendor = \"apple\", target_arch = \"aarch64\"))",
+                &darwin
+            ),
+            Some(true)
+        );
+        assert_eq!(
+            eval("cfg(target_has_atomic = \"128\")", &darwin),
+            Some(true)
+        );
+        assert_eq!(eval("cfg(windows)", &darwin), Some(false));
+
+        let wasm = platform("wasm32-unknown-unknown", WASM32_UNKNOWN);
+        assert_eq!(
+            eval(
+                "cfg(all(target_family = \"wasm\", panic = \"abort\"))",
+                &wasm
+            ),
+            Some(true)
+        );
+        assert_eq!(eval("cfg(unix)", &wasm), Some(false));
+
+        let thumb = platform("thumbv7em-none-eabihf", THUMBV7EM_NONE);
+        assert_eq!(
+            eval(
+                "cfg(all(target_os = \"none\", target_abi = \"eabihf\"))",
+                &thumb
+            ),
+            Some(true)
+        );
+        assert_eq!(eval("cfg(target_has_atomic = \"64\")", &thumb), Some(false));
+        assert_eq!(
+            eval("cfg(target_pointer_width = \"32\")", &thumb),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn extra_key_values() {
+        // Nightly toolchains, and --cfg arguments, print key-value cfgs that aren't part of the
+        // target.
+        let input = format!(
+            "{X86_64_LINUX_AVX2}fmt_debug=\"full\"\ntarget_has_atomic_load_store=\"64\"\ntokio_unstable\n"
+        );
+        let linux = platform("x86_64-unknown-linux-gnu", &input);
+        assert!(linux.has_flag("tokio_unstable"));
+        assert!(linux.has_key_value("fmt_debug", "full"));
+        assert!(
+            !linux.has_key_value("target_os", "linux"),
+            "target keys not duplicated"
+        );
+        assert_eq!(eval("cfg(fmt_debug = \"full\")", &linux), Some(true));
+        assert_eq!(eval("cfg(fmt_debug = \"none\")", &linux), Some(false));
+        assert!(linux.has_key_value("target_has_atomic_load_store", "64"));
+        assert_eq!(eval("cfg(tokio_unstable)", &linux), Some(true));
+    }
+
+    #[test]
+    fn matches_builtin() {
+        // Evaluating against the output of rustc should agree with the builtin table for
+        // properties that don't vary between toolchain versions.
+        let from_cfg = platform("x86_64-unknown-linux-gnu", X86_64_LINUX_AVX2);
+        let builtin = Platform::new(
+            "x86_64-unknown-linux-gnu",
+            from_cfg.target_features().clone(),
+        )
+        .expect("builtin platform");
+        for spec in [
+            "cfg(unix)",
+            "cfg(windows)",
+            "cfg(target_os = \"linux\")",
+            "cfg(target_env = \"musl\")",
+            "cfg(target_pointer_width = \"32\")",
+            "cfg(target_endian = \"little\")",
+            "cfg(target_feature = \"avx2\")",
+            "cfg(all(unix, not(target_arch = \"aarch64\")))",
+        ] {
+            assert_eq!(
+                eval(spec, &from_cfg),
+                eval(spec, &builtin),
+                "for spec {spec}"
+            );
+        }
+    }
+}
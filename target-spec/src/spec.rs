@@ -170,7 +170,7 @@ impl fmt::Display for TargetSpec {
 /// For more information, see [`TargetSpec`].
 #[derive(Clone, Debug)]
 pub struct TargetSpecExpression {
-    inner: Arc<Expression>,
+    pub(crate) inner: Arc<Expression>,
 }
 
 impl TargetSpecExpression {
@@ -196,30 +196,56 @@ impl TargetSpecExpression {
     /// Returns `Some(true)` if there's a match, `Some(false)` if there's none, or `None` if the
     /// result of the evaluation is unknown (typically found if target features are involved).
     pub fn eval(&self, platform: &Platform) -> Option<bool> {
-        self.inner.eval(|pred| {
-            match pred {
-                Predicate::Target(target) => Some(platform.triple().matches(target)),
-                Predicate::TargetFeature(feature) => platform.target_features().matches(feature),
-                Predicate::Test | Predicate::DebugAssertions | Predicate::ProcMacro => {
-                    // Known families that always evaluate to false. See
-                    // https://docs.rs/cargo-platform/0.1.1/src/cargo_platform/lib.rs.html#76.
-                    Some(false)
-                }
-                Predicate::Feature(_) => {
-                    // NOTE: This is not supported by Cargo which always evaluates this to false. See
-                    // https://github.com/rust-lang/cargo/issues/7442 for more details.
-                    Some(false)
-                }
-                Predicate::Flag(flag) => {
-                    // This returns false by default but true in some cases.
-                    Some(platform.has_flag(flag))
-                }
-                Predicate::KeyValue { .. } => {
-                    // This is always interpreted by Cargo as false.
-                    Some(false)
-                }
-            }
-        })
+        self.inner.eval(|pred| eval_predicate(pred, platform).0)
+    }
+}
+
+/// The kind of a predicate, used to explain how it was evaluated.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum PredicateKind {
+    Triple,
+    Target,
+    TargetFeature,
+    AlwaysFalse,
+    Flag,
+    KeyValue,
+}
+
+/// Evaluates a single predicate against the given platform.
+pub(crate) fn eval_predicate(
+    pred: &Predicate<'_>,
+    platform: &Platform,
+) -> (Option<bool>, PredicateKind) {
+    match pred {
+        Predicate::Target(target) => (
+            Some(platform.triple().matches(target)),
+            PredicateKind::Target,
+        ),
+        Predicate::TargetFeature(feature) => (
+            platform.has_target_feature(feature),
+            PredicateKind::TargetFeature,
+        ),
+        Predicate::Test | Predicate::DebugAssertions | Predicate::ProcMacro => {
+            // Known families that always evaluate to false. See
+            // https://docs.rs/cargo-platform/0.1.1/src/cargo_platform/lib.rs.html#76.
+            (Some(false), PredicateKind::AlwaysFalse)
+        }
+        Predicate::Feature(_) => {
+            // NOTE: This is not supported by Cargo which always evaluates this to false. See
+            // https://github.com/rust-lang/cargo/issues/7442 for more details.
+            (Some(false), PredicateKind::AlwaysFalse)
+        }
+        Predicate::Flag(flag) => {
+            // This returns false by default but true in some cases.
+            (Some(platform.has_flag(flag)), PredicateKind::Flag)
+        }
+        Predicate::KeyValue { key, val } => {
+            // This returns false by default but true if set with `--cfg key="val"`.
+            (
+                Some(platform.has_key_value(key, val)),
+                PredicateKind::KeyValue,
+            )
+        }
     }
 }
 
@@ -438,6 +464,31 @@ mod tests {
         assert_eq!(expr.eval(&platform), Some(true));
     }
 
+    #[test]
+    fn test_key_value() {
+        let mut platform = Platform::current().unwrap();
+        platform.add_key_values([("foo", "bar"), ("foo", "baz")]);
+        assert!(platform.has_key_value("foo", "bar"));
+        assert!(!platform.has_key_value("foo", "qux"));
+        assert_eq!(
+            platform.key_values().collect::<Vec<_>>(),
+            vec![("foo", "bar"), ("foo", "baz")],
+        );
+
+        for (spec, expected) in [
+            ("cfg(foo = \"bar\")", true),
+            ("cfg(foo = \"baz\")", true),
+            ("cfg(foo = \"qux\")", false),
+            ("cfg(bar = \"foo\")", false),
+            ("cfg(all(foo = \"bar\", not(foo = \"qux\")))", true),
+            // Flags and key-value cfgs are separate.
+            ("cfg(foo)", false),
+        ] {
+            let expr = TargetSpec::new(spec).expect("valid spec");
+            assert_eq!(expr.eval(&platform), Some(expected), "for spec {spec}");
+        }
+    }
+
     #[test]
     fn test_extra() {
         let res = TargetSpec::new("cfg(unix)this-is-extra");
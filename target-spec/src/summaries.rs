@@ -8,9 +8,12 @@
 //!
 //! Summaries require the `summaries` feature to be enabled.
 
-use crate::{Error, Platform, TargetFeatures};
+use crate::{Error, Platform, RustcCfg, TargetFeatures, TargetMetadata, TargetTier};
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, collections::BTreeSet};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+};
 
 impl Platform {
     /// Converts this `Platform` to a serializable form.
@@ -38,12 +41,32 @@ pub struct PlatformSummary {
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub custom_json: Option<String>,
 
+    /// The output of `rustc --print cfg`, for platforms created from it.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub rustc_cfg: Option<String>,
+
     /// The target features used.
     pub target_features: TargetFeaturesSummary,
 
     /// The flags enabled.
     #[serde(skip_serializing_if = "BTreeSet::is_empty", default)]
     pub flags: BTreeSet<String>,
+
+    /// The key-value cfgs enabled, as a map from each key to its values.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub key_values: BTreeMap<String, BTreeSet<String>>,
+
+    /// Whether target features are matched strictly. See
+    /// [`Platform::set_strict_target_features`].
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub strict_target_features: bool,
+
+    /// Metadata about the platform's triple, if recorded.
+    ///
+    /// This is informational: it is not recorded by [`Self::from_platform`], and not used by
+    /// [`Self::to_platform`]. To record it, use [`Self::with_metadata`].
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub metadata: Option<TargetMetadataSummary>,
 }
 
 impl PlatformSummary {
@@ -52,14 +75,22 @@ impl PlatformSummary {
     /// The default options are:
     ///
     /// * `custom_json` is set to None.
+    /// * `rustc_cfg` is set to None.
     /// * `target_features` is set to [`TargetFeaturesSummary::Unknown`].
     /// * `flags` is empty.
+    /// * `key_values` is empty.
+    /// * `strict_target_features` is false.
+    /// * `metadata` is set to None.
     pub fn new(triple_str: impl Into<String>) -> Self {
         Self {
             triple: triple_str.into(),
             custom_json: None,
+            rustc_cfg: None,
             target_features: TargetFeaturesSummary::Unknown,
             flags: BTreeSet::new(),
+            key_values: BTreeMap::new(),
+            strict_target_features: false,
+            metadata: None,
         }
     }
 
@@ -73,6 +104,12 @@ impl PlatformSummary {
         self
     }
 
+    /// If this represents a platform created from `rustc --print cfg`, sets the output for it.
+    pub fn with_rustc_cfg(mut self, rustc_cfg: impl Into<String>) -> Self {
+        self.rustc_cfg = Some(rustc_cfg.into());
+        self
+    }
+
     /// Sets the target features for this platform.
     pub fn with_target_features(mut self, target_features: TargetFeaturesSummary) -> Self {
         self.target_features = target_features;
@@ -85,42 +122,165 @@ impl PlatformSummary {
         self
     }
 
+    /// Adds key-value cfgs for this platform.
+    pub fn with_added_key_values(
+        mut self,
+        key_values: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        for (key, value) in key_values {
+            self.key_values
+                .entry(key.into())
+                .or_default()
+                .insert(value.into());
+        }
+        self
+    }
+
+    /// Sets whether target features are matched strictly for this platform.
+    pub fn with_strict_target_features(mut self, strict: bool) -> Self {
+        self.strict_target_features = strict;
+        self
+    }
+
+    /// Records metadata about the platform's triple.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use target_spec::{summaries::TargetMetadataSummary, Platform, TargetFeatures};
+    ///
+    /// let platform = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+    /// let mut summary = platform.to_summary();
+    /// if let Some(metadata) = platform.metadata() {
+    ///     summary = summary.with_metadata(TargetMetadataSummary::new(&metadata));
+    /// }
+    /// assert_eq!(summary.metadata.unwrap().tier, 1);
+    /// ```
+    pub fn with_metadata(mut self, metadata: TargetMetadataSummary) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
     /// Creates a new `PlatformSummary` instance from a platform.
     pub fn from_platform(platform: &Platform) -> Self {
         Self {
             triple: platform.triple_str().to_string(),
             custom_json: platform.custom_json().map(|s| s.to_owned()),
+            rustc_cfg: platform.rustc_cfg().map(|cfg| cfg.to_string()),
             target_features: TargetFeaturesSummary::new(platform.target_features()),
             flags: platform.flags().map(|flag| flag.to_string()).collect(),
+            key_values: BTreeMap::new(),
+            strict_target_features: platform.strict_target_features(),
+            metadata: None,
         }
+        .with_added_key_values(platform.key_values())
     }
 
     /// Converts `self` to a `Platform`.
     ///
     /// Returns an `Error` if the platform was unknown.
     pub fn to_platform(&self) -> Result<Platform, Error> {
+        let platform = match self.to_inline_platform()? {
+            Some(platform) => platform,
+            None => Platform::new(
+                self.triple.to_owned(),
+                self.target_features.to_target_features(),
+            )?,
+        };
+        Ok(self.add_cfgs(platform))
+    }
+
+    /// Converts `self` to a `Platform`, resolving the triple through the given registry of custom
+    /// targets.
+    ///
+    /// If this summary has custom target JSON or `rustc --print cfg` output inlined, that is used as
+    /// with [`Self::to_platform`]. Otherwise, the triple is resolved through
+    /// [`TargetRegistry::platform`](crate::TargetRegistry::platform), so summaries can refer to
+    /// custom targets by name.
+    ///
+    /// Returns an `Error` if the platform was unknown, or if its definition was invalid.
+    ///
+    /// Requires the `custom` feature to be enabled.
+    #[cfg(feature = "custom")]
+    pub fn to_platform_with_registry(
+        &self,
+        registry: &crate::TargetRegistry,
+    ) -> Result<Platform, Error> {
+        let platform = match self.to_inline_platform()? {
+            Some(platform) => platform,
+            None => registry.platform(&self.triple, self.target_features.to_target_features())?,
+        };
+        Ok(self.add_cfgs(platform))
+    }
+
+    /// Creates a platform from the rustc cfg output or custom JSON, if either is present.
+    fn to_inline_platform(&self) -> Result<Option<Platform>, Error> {
+        if let Some(rustc_cfg) = &self.rustc_cfg {
+            let cfg = RustcCfg::parse(rustc_cfg).map_err(Error::RustcCfg)?;
+            return Ok(Some(Platform::from_rustc_cfg(self.triple.to_owned(), cfg)?));
+        }
+
         #[allow(unused_variables)] // in some feature branches, json isn't used
-        let mut platform = if let Some(json) = &self.custom_json {
+        if let Some(json) = &self.custom_json {
             #[cfg(not(feature = "custom"))]
             return Err(Error::CustomPlatformCreate(
                 crate::errors::CustomTripleCreateError::Unavailable,
             ));
 
             #[cfg(feature = "custom")]
-            Platform::new_custom(
+            return Ok(Some(Platform::new_custom(
                 self.triple.to_owned(),
                 json,
                 self.target_features.to_target_features(),
-            )?
-        } else {
-            Platform::new(
-                self.triple.to_owned(),
-                self.target_features.to_target_features(),
-            )?
-        };
+            )?));
+        }
+
+        Ok(None)
+    }
 
+    fn add_cfgs(&self, mut platform: Platform) -> Platform {
         platform.add_flags(self.flags.iter().cloned());
-        Ok(platform)
+        platform.add_key_values(self.key_values.iter().flat_map(|(key, values)| {
+            values.iter().map(move |value| (key.clone(), value.clone()))
+        }));
+        platform.set_strict_target_features(self.strict_target_features);
+        platform
+    }
+}
+
+/// An owned, serializable version of [`TargetMetadata`].
+///
+/// This structure can be serialized and deserialized using `serde`.
+///
+/// Requires the `summaries` feature to be enabled.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct TargetMetadataSummary {
+    /// The support tier: 1, 2 or 3.
+    pub tier: u8,
+
+    /// Whether the target supports `std`, or `None` if that isn't known.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub std: Option<bool>,
+
+    /// Whether official builds of host tools are available for the target.
+    #[serde(default)]
+    pub host_tools: bool,
+}
+
+impl TargetMetadataSummary {
+    /// Creates a new `TargetMetadataSummary` from metadata.
+    pub fn new(metadata: &TargetMetadata) -> Self {
+        Self {
+            tier: match metadata.tier() {
+                TargetTier::Tier1 => 1,
+                TargetTier::Tier2 => 2,
+                TargetTier::Tier3 => 3,
+            },
+            std: metadata.has_std(),
+            host_tools: metadata.has_host_tools(),
+        }
     }
 }
 
@@ -186,19 +346,31 @@ mod platform_impl {
                 PlatformSummaryDeserialize::String(triple) => Ok(PlatformSummary {
                     triple,
                     custom_json: None,
+                    rustc_cfg: None,
                     target_features: TargetFeaturesSummary::default(),
                     flags: BTreeSet::default(),
+                    key_values: BTreeMap::default(),
+                    strict_target_features: false,
+                    metadata: None,
                 }),
                 PlatformSummaryDeserialize::Full {
                     triple,
                     custom_json,
+                    rustc_cfg,
                     target_features,
                     flags,
+                    key_values,
+                    strict_target_features,
+                    metadata,
                 } => Ok(PlatformSummary {
                     triple,
                     custom_json,
+                    rustc_cfg,
                     target_features,
                     flags,
+                    key_values,
+                    strict_target_features,
+                    metadata,
                 }),
             }
         }
@@ -213,12 +385,23 @@ mod platform_impl {
             triple: String,
             #[serde(default)]
             custom_json: Option<String>,
+            #[serde(default)]
+            rustc_cfg: Option<String>,
             /// The target features used.
             #[serde(default)]
             target_features: TargetFeaturesSummary,
             /// The flags enabled.
             #[serde(skip_serializing_if = "BTreeSet::is_empty", default)]
             flags: BTreeSet<String>,
+            /// The key-value cfgs enabled.
+            #[serde(default)]
+            key_values: BTreeMap<String, BTreeSet<String>>,
+            /// Whether target features are matched strictly.
+            #[serde(default)]
+            strict_target_features: bool,
+            /// Metadata about the triple.
+            #[serde(default)]
+            metadata: Option<TargetMetadataSummary>,
         },
     }
 }
@@ -292,8 +475,12 @@ mod tests {
             PlatformSummary {
                 triple: "x86_64-unknown-linux-gnu".into(),
                 custom_json: None,
+                rustc_cfg: None,
                 target_features: TargetFeaturesSummary::Unknown,
                 flags: BTreeSet::new(),
+                key_values: BTreeMap::new(),
+                strict_target_features: false,
+                metadata: None,
             },
         ));
         valid.push((
@@ -301,8 +488,12 @@ mod tests {
             PlatformSummary {
                 triple: "x86_64-unknown-linux-gnu".into(),
                 custom_json: None,
+                rustc_cfg: None,
                 target_features: TargetFeaturesSummary::Unknown,
                 flags: BTreeSet::new(),
+                key_values: BTreeMap::new(),
+                strict_target_features: false,
+                metadata: None,
             },
         ));
         valid.push((
@@ -310,8 +501,12 @@ mod tests {
             PlatformSummary {
                 triple: "x86_64-unknown-linux-gnu".into(),
                 custom_json: None,
+                rustc_cfg: None,
                 target_features: TargetFeaturesSummary::Unknown,
                 flags: BTreeSet::new(),
+                key_values: BTreeMap::new(),
+                strict_target_features: false,
+                metadata: None,
             },
         ));
         valid.push((
@@ -319,8 +514,12 @@ mod tests {
             PlatformSummary {
                 triple: "x86_64-unknown-linux-gnu".into(),
                 custom_json: None,
+                rustc_cfg: None,
                 target_features: TargetFeaturesSummary::All,
                 flags: BTreeSet::new(),
+                key_values: BTreeMap::new(),
+                strict_target_features: false,
+                metadata: None,
             },
         ));
         valid.push((
@@ -328,8 +527,12 @@ mod tests {
             PlatformSummary {
                 triple: "x86_64-unknown-linux-gnu".into(),
                 custom_json: None,
+                rustc_cfg: None,
                 target_features: TargetFeaturesSummary::Features(BTreeSet::new()),
                 flags: BTreeSet::new(),
+                key_values: BTreeMap::new(),
+                strict_target_features: false,
+                metadata: None,
             },
         ));
 
@@ -343,8 +546,12 @@ mod tests {
             PlatformSummary {
                 triple: "x86_64-unknown-haiku".into(),
                 custom_json: Some(custom_json.to_owned()),
+                rustc_cfg: None,
                 target_features: TargetFeaturesSummary::Unknown,
                 flags: BTreeSet::new(),
+                key_values: BTreeMap::new(),
+                strict_target_features: false,
+                metadata: None,
             },
         ));
 
@@ -355,8 +562,67 @@ mod tests {
             PlatformSummary {
                 triple: "x86_64-unknown-linux-gnu".into(),
                 custom_json: None,
+                rustc_cfg: None,
                 target_features: TargetFeaturesSummary::Unknown,
                 flags,
+                key_values: BTreeMap::new(),
+                strict_target_features: false,
+                metadata: None,
+            },
+        ));
+
+        let mut key_values = BTreeMap::new();
+        key_values.insert(
+            "tokio_backend".to_owned(),
+            ["epoll".to_owned(), "io-uring".to_owned()]
+                .into_iter()
+                .collect::<BTreeSet<_>>(),
+        );
+        valid.push((
+            r#"platform = { triple = "x86_64-unknown-linux-gnu", key-values = { tokio_backend = ["epoll", "io-uring"] } }"#,
+            PlatformSummary {
+                triple: "x86_64-unknown-linux-gnu".into(),
+                custom_json: None,
+                rustc_cfg: None,
+                target_features: TargetFeaturesSummary::Unknown,
+                flags: BTreeSet::new(),
+                key_values,
+                strict_target_features: false,
+                metadata: None,
+            },
+        ));
+
+        valid.push((
+            r#"platform = { triple = "x86_64-unknown-linux-gnu", target-features = ["avx2"], strict-target-features = true }"#,
+            PlatformSummary {
+                triple: "x86_64-unknown-linux-gnu".into(),
+                custom_json: None,
+                rustc_cfg: None,
+                target_features: TargetFeaturesSummary::Features(
+                    ["avx2".to_owned()].into_iter().collect(),
+                ),
+                flags: BTreeSet::new(),
+                key_values: BTreeMap::new(),
+                strict_target_features: true,
+                metadata: None,
+            },
+        ));
+
+        valid.push((
+            r#"platform = { triple = "x86_64-unknown-linux-gnu", metadata = { tier = 1, std = true, host-tools = true } }"#,
+            PlatformSummary {
+                triple: "x86_64-unknown-linux-gnu".into(),
+                custom_json: None,
+                rustc_cfg: None,
+                target_features: TargetFeaturesSummary::Unknown,
+                flags: BTreeSet::new(),
+                key_values: BTreeMap::new(),
+                strict_target_features: false,
+                metadata: Some(TargetMetadataSummary {
+                    tier: 1,
+                    std: Some(true),
+                    host_tools: true,
+                }),
             },
         ));
 
@@ -371,6 +637,52 @@ mod tests {
                 .unwrap_or_else(|err| panic!("serialized input: {} is valid: {}", input, err));
             assert_eq!(actual, actual_2, "for input: {}", input);
 
+            // Check that key-value cfgs round-trip through platforms.
+            if !actual.platform.key_values.is_empty() {
+                let platform = actual
+                    .platform
+                    .to_platform()
+                    .expect("platform created successfully");
+                assert!(platform.has_key_value("tokio_backend", "io-uring"));
+                assert_eq!(
+                    platform.to_summary(),
+                    actual.platform,
+                    "for input: {}",
+                    input
+                );
+            }
+
+            // Check that recorded metadata matches the platform's.
+            if let Some(metadata) = &actual.platform.metadata {
+                let platform = actual
+                    .platform
+                    .to_platform()
+                    .expect("platform created successfully");
+                let platform_metadata = platform.metadata().expect("builtin platform");
+                assert_eq!(
+                    &TargetMetadataSummary::new(&platform_metadata),
+                    metadata,
+                    "for input: {}",
+                    input
+                );
+            }
+
+            // Check that strict mode round-trips through platforms.
+            if actual.platform.strict_target_features {
+                let platform = actual
+                    .platform
+                    .to_platform()
+                    .expect("platform created successfully");
+                assert!(platform.strict_target_features());
+                assert_eq!(platform.has_target_feature("avx"), Some(false));
+                assert_eq!(
+                    platform.to_summary(),
+                    actual.platform,
+                    "for input: {}",
+                    input
+                );
+            }
+
             // Check that custom JSON functionality works.
             if actual.platform.custom_json.is_some() {
                 #[cfg(feature = "custom")]
@@ -398,6 +710,55 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn platform_rustc_cfg_roundtrip() {
+        let cfg = RustcCfg::parse(include_str!(
+            "../testdata/rustc-cfg/thumbv7em-none-eabihf.txt"
+        ))
+        .expect("rustc cfg parsed");
+        let platform =
+            Platform::from_rustc_cfg("thumbv7em-none-eabihf", cfg).expect("platform created");
+
+        let summary = platform.to_summary();
+        assert!(summary.rustc_cfg.is_some(), "rustc cfg recorded");
+        let serialized = toml::to_string(&summary).expect("serialized correctly");
+        let deserialized: PlatformSummary =
+            toml::from_str(&serialized).expect("deserialized correctly");
+        assert_eq!(summary, deserialized, "summary round trips");
+
+        let platform_2 = deserialized.to_platform().expect("platform created");
+        assert!(platform_2.is_rustc_cfg(), "platform created from rustc cfg");
+        assert_eq!(platform, platform_2, "platform round trips");
+    }
+
+    #[cfg(feature = "custom")]
+    #[test]
+    fn platform_custom_with_registry() {
+        let mut registry = crate::TargetRegistry::new();
+        registry
+            .add_dir(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/testdata/target-json/valid"
+            ))
+            .expect("valid targets loaded");
+
+        let summary: PlatformSummary =
+            toml::from_str("triple = \"x86_64-unknown-myos\"\nflags = [\"foo\"]")
+                .expect("summary deserialized");
+        let platform = summary
+            .to_platform_with_registry(&registry)
+            .expect("custom triple resolved");
+        assert!(platform.is_custom(), "platform is custom");
+        assert!(platform.has_flag("foo"), "flags are added");
+        let spec: crate::TargetSpec = "cfg(target_os = \"myos\")".parse().unwrap();
+        assert_eq!(spec.eval(&platform), Some(true));
+
+        // Without the registry, the triple is unknown.
+        summary
+            .to_platform()
+            .expect_err("custom triple is unknown without registry");
+    }
 }
 
 #[cfg(all(test, feature = "proptest1"))]
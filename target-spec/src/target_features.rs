@@ -0,0 +1,249 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{TargetFeatures, Triple};
+use cfg_expr::{targets::Arch, TargetPredicate};
+use std::{borrow::Cow, collections::BTreeSet};
+
+impl TargetFeatures {
+    /// Returns a new `TargetFeatures` with the features implied by `self` on the given triple
+    /// added.
+    ///
+    /// For example, on x86_64, enabling `avx2` implies `avx`, which in turn implies `sse4.2`, and
+    /// so on. The implications known to `target-spec` are those rustc applies when a feature is
+    /// enabled with `-C target-feature`.
+    ///
+    /// `TargetFeatures::Unknown` and `TargetFeatures::All` are returned as is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use target_spec::{TargetFeatures, Triple};
+    ///
+    /// let triple = Triple::new("x86_64-unknown-linux-gnu").unwrap();
+    /// let features = TargetFeatures::features(["avx2"]).with_implied(&triple);
+    /// assert_eq!(features.matches("sse2"), Some(true));
+    /// assert_eq!(features.matches("avx512f"), Some(false));
+    /// ```
+    pub fn with_implied(&self, triple: &Triple) -> TargetFeatures {
+        match self {
+            TargetFeatures::Unknown | TargetFeatures::All => self.clone(),
+            TargetFeatures::Features(features) => {
+                let implications = implications_for(triple);
+                let mut expanded = features.clone();
+                let mut stack: Vec<&str> =
+                    features.iter().map(|feature| feature.as_ref()).collect();
+                while let Some(feature) = stack.pop() {
+                    for implied in implied_by(implications, feature) {
+                        if expanded.insert(Cow::Borrowed(implied)) {
+                            stack.push(implied);
+                        }
+                    }
+                }
+                TargetFeatures::Features(expanded)
+            }
+        }
+    }
+}
+
+/// Returns true if `feature` is enabled, or implied by an enabled feature, on this triple.
+pub(crate) fn is_enabled_or_implied(
+    triple: &Triple,
+    features: &BTreeSet<Cow<'static, str>>,
+    feature: &str,
+) -> bool {
+    if features.contains(feature) {
+        return true;
+    }
+
+    let implications = implications_for(triple);
+    let mut visited = BTreeSet::new();
+    let mut stack: Vec<&str> = features.iter().map(|feature| feature.as_ref()).collect();
+    while let Some(current) = stack.pop() {
+        for implied in implied_by(implications, current) {
+            if *implied == feature {
+                return true;
+            }
+            if visited.insert(*implied) {
+                stack.push(implied);
+            }
+        }
+    }
+    false
+}
+
+type Implications = &'static [(&'static str, &'static [&'static str])];
+
+fn implications_for(triple: &Triple) -> Implications {
+    IMPLICATIONS
+        .iter()
+        .find(|(arch, _)| triple.matches(&TargetPredicate::Arch(arch.clone())))
+        .map_or(&[], |(_, implications)| implications)
+}
+
+fn implied_by(implications: Implications, feature: &str) -> &'static [&'static str] {
+    implications
+        .iter()
+        .find(|(implying, _)| *implying == feature)
+        .map_or(&[], |(_, implied)| implied)
+}
+
+// Per the target feature tables in rustc_codegen_ssa (as of Rust 1.75), only listing features that
+// imply others. Each feature lists its direct implications only.
+
+static IMPLICATIONS: &[(Arch, Implications)] = &[
+    (Arch::x86_64, X86),
+    (Arch::x86, X86),
+    (Arch::aarch64, AARCH64),
+    (Arch::arm, ARM),
+    (Arch::riscv32, RISCV),
+    (Arch::riscv64, RISCV),
+    (Arch::wasm32, WASM),
+    (Arch::wasm64, WASM),
+];
+
+static X86: Implications = &[
+    ("aes", &["sse2"]),
+    ("avx", &["sse4.2"]),
+    ("avx2", &["avx"]),
+    ("avx512bf16", &["avx512bw"]),
+    ("avx512bitalg", &["avx512bw"]),
+    ("avx512bw", &["avx512f"]),
+    ("avx512cd", &["avx512f"]),
+    ("avx512dq", &["avx512f"]),
+    ("avx512er", &["avx512f"]),
+    ("avx512f", &["avx2", "fma", "f16c"]),
+    ("avx512fp16", &["avx512bw", "avx512vl", "avx512dq"]),
+    ("avx512ifma", &["avx512f"]),
+    ("avx512pf", &["avx512f"]),
+    ("avx512vbmi", &["avx512bw"]),
+    ("avx512vbmi2", &["avx512bw"]),
+    ("avx512vl", &["avx512f"]),
+    ("avx512vnni", &["avx512f"]),
+    ("avx512vp2intersect", &["avx512f"]),
+    ("avx512vpopcntdq", &["avx512f"]),
+    ("f16c", &["avx"]),
+    ("fma", &["avx"]),
+    ("gfni", &["sse2"]),
+    ("pclmulqdq", &["sse2"]),
+    ("sha", &["sse2"]),
+    ("sse2", &["sse"]),
+    ("sse3", &["sse2"]),
+    ("sse4.1", &["ssse3"]),
+    ("sse4.2", &["sse4.1"]),
+    ("sse4a", &["sse3"]),
+    ("ssse3", &["sse3"]),
+    ("vaes", &["avx2", "aes"]),
+    ("vpclmulqdq", &["avx", "pclmulqdq"]),
+    ("xsavec", &["xsave"]),
+    ("xsaveopt", &["xsave"]),
+    ("xsaves", &["xsave"]),
+];
+
+static AARCH64: Implications = &[
+    ("aes", &["neon"]),
+    ("dotprod", &["neon"]),
+    ("f32mm", &["sve"]),
+    ("f64mm", &["sve"]),
+    ("fcma", &["neon"]),
+    ("fhm", &["fp16"]),
+    ("fp16", &["neon"]),
+    ("jsconv", &["neon"]),
+    ("rdm", &["neon"]),
+    ("sha2", &["neon"]),
+    ("sha3", &["sha2"]),
+    ("sm4", &["neon"]),
+    ("sve", &["neon"]),
+    ("sve2", &["sve"]),
+    ("sve2-aes", &["sve2", "aes"]),
+    ("sve2-bitperm", &["sve2"]),
+    ("sve2-sha3", &["sve2", "sha3"]),
+    ("sve2-sm4", &["sve2", "sm4"]),
+];
+
+static ARM: Implications = &[
+    ("aes", &["neon"]),
+    ("fp-armv8", &["vfp4"]),
+    ("neon", &["vfp3"]),
+    ("sha2", &["neon"]),
+    ("vfp3", &["vfp2"]),
+    ("vfp4", &["vfp3"]),
+];
+
+static RISCV: Implications = &[
+    ("d", &["f"]),
+    ("zfh", &["zfhmin"]),
+    ("zfhmin", &["f"]),
+    ("zk", &["zkn", "zkr", "zkt"]),
+    ("zkn", &["zbkb", "zbkc", "zbkx", "zkne", "zknd", "zknh"]),
+    ("zks", &["zbkb", "zbkc", "zbkx", "zksed", "zksh"]),
+];
+
+static WASM: Implications = &[("relaxed-simd", &["simd128"])];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_are_sorted() {
+        for (arch, implications) in IMPLICATIONS {
+            assert!(
+                implications.windows(2).all(|pair| pair[0].0 < pair[1].0),
+                "implications for {arch} are sorted"
+            );
+        }
+    }
+
+    #[test]
+    fn with_implied() {
+        let x86_64 = Triple::new("x86_64-unknown-linux-gnu").unwrap();
+        let features = TargetFeatures::features(["avx2", "bmi2"]).with_implied(&x86_64);
+        assert_eq!(
+            features,
+            TargetFeatures::features([
+                "avx", "avx2", "bmi2", "sse", "sse2", "sse3", "sse4.1", "sse4.2", "ssse3",
+            ]),
+        );
+
+        // Implications are per-architecture.
+        let aarch64 = Triple::new("aarch64-unknown-linux-gnu").unwrap();
+        assert_eq!(
+            TargetFeatures::features(["avx2"]).with_implied(&aarch64),
+            TargetFeatures::features(["avx2"]),
+        );
+        assert_eq!(
+            TargetFeatures::features(["sve2-sha3"]).with_implied(&aarch64),
+            TargetFeatures::features(["neon", "sha2", "sha3", "sve", "sve2", "sve2-sha3"]),
+        );
+
+        assert_eq!(
+            TargetFeatures::Unknown.with_implied(&x86_64),
+            TargetFeatures::Unknown
+        );
+        assert_eq!(
+            TargetFeatures::All.with_implied(&x86_64),
+            TargetFeatures::All
+        );
+    }
+
+    #[test]
+    fn enabled_or_implied() {
+        let x86_64 = Triple::new("x86_64-unknown-linux-gnu").unwrap();
+        let TargetFeatures::Features(features) = TargetFeatures::features(["avx512f"]) else {
+            unreachable!("features() returns TargetFeatures::Features");
+        };
+        for feature in ["avx512f", "avx2", "fma", "f16c", "sse"] {
+            assert!(
+                is_enabled_or_implied(&x86_64, &features, feature),
+                "{feature} is implied by avx512f"
+            );
+        }
+        for feature in ["avx512bw", "aes", "fxsr"] {
+            assert!(
+                !is_enabled_or_implied(&x86_64, &features, feature),
+                "{feature} is not implied by avx512f"
+            );
+        }
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    errors::{CustomTripleCreateError, TargetRegistryError},
+    Error, Platform, TargetFeatures, Triple,
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// A registry of [custom targets](https://docs.rust-embedded.org/embedonomicon/custom-target.html),
+/// discovered from target JSON files and resolved by triple name.
+///
+/// `rustc` looks for custom target definitions in directories listed in the `RUST_TARGET_PATH`
+/// environment variable, where the target `foo` is defined by a file named `foo.json`. A
+/// `TargetRegistry` follows the same conventions, so that platforms referred to by a custom triple
+/// name can be resolved without passing in the JSON contents directly.
+///
+/// Requires the `custom` feature to be enabled.
+///
+/// # Examples
+///
+/// ```
+/// use target_spec::{TargetFeatures, TargetRegistry, TargetSpec};
+///
+/// let mut registry = TargetRegistry::new();
+/// registry
+///     .add_target(
+///         "x86_64-unknown-myos",
+///         r#"{
+///             "arch": "x86_64",
+///             "target-pointer-width": "64",
+///             "llvm-target": "x86_64-unknown-none",
+///             "data-layout": "e-m:e-i64:64-f80:128-n8:16:32:64-S128",
+///             "os": "myos"
+///         }"#,
+///     )
+///     .unwrap();
+///
+/// let platform = registry
+///     .platform("x86_64-unknown-myos", TargetFeatures::Unknown)
+///     .unwrap();
+/// let spec: TargetSpec = "cfg(target_os = \"myos\")".parse().unwrap();
+/// assert_eq!(spec.eval(&platform), Some(true));
+///
+/// // Builtin targets continue to be resolved as usual.
+/// let platform = registry
+///     .platform("x86_64-unknown-linux-gnu", TargetFeatures::Unknown)
+///     .unwrap();
+/// assert_eq!(spec.eval(&platform), Some(false));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TargetRegistry {
+    targets: BTreeMap<String, RegistryEntry>,
+}
+
+impl TargetRegistry {
+    /// Creates a new, empty `TargetRegistry`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `TargetRegistry` from the directories listed in the `RUST_TARGET_PATH`
+    /// environment variable.
+    ///
+    /// As with `rustc`, directories that don't exist are skipped. If `RUST_TARGET_PATH` isn't set,
+    /// the registry is empty.
+    pub fn from_env() -> Result<Self, TargetRegistryError> {
+        let mut registry = Self::new();
+        if let Some(paths) = std::env::var_os("RUST_TARGET_PATH") {
+            for dir in std::env::split_paths(&paths) {
+                if dir.is_dir() {
+                    registry.add_dir(&dir)?;
+                }
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Adds all target JSON files in the given directory to this registry.
+    ///
+    /// Every file in `dir` with a `.json` extension is treated as a target definition, named after
+    /// the file without its extension. Subdirectories are not searched.
+    ///
+    /// If a target with the same name has already been added, the existing definition is kept: this
+    /// matches `rustc`, which uses the first definition found on `RUST_TARGET_PATH`.
+    ///
+    /// Returns an error if the directory could not be read, or if any target JSON file within it is
+    /// invalid. In that case, no targets from `dir` are added.
+    pub fn add_dir(&mut self, dir: impl AsRef<Path>) -> Result<(), TargetRegistryError> {
+        let dir = dir.as_ref();
+        let read_dir_err = |error| TargetRegistryError::ReadDir {
+            dir: dir.to_owned(),
+            error: Arc::new(error),
+        };
+
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir).map_err(read_dir_err)? {
+            let path = entry.map_err(read_dir_err)?.path();
+            if path.extension().is_some_and(|ext| ext == "json") && path.is_file() {
+                paths.push(path);
+            }
+        }
+        // Sort paths so that errors are reported deterministically.
+        paths.sort();
+
+        let mut new_targets = Vec::with_capacity(paths.len());
+        for path in paths {
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.to_owned(),
+                None => return Err(TargetRegistryError::NonUtf8Name { path }),
+            };
+            let triple = load_target_file(name.clone(), &path)?;
+            new_targets.push((name, triple, path));
+        }
+
+        for (name, triple, path) in new_targets {
+            self.targets.entry(name).or_insert(RegistryEntry {
+                triple,
+                path: Some(path),
+            });
+        }
+        Ok(())
+    }
+
+    /// Adds a target to this registry from its name and JSON definition.
+    ///
+    /// This is useful for target definitions that are embedded rather than read from disk.
+    ///
+    /// Unlike [`Self::add_dir`], if a target with the same name has already been added, it is
+    /// replaced.
+    pub fn add_target(
+        &mut self,
+        triple_str: impl Into<String>,
+        json: &str,
+    ) -> Result<(), TargetRegistryError> {
+        let triple_str = triple_str.into();
+        let triple = Triple::new_custom(triple_str.clone(), json)
+            .map_err(|error| TargetRegistryError::InvalidTarget { path: None, error })?;
+        self.targets
+            .insert(triple_str, RegistryEntry { triple, path: None });
+        Ok(())
+    }
+
+    /// Returns the names of the custom targets in this registry, in sorted order.
+    pub fn custom_triples(&self) -> impl Iterator<Item = &str> + '_ {
+        self.targets.keys().map(|name| name.as_str())
+    }
+
+    /// Returns the number of custom targets in this registry.
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Returns true if this registry has no custom targets.
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Returns the path to the JSON file a custom target was read from.
+    ///
+    /// Returns `None` if the target isn't in this registry, or if it was added through
+    /// [`Self::add_target`].
+    pub fn path(&self, triple_str: &str) -> Option<&Path> {
+        self.targets.get(triple_str)?.path.as_deref()
+    }
+
+    /// Resolves a triple string to a [`Triple`].
+    ///
+    /// Resolution follows `rustc`:
+    ///
+    /// 1. Triples builtin to `rustc` are always resolved as builtin targets.
+    /// 2. Triples ending in `.json` are treated as paths to target JSON files, and named after the
+    ///    file without its extension.
+    /// 3. Otherwise, the triple is looked up in this registry.
+    ///
+    /// If none of these match, this falls back to [`Triple::new`].
+    pub fn triple(&self, triple_str: &str) -> Result<Triple, Error> {
+        if cfg_expr::targets::get_builtin_target_by_triple(triple_str).is_some() {
+            return Triple::new(triple_str.to_owned()).map_err(Error::UnknownPlatformTriple);
+        }
+
+        if triple_str.ends_with(".json") {
+            let path = Path::new(triple_str);
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.to_owned(),
+                None => {
+                    return Err(Error::TargetRegistry(TargetRegistryError::NonUtf8Name {
+                        path: path.to_owned(),
+                    }))
+                }
+            };
+            return load_target_file(name, path).map_err(Error::TargetRegistry);
+        }
+
+        if let Some(entry) = self.targets.get(triple_str) {
+            return Ok(entry.triple.clone());
+        }
+
+        Triple::new(triple_str.to_owned()).map_err(Error::UnknownPlatformTriple)
+    }
+
+    /// Resolves a triple string to a [`Platform`] with the given target features.
+    ///
+    /// For how the triple string is resolved, see [`Self::triple`].
+    pub fn platform(
+        &self,
+        triple_str: &str,
+        target_features: TargetFeatures,
+    ) -> Result<Platform, Error> {
+        let triple = self.triple(triple_str)?;
+        Ok(Platform::from_triple(triple, target_features))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RegistryEntry {
+    triple: Triple,
+    path: Option<PathBuf>,
+}
+
+fn load_target_file(name: String, path: &Path) -> Result<Triple, TargetRegistryError> {
+    let json = fs::read_to_string(path).map_err(|error| TargetRegistryError::ReadFile {
+        path: path.to_owned(),
+        error: Arc::new(error),
+    })?;
+    Triple::new_custom(name, &json).map_err(|error: CustomTripleCreateError| {
+        TargetRegistryError::InvalidTarget {
+            path: Some(path.to_owned()),
+            error,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TargetSpec;
+    use std::error::Error as _;
+
+    const VALID_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/target-json/valid");
+    const INVALID_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/target-json/invalid");
+
+    #[test]
+    fn discover_dir() {
+        let mut registry = TargetRegistry::new();
+        registry.add_dir(VALID_DIR).expect("valid targets loaded");
+
+        // README.txt is not a target.
+        assert_eq!(
+            registry.custom_triples().collect::<Vec<_>>(),
+            ["thumbv7em-myboard-eabihf", "x86_64-unknown-myos"]
+        );
+        assert_eq!(
+            registry.path("x86_64-unknown-myos"),
+            Some(
+                Path::new(VALID_DIR)
+                    .join("x86_64-unknown-myos.json")
+                    .as_path()
+            )
+        );
+
+        let spec: TargetSpec = "cfg(all(unix, target_os = \"myos\"))".parse().unwrap();
+        let platform = registry
+            .platform("x86_64-unknown-myos", TargetFeatures::Unknown)
+            .expect("custom target resolved");
+        assert!(platform.is_custom());
+        assert_eq!(spec.eval(&platform), Some(true));
+
+        let platform = registry
+            .platform("thumbv7em-myboard-eabihf", TargetFeatures::Unknown)
+            .expect("custom target resolved");
+        assert_eq!(spec.eval(&platform), Some(false));
+        let spec: TargetSpec = "cfg(target_abi = \"eabihf\")".parse().unwrap();
+        assert_eq!(spec.eval(&platform), Some(true));
+
+        // Builtin targets are resolved as builtin.
+        let platform = registry
+            .platform("x86_64-unknown-linux-gnu", TargetFeatures::Unknown)
+            .expect("builtin target resolved");
+        assert!(platform.triple().is_builtin());
+
+        // Paths to target JSON files are resolved directly.
+        let path = format!("{VALID_DIR}/x86_64-unknown-myos.json");
+        let triple = TargetRegistry::new()
+            .triple(&path)
+            .expect("target JSON path resolved");
+        assert_eq!(triple.as_str(), "x86_64-unknown-myos");
+        assert!(triple.is_custom());
+
+        // The first definition added by add_dir wins, while add_target replaces.
+        registry
+            .add_dir(VALID_DIR)
+            .expect("valid targets loaded again");
+        assert!(registry.path("x86_64-unknown-myos").is_some());
+        registry
+            .add_target(
+                "x86_64-unknown-myos",
+                r#"{"arch": "x86_64", "target-pointer-width": "64", "llvm-target": "x", "data-layout": "x", "os": "otheros"}"#,
+            )
+            .expect("valid target");
+        assert_eq!(registry.path("x86_64-unknown-myos"), None);
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[test]
+    fn invalid_targets() {
+        let mut registry = TargetRegistry::new();
+        let error = registry
+            .add_dir(INVALID_DIR)
+            .expect_err("invalid target JSON");
+        match &error {
+            TargetRegistryError::InvalidTarget {
+                path: Some(path),
+                error: CustomTripleCreateError::Deserialize { triple, .. },
+            } => {
+                assert_eq!(
+                    path,
+                    &Path::new(INVALID_DIR).join("x86_64-unknown-broken.json")
+                );
+                assert_eq!(triple, "x86_64-unknown-broken");
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        assert!(error.to_string().contains("x86_64-unknown-broken.json"));
+        let source = error
+            .source()
+            .and_then(|source| source.source())
+            .expect("serde error exists")
+            .to_string();
+        assert!(
+            source.contains("target-pointer-width"),
+            "source mentions missing field: {source}"
+        );
+        assert!(registry.is_empty(), "no targets added on error");
+
+        let error = registry
+            .add_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/nonexistent"))
+            .expect_err("nonexistent directory");
+        assert!(
+            matches!(error, TargetRegistryError::ReadDir { .. }),
+            "unexpected error: {error:?}"
+        );
+
+        let error = registry
+            .add_target("foo-bar-baz", "{}")
+            .expect_err("invalid JSON");
+        assert_eq!(error.to_string(), "invalid target JSON for `foo-bar-baz`");
+    }
+}
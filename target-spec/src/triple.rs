@@ -1,14 +1,17 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{errors::TripleParseError, Platform};
+use crate::{
+    errors::{RustcCfgError, TripleParseError},
+    Platform, RustcCfg,
+};
 use cfg_expr::{
     expr::TargetMatcher,
     target_lexicon,
     targets::{get_builtin_target_by_triple, TargetInfo},
     TargetPredicate,
 };
-use std::{borrow::Cow, cmp::Ordering, hash, str::FromStr};
+use std::{borrow::Cow, cmp::Ordering, hash, str::FromStr, sync::Arc};
 
 /// A single, specific target, uniquely identified by a triple.
 ///
@@ -18,7 +21,7 @@ use std::{borrow::Cow, cmp::Ordering, hash, str::FromStr};
 ///
 /// # Standard and custom platforms
 ///
-/// `target-spec` recognizes two kinds of platforms:
+/// `target-spec` recognizes three kinds of platforms:
 ///
 /// * **Standard platforms:** These platforms are only specified by their triple string, either
 ///   directly or via a [`Triple`]. For example, the platform `x86_64-unknown-linux-gnu` is a
@@ -36,6 +39,9 @@ use std::{borrow::Cow, cmp::Ordering, hash, str::FromStr};
 ///   Rust](https://docs.rust-embedded.org/embedonomicon/custom-target.html). Custom platforms are
 ///   used for targets not recognized by Rust.
 ///
+/// * **Platforms from `rustc --print cfg`:** These platforms are specified via a triple string and
+///   a [`RustcCfg`]. See [`Triple::from_rustc_cfg`].
+///
 /// # Examples
 ///
 /// ```
@@ -96,6 +102,24 @@ impl Triple {
         })
     }
 
+    /// Creates a new `Triple` from the given triple string and the output of `rustc --print cfg`
+    /// for it.
+    ///
+    /// The triple string is only used as a name, and does not have to be known to `target-spec`.
+    /// Target predicates are evaluated against `cfg`.
+    pub fn from_rustc_cfg(
+        triple_str: impl Into<Cow<'static, str>>,
+        cfg: RustcCfg,
+    ) -> Result<Self, RustcCfgError> {
+        let target_info = Box::new(cfg.to_target_info(triple_str.into())?);
+        Ok(Self {
+            inner: TripleInner::RustcCfg {
+                target_info,
+                cfg: Arc::new(cfg),
+            },
+        })
+    }
+
     /// Returns the string corresponding to this triple.
     #[inline]
     pub fn as_str(&self) -> &str {
@@ -162,6 +186,16 @@ impl Triple {
         self.inner.is_custom()
     }
 
+    /// Returns true if this triple was created from the output of `rustc --print cfg`.
+    pub fn is_rustc_cfg(&self) -> bool {
+        self.inner.rustc_cfg().is_some()
+    }
+
+    /// Returns the output of `rustc --print cfg` this triple was created from, if any.
+    pub fn rustc_cfg(&self) -> Option<&RustcCfg> {
+        self.inner.rustc_cfg()
+    }
+
     /// Evaluates this triple against the given platform.
     ///
     /// This simply compares `self`'s string representation against the `Triple` the platform is
@@ -207,6 +241,12 @@ enum TripleInner {
         json: String,
     },
 
+    /// A triple created from the output of `rustc --print cfg`.
+    RustcCfg {
+        target_info: Box<TargetInfo>,
+        cfg: Arc<RustcCfg>,
+    },
+
     /// Fall back to the lexicon representation.
     Lexicon {
         triple_str: Cow<'static, str>,
@@ -260,6 +300,7 @@ impl TripleInner {
     fn is_standard(&self) -> bool {
         match self {
             TripleInner::Builtin(_) | TripleInner::Lexicon { .. } => true,
+            TripleInner::RustcCfg { .. } => false,
             #[cfg(feature = "custom")]
             TripleInner::Custom { .. } => false,
         }
@@ -268,7 +309,7 @@ impl TripleInner {
     fn is_builtin(&self) -> bool {
         match self {
             TripleInner::Builtin(_) => true,
-            TripleInner::Lexicon { .. } => false,
+            TripleInner::RustcCfg { .. } | TripleInner::Lexicon { .. } => false,
             #[cfg(feature = "custom")]
             TripleInner::Custom { .. } => false,
         }
@@ -276,7 +317,7 @@ impl TripleInner {
 
     fn is_heuristic(&self) -> bool {
         match self {
-            TripleInner::Builtin(_) => false,
+            TripleInner::Builtin(_) | TripleInner::RustcCfg { .. } => false,
             TripleInner::Lexicon { .. } => true,
             #[cfg(feature = "custom")]
             TripleInner::Custom { .. } => false,
@@ -285,7 +326,9 @@ impl TripleInner {
 
     fn is_custom(&self) -> bool {
         match self {
-            TripleInner::Builtin(_) | TripleInner::Lexicon { .. } => false,
+            TripleInner::Builtin(_)
+            | TripleInner::RustcCfg { .. }
+            | TripleInner::Lexicon { .. } => false,
             #[cfg(feature = "custom")]
             TripleInner::Custom { .. } => true,
         }
@@ -296,6 +339,7 @@ impl TripleInner {
             TripleInner::Builtin(target_info) => target_info.triple.as_str(),
             #[cfg(feature = "custom")]
             TripleInner::Custom { target_info, .. } => target_info.triple.as_str(),
+            TripleInner::RustcCfg { target_info, .. } => target_info.triple.as_str(),
             TripleInner::Lexicon { triple_str, .. } => triple_str,
         }
     }
@@ -305,6 +349,7 @@ impl TripleInner {
             TripleInner::Builtin(target_info) => target_info.matches(tp),
             #[cfg(feature = "custom")]
             TripleInner::Custom { target_info, .. } => target_info.matches(tp),
+            TripleInner::RustcCfg { target_info, .. } => target_info.matches(tp),
             TripleInner::Lexicon { lexicon_triple, .. } => lexicon_triple.matches(tp),
         }
     }
@@ -315,7 +360,14 @@ impl TripleInner {
             TripleInner::Builtin(_) => None,
             #[cfg(feature = "custom")]
             TripleInner::Custom { json, .. } => Some(json),
-            TripleInner::Lexicon { .. } => None,
+            TripleInner::RustcCfg { .. } | TripleInner::Lexicon { .. } => None,
+        }
+    }
+
+    fn rustc_cfg(&self) -> Option<&RustcCfg> {
+        match self {
+            TripleInner::RustcCfg { cfg, .. } => Some(cfg),
+            _ => None,
         }
     }
 
@@ -326,6 +378,9 @@ impl TripleInner {
             }
             #[cfg(feature = "custom")]
             TripleInner::Custom { target_info, .. } => TripleInnerProjected::Custom(target_info),
+            TripleInner::RustcCfg { target_info, cfg } => {
+                TripleInnerProjected::RustcCfg(target_info.triple.as_str(), cfg)
+            }
             TripleInner::Lexicon { triple_str, .. } => TripleInnerProjected::Lexicon(triple_str),
         }
     }
@@ -338,6 +393,8 @@ enum TripleInnerProjected<'a> {
     Builtin(&'a str),
     #[cfg(feature = "custom")]
     Custom(&'a TargetInfo),
+    // The target info is a pure function of the triple and the cfg.
+    RustcCfg(&'a str, &'a RustcCfg),
     Lexicon(&'a str),
 }
 
@@ -392,6 +449,9 @@ mod tests {
             TripleInner::Builtin(_) => {
                 panic!("should not have been able to parse x86_64-pc-darwin as a builtin");
             }
+            TripleInner::RustcCfg { .. } => {
+                panic!("not created from rustc cfg")
+            }
             #[cfg(feature = "custom")]
             TripleInner::Custom { .. } => {
                 panic!("not a custom platform")
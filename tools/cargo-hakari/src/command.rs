@@ -3,20 +3,20 @@
 
 use crate::{
     helpers::{read_contents, regenerate_lockfile},
-    output::{OutputContext, OutputOpts},
+    output::{MessageFormat, OutputContext, OutputOpts},
     publish::publish_hakari,
 };
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::Parser;
-use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use color_eyre::eyre::{bail, Result, WrapErr};
 use guppy::{
-    graph::{PackageGraph, PackageSet},
+    graph::{PackageGraph, PackageMetadata, PackageSet},
     MetadataCommand,
 };
 use hakari::{
     cli_ops::{HakariInit, WorkspaceOps},
     diffy::PatchFormatter,
-    summaries::{HakariConfig, DEFAULT_CONFIG_PATH, FALLBACK_CONFIG_PATH},
+    summaries::{HakariConfig, VerifyErrorsSummary, DEFAULT_CONFIG_PATH, FALLBACK_CONFIG_PATH},
     DepFormatVersion, HakariBuilder, HakariCargoToml, HakariOutputOptions, TomlOutError,
 };
 use log::{error, info};
@@ -157,8 +157,8 @@ impl Command {
                 })
             }
             Command::WithBuilder(cmd) => {
-                let (builder, hakari_output) = make_builder_and_output(&package_graph)?;
-                cmd.exec(builder, hakari_output, output)
+                let (builders, hakari_output) = make_builders_and_output(&package_graph)?;
+                cmd.exec(builders, hakari_output, output)
             }
         }
     }
@@ -232,15 +232,25 @@ enum CommandWithBuilder {
     /// to be added if they're built with a second feature set. These cases are marked as
     /// "post-compute fixup".
     ///
-    /// Currently, this command only prints out the different feature sets that get built for a
-    /// dependency, and the workspace crates responsible for them. Further investigation can be done
-    /// through `cargo tree`. In the future, the scope of this command may be extended to provide
-    /// information about intermediate dependencies as well.
+    /// For each feature set, the command also prints a chain of intermediate dependencies through
+    /// which each workspace crate reaches the dependency. Dev-only and build dependencies along
+    /// the chain are marked as such.
     Explain {
         /// The name of the dependency, as present in the workspace-hack.
         dep_name: String,
     },
 
+    /// Report the extra crates and features the workspace-hack adds to individual builds.
+    ///
+    /// For each workspace crate, simulate building it alone with and without the workspace-hack
+    /// (with default features, excluding dev-dependencies), and print the third-party crates that
+    /// are only built with the workspace-hack, and the crates that are built with extra features.
+    /// If platforms are configured in hakari.toml, each crate is analyzed once per platform.
+    ///
+    /// The workspace-hack as currently present in the workspace is used, so run `cargo hakari
+    /// generate` first. The results can be used to tune the final-excludes option.
+    Impact,
+
     /// Publish a package after temporarily removing the workspace-hack dependency from it.
     ///
     /// For more information about publishing options,
@@ -273,54 +283,95 @@ enum CommandWithBuilder {
 impl CommandWithBuilder {
     fn exec(
         self,
-        builder: HakariBuilder<'_>,
+        builders: Vec<HakariBuilder<'_>>,
         hakari_output: HakariOutputOptions,
         output: OutputContext,
     ) -> Result<i32> {
-        let hakari_package = *builder
-            .hakari_package()
-            .expect("hakari-package must be specified in hakari.toml");
+        let hakari_packages: Vec<_> = builders
+            .iter()
+            .map(|builder| {
+                *builder
+                    .hakari_package()
+                    .expect("hakari-package must be specified in hakari.toml")
+            })
+            .collect();
+        let graph = builders
+            .first()
+            .expect("at least one builder is always returned")
+            .graph();
+        // With more than one workspace-hack, messages are prefixed with the package they're for.
+        let prefix = |package: &PackageMetadata<'_>| {
+            if hakari_packages.len() > 1 {
+                format!("{}: ", package.name().style(output.styles.package_name))
+            } else {
+                String::new()
+            }
+        };
 
         match self {
             CommandWithBuilder::Generate { diff } => {
-                let package_graph = builder.graph();
-                let hakari = builder.compute();
-                let toml_out = match hakari.to_toml_string(&hakari_output) {
-                    Ok(toml_out) => toml_out,
-                    Err(TomlOutError::UnrecognizedRegistry {
-                        package_id,
-                        registry_url,
-                    }) => {
-                        // Print out a better error message for this more common use case.
-                        let package = package_graph
-                            .metadata(&package_id)
-                            .expect("package ID obtained from the same graph");
-                        error!(
-                            "unrecognized registry URL {} found for {} v{}\n\
-                             (add to [registries] section of {})",
-                            registry_url.style(output.styles.registry_url),
-                            package.name().style(output.styles.package_name),
-                            package.version().style(output.styles.package_version),
-                            "hakari.toml".style(output.styles.config_path),
-                        );
-                        // 102 is picked pretty arbitrarily because regular errors exit with 101.
-                        return Ok(102);
-                    }
-                    Err(err) => Err(err).with_context(|| "error generating new hakari.toml")?,
-                };
+                let dep_format_version = builders[0].dep_format_version();
+                // Compute all outputs before writing any of them, so that an error for one
+                // workspace-hack doesn't leave the others half-updated.
+                let mut outputs = Vec::with_capacity(builders.len());
+                let mut unrecognized_registry = false;
+                for builder in builders {
+                    let hakari = builder.compute();
+                    let toml_out = match hakari.to_toml_string(&hakari_output) {
+                        Ok(toml_out) => toml_out,
+                        Err(TomlOutError::UnrecognizedRegistry {
+                            package_id,
+                            registry_url,
+                        }) => {
+                            // Print out a better error message for this more common use case.
+                            let package = graph
+                                .metadata(&package_id)
+                                .expect("package ID obtained from the same graph");
+                            error!(
+                                "unrecognized registry URL {} found for {} v{}\n\
+                                 (add to [registries] section of {})",
+                                registry_url.style(output.styles.registry_url),
+                                package.name().style(output.styles.package_name),
+                                package.version().style(output.styles.package_version),
+                                "hakari.toml".style(output.styles.config_path),
+                            );
+                            unrecognized_registry = true;
+                            continue;
+                        }
+                        Err(err) => Err(err).with_context(|| "error generating new hakari.toml")?,
+                    };
+
+                    let existing_toml = hakari
+                        .read_toml()
+                        .expect("hakari-package must be specified")?;
+                    outputs.push((existing_toml, toml_out));
+                }
+                if unrecognized_registry {
+                    // 102 is picked pretty arbitrarily because regular errors exit with 101.
+                    return Ok(102);
+                }
 
-                let existing_toml = hakari
-                    .read_toml()
-                    .expect("hakari-package must be specified")?;
+                let mut exit_code = 0;
+                for ((existing_toml, toml_out), hakari_package) in
+                    outputs.into_iter().zip(&hakari_packages)
+                {
+                    exit_code = exit_code.max(write_to_cargo_toml(
+                        existing_toml,
+                        &toml_out,
+                        diff,
+                        hakari_package,
+                        &prefix(hakari_package),
+                        output.clone(),
+                    )?);
+                }
 
-                let exit_code =
-                    write_to_cargo_toml(existing_toml, &toml_out, diff, output.clone())?;
-                if hakari.builder().dep_format_version() < DepFormatVersion::latest() {
+                // The format version is shared across all workspace-hacks.
+                if dep_format_version < DepFormatVersion::latest() {
                     info!(
                         "new hakari format version available: {latest} (current: {})\n\
                         (add or update `dep-format-version = \"{latest}\"` in {}, then run \
                         `cargo hakari generate && cargo hakari manage-deps`)",
-                        hakari.builder().dep_format_version(),
+                        dep_format_version,
                         "hakari.toml".style(output.styles.config_path),
                         latest = DepFormatVersion::latest(),
                     );
@@ -328,41 +379,70 @@ impl CommandWithBuilder {
 
                 Ok(exit_code)
             }
-            CommandWithBuilder::Verify => match builder.verify() {
-                Ok(()) => {
-                    info!(
-                        "{} works correctly",
-                        hakari_package.name().style(output.styles.package_name),
-                    );
-                    Ok(0)
-                }
-                Err(errs) => {
-                    let mut display = errs.display();
-                    if output.color.is_enabled() {
-                        display.colorize();
+            CommandWithBuilder::Verify => {
+                let mut exit_code = 0;
+                for (builder, hakari_package) in builders.into_iter().zip(&hakari_packages) {
+                    let verify = builder.verify();
+                    if output.message_format == MessageFormat::Json {
+                        let summary = match &verify {
+                            Ok(()) => VerifyErrorsSummary::default(),
+                            Err(errs) => errs.to_summary(),
+                        };
+                        print_json_message(
+                            "verify",
+                            hakari_package,
+                            serde_json::to_value(summary)?,
+                        );
+                        if verify.is_err() {
+                            exit_code = 1;
+                        }
+                        continue;
+                    }
+
+                    match verify {
+                        Ok(()) => {
+                            info!(
+                                "{} works correctly",
+                                hakari_package.name().style(output.styles.package_name),
+                            );
+                        }
+                        Err(errs) => {
+                            let mut display = errs.display();
+                            if output.color.is_enabled() {
+                                display.colorize();
+                            }
+                            info!(
+                                "{} didn't work correctly:\n{}",
+                                hakari_package.name().style(output.styles.package_name),
+                                display,
+                            );
+                            exit_code = 1;
+                        }
                     }
-                    info!(
-                        "{} didn't work correctly:\n{}",
-                        hakari_package.name().style(output.styles.package_name),
-                        display,
-                    );
-                    Ok(1)
                 }
-            },
+                Ok(exit_code)
+            }
             CommandWithBuilder::ManageDeps {
                 packages,
                 dry_run,
                 yes,
             } => {
-                let ops = builder
-                    .manage_dep_ops(&packages.to_package_set(builder.graph())?)
-                    .expect("hakari-package must be specified in hakari.toml");
-                if ops.is_empty() {
+                let package_set = packages.to_package_set(graph)?;
+                let mut ops = builders.iter().map(|builder| {
+                    builder
+                        .manage_dep_ops(&package_set)
+                        .expect("hakari-package must be specified in hakari.toml")
+                });
+                let mut all_ops = ops.next().expect("at least one builder is always returned");
+                for ops in ops {
+                    all_ops.merge(ops);
+                }
+                if all_ops.is_empty() {
                     info!("no operations to perform");
                     return Ok(0);
                 }
 
-                apply_on_dialog(dry_run, yes, &ops, &output, || {
+                apply_on_dialog(dry_run, yes, &all_ops, &output, || {
                     regenerate_lockfile(output.clone())
                 })
             }
@@ -371,53 +451,119 @@ impl CommandWithBuilder {
                 dry_run,
                 yes,
             } => {
-                let ops = builder
-                    .remove_dep_ops(&packages.to_package_set(builder.graph())?, false)
-                    .expect("hakari-package must be specified in hakari.toml");
-                if ops.is_empty() {
+                let package_set = packages.to_package_set(graph)?;
+                let mut ops = builders.iter().map(|builder| {
+                    builder
+                        .remove_dep_ops(&package_set, false)
+                        .expect("hakari-package must be specified in hakari.toml")
+                });
+                let mut all_ops = ops.next().expect("at least one builder is always returned");
+                for ops in ops {
+                    all_ops.merge(ops);
+                }
+                if all_ops.is_empty() {
                     info!("no operations to perform");
                     return Ok(0);
                 }
 
-                apply_on_dialog(dry_run, yes, &ops, &output, || {
+                apply_on_dialog(dry_run, yes, &all_ops, &output, || {
                     regenerate_lockfile(output.clone())
                 })
             }
             CommandWithBuilder::Explain {
                 dep_name: crate_name,
             } => {
-                let hakari = builder.compute();
-                let toml_name_map = hakari.toml_name_map();
-                let dep = toml_name_map.get(crate_name.as_str()).ok_or_else(|| {
-                    eyre!(
+                let mut found = false;
+                for (builder, hakari_package) in builders.into_iter().zip(&hakari_packages) {
+                    let hakari = builder.compute();
+                    let toml_name_map = hakari.toml_name_map();
+                    let Some(dep) = toml_name_map.get(crate_name.as_str()) else {
+                        continue;
+                    };
+                    found = true;
+
+                    let explain = hakari
+                        .explain(dep.id())
+                        .expect("package ID should be known since it was in the output");
+                    if output.message_format == MessageFormat::Json {
+                        print_json_message(
+                            "explain",
+                            hakari_package,
+                            serde_json::to_value(explain.to_summary())?,
+                        );
+                        continue;
+                    }
+                    let mut display = explain.display();
+                    if output.color.is_enabled() {
+                        display.colorize();
+                    }
+                    info!("{}\n{}", prefix(hakari_package), display);
+                }
+
+                if !found {
+                    bail!(
                         "crate name '{}' not found in workspace-hack\n\
                         (hint: check spelling, or regenerate workspace-hack with `cargo hakari generate`)",
                         crate_name
-                    )
-                })?;
-
-                let explain = hakari
-                    .explain(dep.id())
-                    .expect("package ID should be known since it was in the output");
-                let mut display = explain.display();
-                if output.color.is_enabled() {
-                    display.colorize();
+                    );
+                }
+                Ok(0)
+            }
+            CommandWithBuilder::Impact => {
+                for (builder, hakari_package) in builders.iter().zip(&hakari_packages) {
+                    let impact = builder
+                        .impact()
+                        .expect("hakari-package must be specified in hakari.toml");
+                    if output.message_format == MessageFormat::Json {
+                        print_json_message(
+                            "impact",
+                            hakari_package,
+                            serde_json::to_value(impact.to_summary())?,
+                        );
+                        continue;
+                    }
+
+                    let mut display = impact.display();
+                    if output.color.is_enabled() {
+                        display.colorize();
+                    }
+                    info!("{}\n{}", prefix(hakari_package), display);
                 }
-                info!("\n{}", display);
                 Ok(0)
             }
             CommandWithBuilder::Publish {
                 package,
                 pass_through,
             } => {
+                // Use the workspace-hack this package is a member of.
+                let package_id = graph.workspace().member_by_name(&package)?.id();
+                let idx = builders
+                    .iter()
+                    .position(|builder| !builder.is_excluded(package_id).unwrap_or(true))
+                    .unwrap_or(0);
+                let builder = builders
+                    .into_iter()
+                    .nth(idx)
+                    .expect("index is within bounds");
                 publish_hakari(&package, builder, &pass_through, output)?;
                 Ok(0)
             }
             CommandWithBuilder::Disable { diff } => {
-                let existing_toml = builder
-                    .read_toml()
-                    .expect("hakari-package must be specified")?;
-                write_to_cargo_toml(existing_toml, DISABLE_MESSAGE, diff, output)
+                let mut exit_code = 0;
+                for (builder, hakari_package) in builders.iter().zip(&hakari_packages) {
+                    let existing_toml = builder
+                        .read_toml()
+                        .expect("hakari-package must be specified")?;
+                    exit_code = exit_code.max(write_to_cargo_toml(
+                        existing_toml,
+                        DISABLE_MESSAGE,
+                        diff,
+                        hakari_package,
+                        &prefix(hakari_package),
+                        output.clone(),
+                    )?);
+                }
+                Ok(exit_code)
             }
         }
     }
@@ -467,9 +613,9 @@ fn cwd_rel_to_workspace_rel(path: &Utf8Path, workspace_root: &Utf8Path) -> Resul
         })
 }
 
-fn make_builder_and_output(
+fn make_builders_and_output(
     package_graph: &PackageGraph,
-) -> Result<(HakariBuilder<'_>, HakariOutputOptions)> {
+) -> Result<(Vec<HakariBuilder<'_>>, HakariOutputOptions)> {
     let (config_path, contents) = read_contents(
         package_graph.workspace().root(),
         [DEFAULT_CONFIG_PATH, FALLBACK_CONFIG_PATH],
@@ -480,28 +626,37 @@ fn make_builder_and_output(
         .parse()
         .wrap_err_with(|| format!("error deserializing Hakari config at {}", config_path))?;
 
-    let builder = config
+    let builders = config
         .builder
-        .to_hakari_builder(package_graph)
+        .to_hakari_builders(package_graph)
         .wrap_err_with(|| format!("error resolving Hakari config at {}", config_path))?;
     let hakari_output = config.output.to_options();
 
-    Ok((builder, hakari_output))
+    Ok((builders, hakari_output))
 }
 
 fn write_to_cargo_toml(
     existing_toml: HakariCargoToml,
     new_contents: &str,
     diff: bool,
+    hakari_package: &PackageMetadata<'_>,
+    prefix: &str,
     output: OutputContext,
 ) -> Result<i32> {
+    if output.message_format == MessageFormat::Json {
+        let summary = existing_toml.to_diff_summary(new_contents);
+        print_json_message("diff", hakari_package, serde_json::to_value(summary)?);
+    }
+
     if diff {
         let patch = existing_toml.diff_toml(new_contents);
-        let mut formatter = PatchFormatter::new();
-        if output.color.is_enabled() {
-            formatter = formatter.with_color();
+        if output.message_format == MessageFormat::Human {
+            let mut formatter = PatchFormatter::new();
+            if output.color.is_enabled() {
+                formatter = formatter.with_color();
+            }
+            info!("{prefix}\n{}", formatter.fmt_patch(&patch));
         }
-        info!("\n{}", formatter.fmt_patch(&patch));
         if patch.hunks().is_empty() {
             // No differences.
             Ok(0)
@@ -510,18 +665,31 @@ fn write_to_cargo_toml(
         }
     } else {
         if !existing_toml.is_changed(new_contents) {
-            info!("no changes detected");
+            info!("{prefix}no changes detected");
         } else {
             existing_toml
                 .write_to_file(new_contents)
                 .with_context(|| "error writing updated Hakari contents")?;
-            info!("contents updated");
+            info!("{prefix}contents updated");
             regenerate_lockfile(output)?;
         }
         Ok(0)
     }
 }
 
+/// Prints a message for `--message-format json` to stdout, as a single line.
+fn print_json_message(
+    reason: &str,
+    hakari_package: &PackageMetadata<'_>,
+    value: serde_json::Value,
+) {
+    let mut message = serde_json::Map::new();
+    message.insert("reason".to_owned(), reason.into());
+    message.insert("workspace-hack".to_owned(), hakari_package.name().into());
+    message.insert(reason.to_owned(), value);
+    println!("{}", serde_json::Value::Object(message));
+}
+
 fn apply_on_dialog(
     dry_run: bool,
     yes: bool,
@@ -13,7 +13,8 @@
 //!
 //! ## hakari-package
 //!
-//! The name of the hakari-managed crate in the workspace. Must be specified. For example:
+//! The name of the hakari-managed crate in the workspace. Must be specified, unless
+//! [additional workspace-hacks](#workspace-hack) cover every workspace member. For example:
 //!
 //! ```toml
 //! hakari-package = "my-workspace-hack"
@@ -212,6 +213,37 @@
 //! unify-target-host = "replicate-target-on-host"
 //! ```
 //!
+//! ## workspace-hack
+//!
+//! Additional workspace-hack packages, each with its own set of workspace members.
+//!
+//! By default, every workspace member depends on the single `hakari-package`, which unifies
+//! features across the whole workspace. In workspaces where groups of crates share few
+//! dependencies (for example, a server stack and a few small command-line tools), this can cause
+//! crates to build many more features than they need. Each `[[workspace-hack]]` table lists a
+//! workspace-hack package along with the members that should depend on it.
+//!
+//! Each workspace-hack is generated and verified independently, only considering its own
+//! members. `cargo hakari manage-deps` adds a dependency on the right workspace-hack to each
+//! member, and removes dependencies on the others. Workspace members not listed in any
+//! `[[workspace-hack]]` table depend on `hakari-package`. If `hakari-package` isn't specified,
+//! every workspace member other than the workspace-hack packages must be listed in some
+//! `[[workspace-hack]]` table, otherwise `cargo hakari` reports an error.
+//!
+//! `members` accepts the same format as `traversal-excludes` above, though only workspace members
+//! are considered. If a workspace member is listed in more than one `[[workspace-hack]]` table,
+//! the first one is used.
+//!
+//! Defaults to an empty list.
+//!
+//! ```toml
+//! hakari-package = "server-workspace-hack"
+//!
+//! [[workspace-hack]]
+//! package = "tools-workspace-hack"
+//! members = { workspace-members = ["my-cli", "my-other-cli"] }
+//! ```
+//!
 //! ## output-single-feature
 //!
 //! By default, `cargo hakari` only outputs lines corresponding to third-party dependencies which
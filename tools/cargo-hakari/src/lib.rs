@@ -132,6 +132,10 @@
 //! All `cargo hakari` commands take a `--quiet` option to suppress output, though showing diff
 //! output in CI is often useful.
 //!
+//! For tools that consume the results, `generate`, `verify`, `explain` and `impact` also accept
+//! `--message-format json`. With this option, results are printed to stdout as JSON, one line per
+//! workspace-hack package.
+//!
 //! ### 3. Consider a `[patch]` directive
 //!
 //! If your workspace is depended on as a Git or path dependency, it is **strongly recommended**
@@ -165,6 +169,17 @@
 //! report](https://github.com/guppy-rs/guppy/issues/new) with more information would be greatly
 //! appreciated!
 //!
+//! ### How much extra work does the workspace-hack add to individual builds?
+//!
+//! ```sh
+//! cargo hakari impact
+//! ```
+//!
+//! For each workspace crate built alone, this command prints out the third-party crates and
+//! features that are only built because of the workspace-hack, once for each configured platform.
+//! Crates that add a lot of extra work for a few workspace crates are good candidates for
+//! `final-excludes`.
+//!
 //! ###
 //! ## Publishing a crate
 //!
@@ -37,6 +37,18 @@ pub(crate) struct OutputOpts {
         default_value_t = Color::Auto,
     )]
     pub(crate) color: Color,
+
+    /// Output format for results of explain, impact, verify and generate
+    ///
+    /// With `json`, results are printed to stdout as JSON, one object per line and per
+    /// workspace-hack package.
+    #[clap(
+        long,
+        value_enum,
+        global = true,
+        default_value_t = MessageFormat::Human,
+    )]
+    pub(crate) message_format: MessageFormat,
 }
 
 impl OutputOpts {
@@ -45,6 +57,7 @@ impl OutputOpts {
             quiet,
             verbose,
             color,
+            message_format,
         } = self;
         let level = if quiet {
             LevelFilter::Error
@@ -70,6 +83,7 @@ impl OutputOpts {
             quiet,
             verbose,
             color,
+            message_format,
             styles: Arc::new(styles),
         }
     }
@@ -81,6 +95,7 @@ pub(crate) struct OutputContext {
     pub(crate) quiet: bool,
     pub(crate) verbose: bool,
     pub(crate) color: Color,
+    pub(crate) message_format: MessageFormat,
     pub(crate) styles: Arc<Styles>,
 }
 
@@ -172,6 +187,13 @@ impl std::str::FromStr for Color {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+#[must_use]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
 #[derive(Clone, Debug, Default)]
 pub(crate) struct Styles {
     pub(crate) config_path: Style,
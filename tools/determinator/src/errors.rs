@@ -93,3 +93,46 @@ impl fmt::Display for RulesErrorKind {
         }
     }
 }
+
+/// An error that occurred while parsing a `CODEOWNERS` file.
+#[derive(Debug)]
+pub struct CodeOwnersError {
+    line_number: usize,
+    err: globset::Error,
+}
+
+impl CodeOwnersError {
+    /// Returns the 1-based line number that failed to parse.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Returns the glob that failed to parse, if one was present.
+    pub fn glob(&self) -> Option<&str> {
+        self.err.glob()
+    }
+
+    // ---
+    // Internal constructors
+    // ---
+
+    pub(crate) fn glob_parse(line_number: usize, err: globset::Error) -> Self {
+        Self { line_number, err }
+    }
+}
+
+impl fmt::Display for CodeOwnersError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "error while parsing CODEOWNERS line {}: {}",
+            self.line_number, self.err
+        )
+    }
+}
+
+impl error::Error for CodeOwnersError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.err)
+    }
+}
@@ -138,6 +138,12 @@
 //!
 //! For more about custom rules, see the documentation for the [`rules` module](crate::rules).
 //!
+//! # Code owners
+//!
+//! Once the affected set is known, the [`owners` module](crate::owners) can be used to figure out
+//! who should review a change, by mapping workspace packages to owners through a `CODEOWNERS`
+//! file.
+//!
 //! # Limitations
 //!
 //! While the determinator can bring significant benefits to CI and local workflows, its model is
@@ -246,6 +252,7 @@
 
 mod determinator;
 pub mod errors;
+pub mod owners;
 mod paths0;
 pub mod rules;
 
@@ -0,0 +1,310 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Map workspace packages to owners using a `CODEOWNERS` file.
+//!
+//! The determinator figures out which packages are affected by a change. The next question is
+//! usually who should review it. This module parses a
+//! [`CODEOWNERS`](https://docs.github.com/en/repositories/managing-your-repositorys-settings-and-features/customizing-your-repository/about-code-owners)
+//! file and uses it to find the owners of a set of packages, such as
+//! [`DeterminatorSet::affected_set`](crate::DeterminatorSet::affected_set) or the result of a
+//! reverse dependency query.
+//!
+//! # File format
+//!
+//! Each non-empty line that doesn't start with `#` consists of a pattern followed by zero or more
+//! owners, separated by whitespace. Patterns follow `.gitignore` rules:
+//! * A pattern that starts with `/` or contains a `/` in the middle is matched relative to the
+//!   workspace root. Otherwise, it matches at any depth.
+//! * A pattern that ends with `/` only matches directories.
+//! * `*` doesn't match across `/`, while `**` does.
+//!
+//! As with GitHub, the last matching line wins. A line with no owners means that matching paths
+//! don't have an owner.
+//!
+//! # Package ownership
+//!
+//! A workspace package is owned by the owners of its `Cargo.toml`. For example, with:
+//!
+//! ```text
+//! *.toml      @build-team
+//! /guppy/     @guppy-team
+//! ```
+//!
+//! the package at `guppy` is owned by `@guppy-team`, since that line matches `guppy/Cargo.toml`
+//! and comes last.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use determinator::owners::CodeOwners;
+//! use guppy::CargoMetadata;
+//!
+//! let metadata = CargoMetadata::parse_json(include_str!("../../../fixtures/guppy/metadata_guppy_869476c.json")).unwrap();
+//! let graph = metadata.build_graph().unwrap();
+//!
+//! let code_owners = CodeOwners::parse("* @core\n/tools/ @tools-team\n").unwrap();
+//! let package_owners = code_owners.package_owners(&graph.workspace());
+//!
+//! // Find the owners for every package that depends on guppy.
+//! let guppy_id = graph.workspace().member_by_path("guppy").unwrap().id();
+//! let dependents = graph.query_reverse([guppy_id]).unwrap().resolve();
+//! let report = package_owners.report(&dependents);
+//! for (owner, packages) in report.by_owner() {
+//!     println!("{}: {} packages", owner, packages.len());
+//! }
+//! ```
+
+use crate::errors::CodeOwnersError;
+use ahash::AHashMap;
+use camino::Utf8Path;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use guppy::{
+    graph::{DependencyDirection, PackageMetadata, PackageSet, Workspace},
+    PackageId,
+};
+use std::collections::BTreeMap;
+
+/// A parsed `CODEOWNERS` file.
+///
+/// For more about the file format, see the [module-level documentation](crate::owners).
+#[derive(Clone, Debug)]
+pub struct CodeOwners {
+    rules: Vec<OwnerRule>,
+}
+
+impl CodeOwners {
+    /// Parses the contents of a `CODEOWNERS` file.
+    pub fn parse(s: &str) -> Result<Self, CodeOwnersError> {
+        let rules = s
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    return None;
+                }
+                Some(OwnerRule::parse(idx + 1, line))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Returns the owners for this path, relative to the workspace root.
+    ///
+    /// Returns `None` if no line matches the path. Returns an empty slice if the last matching
+    /// line has no owners.
+    pub fn owners_for_path(&self, path: impl AsRef<Utf8Path>) -> Option<&[String]> {
+        let path = path.as_ref();
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.glob_set.is_match(path))
+            .map(|rule| rule.owners.as_slice())
+    }
+
+    /// Maps every package in this workspace to its owners.
+    pub fn package_owners<'a>(&'a self, workspace: &Workspace<'a>) -> PackageOwners<'a> {
+        let owners = workspace
+            .iter_by_path()
+            .filter_map(|(path, package)| {
+                let owners = self.owners_for_path(path.join("Cargo.toml"))?;
+                Some((package.id(), owners))
+            })
+            .collect();
+        PackageOwners { owners }
+    }
+}
+
+/// The owners of every package in a workspace.
+///
+/// Returned by [`CodeOwners::package_owners`].
+#[derive(Clone, Debug)]
+pub struct PackageOwners<'a> {
+    owners: AHashMap<&'a PackageId, &'a [String]>,
+}
+
+impl<'a> PackageOwners<'a> {
+    /// Returns the owners of this package.
+    ///
+    /// Returns `None` if the package isn't in the workspace or doesn't have an owner.
+    pub fn owners_for(&self, package_id: &PackageId) -> Option<&'a [String]> {
+        self.owners
+            .get(package_id)
+            .copied()
+            .filter(|owners| !owners.is_empty())
+    }
+
+    /// Reports the owners of the workspace packages in this set.
+    ///
+    /// Packages outside the workspace are ignored.
+    pub fn report(&self, package_set: &PackageSet<'a>) -> OwnersReport<'a> {
+        let mut by_owner: BTreeMap<&'a str, Vec<PackageMetadata<'a>>> = BTreeMap::new();
+        let mut unowned = vec![];
+
+        for package in package_set
+            .packages(DependencyDirection::Forward)
+            .filter(|package| package.in_workspace())
+        {
+            match self.owners_for(package.id()) {
+                Some(owners) => {
+                    for owner in owners {
+                        by_owner.entry(owner).or_default().push(package);
+                    }
+                }
+                None => unowned.push(package),
+            }
+        }
+
+        OwnersReport { by_owner, unowned }
+    }
+}
+
+/// The owners of a set of packages.
+///
+/// Returned by [`PackageOwners::report`].
+#[derive(Clone, Debug)]
+pub struct OwnersReport<'a> {
+    by_owner: BTreeMap<&'a str, Vec<PackageMetadata<'a>>>,
+    unowned: Vec<PackageMetadata<'a>>,
+}
+
+impl<'a> OwnersReport<'a> {
+    /// Returns the owners of the set, sorted by name.
+    pub fn owners<'b>(&'b self) -> impl ExactSizeIterator<Item = &'a str> + 'b {
+        self.by_owner.keys().copied()
+    }
+
+    /// Returns each owner along with the packages in the set that they own, sorted by owner name.
+    ///
+    /// Packages are returned in forward topological order.
+    pub fn by_owner<'b>(
+        &'b self,
+    ) -> impl ExactSizeIterator<Item = (&'a str, &'b [PackageMetadata<'a>])> + 'b {
+        self.by_owner
+            .iter()
+            .map(|(owner, packages)| (*owner, packages.as_slice()))
+    }
+
+    /// Returns the workspace packages in the set that don't have an owner.
+    pub fn unowned(&self) -> &[PackageMetadata<'a>] {
+        &self.unowned
+    }
+}
+
+#[derive(Clone, Debug)]
+struct OwnerRule {
+    glob_set: GlobSet,
+    owners: Vec<String>,
+}
+
+impl OwnerRule {
+    fn parse(line_number: usize, line: &str) -> Result<Self, CodeOwnersError> {
+        let mut parts = line.split_whitespace();
+        let pattern = parts.next().expect("line is non-empty");
+        let owners = parts.map(|owner| owner.to_owned()).collect();
+
+        let mut builder = GlobSetBuilder::new();
+        for glob in pattern_to_globs(pattern) {
+            let glob = GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()
+                .map_err(|err| CodeOwnersError::glob_parse(line_number, err))?;
+            builder.add(glob);
+        }
+        let glob_set = builder
+            .build()
+            .map_err(|err| CodeOwnersError::glob_parse(line_number, err))?;
+
+        Ok(Self { glob_set, owners })
+    }
+}
+
+/// Converts a `.gitignore`-style pattern to globs matched against paths relative to the root.
+fn pattern_to_globs(pattern: &str) -> Vec<String> {
+    let (pattern, dir_only) = match pattern.strip_suffix('/') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let pattern = match pattern.strip_prefix('/') {
+        Some(pattern) => pattern.to_owned(),
+        // Patterns without a slash in them match at any depth.
+        None if !pattern.contains('/') => format!("**/{}", pattern),
+        None => pattern.to_owned(),
+    };
+
+    // A pattern matching a directory matches everything under it. A last segment with a wildcard
+    // in it only matches entries within its parent directory (e.g. `docs/*` doesn't match
+    // `docs/a/b.md`), so it isn't treated as naming a directory unless it ends with a slash.
+    let last_segment = pattern.rsplit('/').next().unwrap_or(&pattern);
+    let has_wildcard = last_segment.contains(['*', '?', '[']);
+    if dir_only {
+        vec![format!("{}/**", pattern)]
+    } else if has_wildcard {
+        vec![pattern]
+    } else {
+        vec![format!("{}/**", pattern), pattern]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owners_for_path() {
+        let s = r#"
+        # Comment
+        *           @default
+        *.toml      @build
+        /docs/      @docs
+        src/        @src
+        /tools/hakari @hakari
+        /tools/hakari/README.md
+        "#;
+        let code_owners = CodeOwners::parse(s).expect("CODEOWNERS parsed");
+
+        let cases: &[(&str, Option<&[&str]>)] = &[
+            ("README.md", Some(&["@default"])),
+            ("guppy/Cargo.toml", Some(&["@build"])),
+            ("docs/index.md", Some(&["@docs"])),
+            ("docs/nested/Cargo.toml", Some(&["@docs"])),
+            // Not anchored, so this matches at any depth.
+            ("guppy/src/lib.rs", Some(&["@src"])),
+            // Matches directories only.
+            ("foo/docs", Some(&["@default"])),
+            ("tools/hakari/Cargo.toml", Some(&["@hakari"])),
+            ("tools/hakari/README.md", Some(&[])),
+        ];
+        for (path, expected) in cases {
+            let actual = code_owners.owners_for_path(path);
+            let actual: Option<Vec<_>> =
+                actual.map(|owners| owners.iter().map(|s| s.as_str()).collect());
+            assert_eq!(actual.as_deref(), *expected, "owners for {}", path);
+        }
+
+        let empty = CodeOwners::parse("").expect("empty CODEOWNERS parsed");
+        assert_eq!(empty.owners_for_path("README.md"), None, "no rules match");
+    }
+
+    #[test]
+    fn wildcard_last_segment() {
+        let code_owners = CodeOwners::parse("docs/* @docs").expect("CODEOWNERS parsed");
+        assert_eq!(
+            code_owners.owners_for_path("docs/x.md"),
+            Some(&["@docs".to_owned()][..]),
+            "docs/* matches files directly under docs"
+        );
+        assert_eq!(
+            code_owners.owners_for_path("docs/a/x.md"),
+            None,
+            "docs/* doesn't match nested files"
+        );
+    }
+
+    #[test]
+    fn parse_error() {
+        let err = CodeOwners::parse("* @a\n\n[z-a] @b\n").expect_err("invalid glob");
+        assert_eq!(err.line_number(), 3, "line number is correct");
+    }
+}
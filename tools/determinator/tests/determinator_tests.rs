@@ -5,6 +5,7 @@
 
 use cfg_if::cfg_if;
 use determinator::{
+    owners::CodeOwners,
     rules::{DeterminatorRules, PathMatch, RuleIndex},
     Determinator, Utf8Paths0,
 };
@@ -439,6 +440,94 @@ fn guppy_match_paths() {
     }
 }
 
+#[test]
+fn guppy_owners() {
+    let old = JsonFixture::metadata_guppy_869476c();
+    let new = JsonFixture::metadata_guppy_c9b4f76();
+    let code_owners = CodeOwners::parse(
+        r#"
+        * @core
+        /internal-tools/ @tools
+        /target-spec/ @platforms @core
+        /guppy-summaries/
+        "#,
+    )
+    .expect("CODEOWNERS parsed");
+    let workspace = new.graph().workspace();
+    let package_owners = code_owners.package_owners(&workspace);
+
+    let mut determinator = Determinator::new(old.graph(), new.graph());
+    determinator.add_changed_paths(vec!["target-spec/src/lib.rs"]);
+    let determinator_set = determinator.compute();
+
+    let report = package_owners.report(&determinator_set.affected_set);
+    let by_owner: Vec<(&str, Vec<&str>)> = report
+        .by_owner()
+        .map(|(owner, packages)| {
+            let mut names: Vec<_> = packages.iter().map(|package| package.name()).collect();
+            names.sort_unstable();
+            (owner, names)
+        })
+        .collect();
+    assert_eq!(
+        by_owner,
+        vec![
+            (
+                "@core",
+                vec![
+                    "cargo-guppy",
+                    "fixtures",
+                    "guppy",
+                    "guppy-cmdlib",
+                    "target-spec"
+                ],
+            ),
+            ("@platforms", vec!["target-spec"]),
+            (
+                "@tools",
+                vec!["cargo-compare", "fixture-manager", "guppy-benchmarks"],
+            ),
+        ],
+        "owners of affected set"
+    );
+    assert!(
+        report.unowned().is_empty(),
+        "every affected package is owned"
+    );
+
+    // Owners for a reverse dependency query.
+    let summaries = workspace
+        .member_by_path("guppy-summaries")
+        .expect("guppy-summaries is in the workspace");
+    assert_eq!(
+        package_owners.owners_for(summaries.id()),
+        None,
+        "guppy-summaries has no owners"
+    );
+    let dependents = new
+        .graph()
+        .query_reverse([summaries.id()])
+        .expect("valid package ID")
+        .resolve();
+    let report = package_owners.report(&dependents);
+    let unowned: Vec<_> = report
+        .unowned()
+        .iter()
+        .map(|package| package.name())
+        .collect();
+    assert_eq!(
+        unowned,
+        vec!["guppy-summaries"],
+        "only guppy-summaries is unowned"
+    );
+    assert!(
+        report
+            .owners()
+            .all(|owner| owner == "@core" || owner == "@tools"),
+        "dependents of guppy-summaries are owned by @core or @tools"
+    );
+}
+
 static GIT_MATCH_PATHS_DIFF: &str =
     include_str!("../../../fixtures/determinator-paths/git-diff.out");
 
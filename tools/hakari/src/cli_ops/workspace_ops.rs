@@ -47,6 +47,14 @@ impl<'g, 'a> WorkspaceOps<'g, 'a> {
         self.ops.is_empty()
     }
 
+    /// Adds the operations in `other` to the end of this set of operations.
+    ///
+    /// This can be used to combine operations for several workspace-hack packages.
+    pub fn merge(&mut self, other: WorkspaceOps<'g, 'a>) -> &mut Self {
+        self.ops.extend(other.ops);
+        self
+    }
+
     /// Apply these workspace operations.
     ///
     /// Returns an error if any operations failed to complete.
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::explain::HakariExplain;
-use guppy::graph::{feature::StandardFeatures, DependencyDirection};
+use guppy::graph::{feature::StandardFeatures, DependencyDirection, PackageLink};
 use itertools::{Itertools, Position};
 use owo_colors::{OwoColorize, Style};
 use std::{collections::BTreeSet, fmt};
@@ -143,6 +143,17 @@ impl<'g, 'a, 'explain> fmt::Display for HakariExplainDisplay<'g, 'a, 'explain> {
                         .add_ansi_cell(platform_display);
                     table.add_row(row);
                 }
+
+                // Show how each workspace package reaches the dependency.
+                let mut paths = String::from("\n  via:\n");
+                for package_id in package_set.package_ids(DependencyDirection::Reverse) {
+                    let path = PathDisplay {
+                        links: &inner.workspace_packages[package_id].path,
+                        styles: &self.styles,
+                    };
+                    paths.push_str(&format!("    {}\n", path));
+                }
+                table.add_heading(paths);
             }
         }
 
@@ -150,6 +161,38 @@ impl<'g, 'a, 'explain> fmt::Display for HakariExplainDisplay<'g, 'a, 'explain> {
     }
 }
 
+struct PathDisplay<'g, 'a> {
+    links: &'a [PackageLink<'g>],
+    styles: &'a Styles,
+}
+
+impl<'g, 'a> fmt::Display for PathDisplay<'g, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(first) = self.links.first() else {
+            return write!(f, "(path not found)");
+        };
+        write!(
+            f,
+            "{}",
+            first.from().name().style(self.styles.package_name_style)
+        )?;
+        for link in self.links {
+            write!(f, " -> ")?;
+            if link.dev_only() {
+                write!(f, "{} ", "[dev]".style(self.styles.dep_kind_style))?;
+            } else if !link.normal().is_present() {
+                write!(f, "{} ", "[build]".style(self.styles.dep_kind_style))?;
+            }
+            write!(
+                f,
+                "{}",
+                link.to().name().style(self.styles.package_name_style)
+            )?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 struct Styles {
     build_platform_style: Style,
@@ -163,6 +206,7 @@ struct Styles {
     no_style: Style,
     standard_features_style: Style,
     platform_style: Style,
+    dep_kind_style: Style,
 }
 
 impl Styles {
@@ -178,6 +222,7 @@ impl Styles {
         self.no_style = Style::new().bright_red();
         self.standard_features_style = Style::new().bright_blue();
         self.platform_style = Style::new().yellow();
+        self.dep_kind_style = Style::new().dimmed();
     }
 }
 
@@ -3,23 +3,31 @@
 
 //! Information about why a dependency is in the workspace-hack.
 //!
-//! [`HakariExplain`] instances are produced by [`Hakari::explain`]. The current API is limited
-//! to displaying these instances if the `cli-support` feature is enabled.
+//! [`HakariExplain`] instances are produced by [`Hakari::explain`]. If the `cli-support` feature
+//! is enabled, they can be displayed or converted to a serializable `ExplainSummary`. The paths
+//! from workspace packages to the dependency can be accessed through [`HakariExplain::paths`].
 
 #[cfg(feature = "cli-support")]
 mod display;
 mod simplify;
+#[cfg(feature = "cli-support")]
+mod summary;
 
 #[cfg(feature = "cli-support")]
 pub use display::HakariExplainDisplay;
 
 use crate::{explain::simplify::*, Hakari};
 use guppy::{
-    graph::{cargo::BuildPlatform, feature::StandardFeatures, PackageGraph, PackageMetadata},
+    graph::{
+        cargo::{BuildPlatform, CargoOptions, CargoSet},
+        feature::StandardFeatures,
+        DependencyDirection, PackageGraph, PackageLink, PackageMetadata,
+    },
+    platform::PlatformSpec,
     PackageId,
 };
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     sync::Arc,
 };
 use target_spec::Platform;
@@ -53,6 +61,7 @@ struct ExplainInner<'g> {
 struct ExplainInnerValue<'g> {
     #[cfg_attr(not(feature = "cli-support"), allow(dead_code))]
     metadata: PackageMetadata<'g>,
+    path: Vec<PackageLink<'g>>,
     #[cfg_attr(not(feature = "cli-support"), allow(dead_code))]
     sets: Vec<(
         Simple<bool>,
@@ -67,8 +76,18 @@ impl<'g, 'a> HakariExplain<'g, 'a> {
         let metadata = hakari.builder.graph().metadata(dep_id)?;
         let intermediate = ExplainIntermediate::new(hakari, metadata.id())?;
 
-        let target_map = Self::simplify_map(hakari, intermediate.target_map);
-        let host_map = Self::simplify_map(hakari, intermediate.host_map);
+        let target_map = Self::simplify_map(
+            hakari,
+            BuildPlatform::Target,
+            metadata.id(),
+            intermediate.target_map,
+        );
+        let host_map = Self::simplify_map(
+            hakari,
+            BuildPlatform::Host,
+            metadata.id(),
+            intermediate.host_map,
+        );
 
         Ok(Self {
             graph,
@@ -79,7 +98,12 @@ impl<'g, 'a> HakariExplain<'g, 'a> {
         })
     }
 
-    fn simplify_map(hakari: &'a Hakari<'g>, map: IntermediateMap<'g, 'a>) -> ExplainMap<'g, 'a> {
+    fn simplify_map(
+        hakari: &'a Hakari<'g>,
+        build_platform: BuildPlatform,
+        dep_id: &'g PackageId,
+        map: IntermediateMap<'g, 'a>,
+    ) -> ExplainMap<'g, 'a> {
         const STANDARD_FEATURES_COUNT: usize = 3;
         const INCLUDE_DEV_COUNT: usize = 2;
         // +1 for the None case
@@ -91,11 +115,22 @@ impl<'g, 'a> HakariExplain<'g, 'a> {
                     .workspace_packages
                     .into_iter()
                     .map(|(package_id, IntermediateInnerValue { metadata, sets })| {
+                        // Any of the builds that produced this feature set will do: use the
+                        // first one.
+                        let first = sets.first().expect("at least one build is recorded");
+                        let path = find_path(hakari, metadata, *first, build_platform, dep_id);
                         let sets = simplify3(
                             &sets,
                             (INCLUDE_DEV_COUNT, STANDARD_FEATURES_COUNT, platform_count),
                         );
-                        (package_id, ExplainInnerValue { metadata, sets })
+                        (
+                            package_id,
+                            ExplainInnerValue {
+                                metadata,
+                                path,
+                                sets,
+                            },
+                        )
                     })
                     .collect();
                 let fixup_platforms = simplify1(&inner.fixup_platforms, platform_count);
@@ -116,14 +151,37 @@ impl<'g, 'a> HakariExplain<'g, 'a> {
         self.metadata
     }
 
+    /// Returns a path from each workspace package to the dependency, for every feature set the
+    /// dependency is built with.
+    ///
+    /// For more about what each path represents, see [`ExplainPath`].
+    ///
+    /// Paths are returned for the target platform first, then for the host platform. Within each
+    /// build platform, they're grouped by feature set.
+    pub fn paths<'explain>(&'explain self) -> impl Iterator<Item = ExplainPath<'g, 'explain>> {
+        self.explain_maps()
+            .into_iter()
+            .flat_map(|(build_platform, explain_map)| {
+                explain_map.iter().flat_map(move |(&features, inner)| {
+                    inner
+                        .workspace_packages
+                        .values()
+                        .map(move |value| ExplainPath {
+                            build_platform,
+                            features,
+                            workspace_package: value.metadata,
+                            links: &value.path,
+                        })
+                })
+            })
+    }
+
     /// Returns a displayer for the output.
     #[cfg(feature = "cli-support")]
     pub fn display<'explain>(&'explain self) -> HakariExplainDisplay<'g, 'a, 'explain> {
         HakariExplainDisplay::new(self)
     }
 
-    // Used by the display module.
-    #[allow(dead_code)]
     fn explain_maps(&self) -> [(BuildPlatform, &ExplainMap<'g, 'a>); 2] {
         [
             (BuildPlatform::Target, &self.target_map),
@@ -132,6 +190,111 @@ impl<'g, 'a> HakariExplain<'g, 'a> {
     }
 }
 
+/// A path from a workspace package to the dependency being explained, produced by
+/// [`HakariExplain::paths`].
+///
+/// This is a shortest chain of package dependencies from the workspace package to the dependency,
+/// in a simulated build of the workspace package. If there are several such chains, an arbitrary
+/// one is picked, so it isn't necessarily the chain that caused the dependency to be built with
+/// this feature set. Feature edges aren't recorded.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExplainPath<'g, 'explain> {
+    /// The platform the dependency is built on.
+    pub build_platform: BuildPlatform,
+
+    /// The features the dependency is built with.
+    pub features: &'explain BTreeSet<&'g str>,
+
+    /// The workspace package at the start of the path.
+    pub workspace_package: PackageMetadata<'g>,
+
+    /// The links from the workspace package to the dependency, in order.
+    ///
+    /// The first link is from the workspace package, and the last link is to the dependency. This
+    /// is empty in the unlikely case that a path couldn't be found.
+    pub links: &'explain [PackageLink<'g>],
+}
+
+/// Finds a shortest path from `workspace_package` to `dep_id`, by simulating a build with the given
+/// options.
+///
+/// Paths start on the target platform, and cross over to the host platform through build
+/// dependencies and proc macros.
+fn find_path<'g>(
+    hakari: &Hakari<'g>,
+    workspace_package: PackageMetadata<'g>,
+    (include_dev, standard_features, platform_idx): (bool, StandardFeatures, Option<usize>),
+    build_platform: BuildPlatform,
+    dep_id: &'g PackageId,
+) -> Vec<PackageLink<'g>> {
+    let builder = &hakari.builder;
+    let platform_spec = match platform_idx {
+        Some(idx) => PlatformSpec::Platform(builder.platforms[idx].clone()),
+        None => PlatformSpec::Always,
+    };
+    let mut cargo_opts = CargoOptions::new();
+    cargo_opts
+        .set_include_dev(include_dev)
+        .set_resolver(builder.resolver())
+        .set_platform(platform_spec)
+        .add_omitted_packages(builder.traversal_excludes());
+    let initials = workspace_package
+        .to_package_set()
+        .to_feature_set(standard_features);
+    let cargo_set = CargoSet::new(initials, builder.make_features_only(), &cargo_opts)
+        .expect("cargo resolution should succeed");
+
+    // Build an adjacency list of (package, build platform) nodes.
+    let mut edges: BTreeMap<(&'g PackageId, BuildPlatform), Vec<(PackageLink<'g>, BuildPlatform)>> =
+        BTreeMap::new();
+    for (platform, feature_set) in cargo_set.all_features() {
+        for link in feature_set.conditional_links(DependencyDirection::Forward) {
+            let link = link.package_link();
+            edges
+                .entry((link.from().id(), platform))
+                .or_default()
+                .push((link, platform));
+        }
+    }
+    for link in cargo_set
+        .build_dep_links()
+        .chain(cargo_set.proc_macro_links())
+    {
+        edges
+            .entry((link.from().id(), BuildPlatform::Target))
+            .or_default()
+            .push((link, BuildPlatform::Host));
+    }
+
+    // Breadth-first search, tracking the link and node used to reach each node.
+    let start = (workspace_package.id(), BuildPlatform::Target);
+    let end = (dep_id, build_platform);
+    let mut parents = BTreeMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        if node == end {
+            break;
+        }
+        for &(link, platform) in edges.get(&node).into_iter().flatten() {
+            let next = (link.to().id(), platform);
+            if next != start && !parents.contains_key(&next) {
+                parents.insert(next, (link, node));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut node = end;
+    while let Some(&(link, parent)) = parents.get(&node) {
+        path.push(link);
+        node = parent;
+    }
+    path.reverse();
+    path
+}
+
 /// Pre-simplification map.
 #[derive(Debug)]
 struct ExplainIntermediate<'g, 'a> {
@@ -211,3 +374,54 @@ impl<'g, 'a> ExplainIntermediate<'g, 'a> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::HakariBuilder;
+    use fixtures::json::JsonFixture;
+    use guppy::graph::cargo::BuildPlatform;
+
+    #[test]
+    fn explain_paths() {
+        let graph = JsonFixture::metadata_guppy_78cb7e8().graph();
+        let hakari = HakariBuilder::new(graph, None)
+            .expect("builder created")
+            .compute();
+        let deps: Vec<_> = hakari
+            .output_map
+            .values()
+            .flat_map(|deps| deps.keys().copied())
+            .collect();
+        assert!(!deps.is_empty(), "some dependencies were unified");
+
+        let mut any_multi_link = false;
+        for dep_id in deps {
+            let explain = hakari.explain(dep_id).expect("dependency is in output");
+            for path in explain.paths() {
+                let links = path.links;
+                assert!(
+                    !links.is_empty(),
+                    "path found from {} to {dep_id}",
+                    path.workspace_package.name()
+                );
+                assert_eq!(links[0].from().id(), path.workspace_package.id());
+                assert_eq!(links[links.len() - 1].to().id(), dep_id);
+                for pair in links.windows(2) {
+                    assert_eq!(pair[0].to().id(), pair[1].from().id(), "links are chained");
+                }
+                // Build dependencies cross over to the host platform, so a path to the dependency
+                // on the target platform only goes through normal and dev-only dependencies.
+                if path.build_platform == BuildPlatform::Target {
+                    assert!(links
+                        .iter()
+                        .all(|link| link.normal().is_present() || link.dev_only()));
+                }
+                any_multi_link |= links.len() > 1;
+            }
+        }
+        assert!(
+            any_multi_link,
+            "at least one path goes through intermediate packages"
+        );
+    }
+}
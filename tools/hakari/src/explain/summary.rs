@@ -0,0 +1,109 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    explain::{simplify::Simple, HakariExplain},
+    summaries::{
+        DependencyKindSummary, ExplainBuildSummary, ExplainFeatureSetSummary, ExplainLinkSummary,
+        ExplainPackageSummary, ExplainPlatformSummary, ExplainSummary,
+    },
+};
+use guppy::graph::DependencyDirection;
+
+impl<'g, 'a> HakariExplain<'g, 'a> {
+    /// Converts this `HakariExplain` to a serializable summary.
+    ///
+    /// Requires the `cli-support` feature to be enabled.
+    pub fn to_summary(&self) -> ExplainSummary {
+        let mut feature_sets = Vec::new();
+        for (build_platform, explain_map) in self.explain_maps() {
+            for (&features, inner) in explain_map {
+                let package_set = self
+                    .graph
+                    .resolve_ids(inner.workspace_packages.keys().copied())
+                    .expect("keys derived from package graph");
+
+                // Use the same order as the display output: reverse dependency order within the
+                // workspace.
+                let workspace_packages = package_set
+                    .package_ids(DependencyDirection::Reverse)
+                    .map(|package_id| {
+                        let inner_value = &inner.workspace_packages[package_id];
+                        let builds = inner_value
+                            .sets
+                            .iter()
+                            .map(|(include_dev, standard_features, platform_idx)| {
+                                ExplainBuildSummary {
+                                    include_dev: simple_to_option(include_dev, |&v| v),
+                                    standard_features: simple_to_option(
+                                        standard_features,
+                                        |&features| features.into(),
+                                    ),
+                                    platform: self.platform_summary(platform_idx),
+                                }
+                            })
+                            .collect();
+                        let path = inner_value
+                            .path
+                            .iter()
+                            .map(|link| {
+                                let kind = if link.dev_only() {
+                                    DependencyKindSummary::Dev
+                                } else if !link.normal().is_present() {
+                                    DependencyKindSummary::Build
+                                } else {
+                                    DependencyKindSummary::Normal
+                                };
+                                ExplainLinkSummary {
+                                    from: link.from().to_summary_id(),
+                                    to: link.to().to_summary_id(),
+                                    kind,
+                                }
+                            })
+                            .collect();
+                        ExplainPackageSummary {
+                            package: inner_value.metadata.to_summary_id(),
+                            builds,
+                            path,
+                        }
+                    })
+                    .collect();
+
+                let fixup_platforms = inner
+                    .fixup_platforms
+                    .iter()
+                    .map(|platform_idx| self.platform_summary(platform_idx))
+                    .collect();
+
+                feature_sets.push(ExplainFeatureSetSummary {
+                    build_platform: build_platform.into(),
+                    features: features.iter().map(|&feature| feature.to_owned()).collect(),
+                    workspace_packages,
+                    fixup_platforms,
+                });
+            }
+        }
+
+        ExplainSummary {
+            dependency: self.metadata.to_summary_id(),
+            feature_sets,
+        }
+    }
+
+    fn platform_summary(&self, platform_idx: &Simple<Option<usize>>) -> ExplainPlatformSummary {
+        match platform_idx {
+            Simple::Any => ExplainPlatformSummary::Any,
+            Simple::Some(None) => ExplainPlatformSummary::All,
+            Simple::Some(Some(idx)) => {
+                ExplainPlatformSummary::Platform(self.platforms[*idx].triple_str().to_owned())
+            }
+        }
+    }
+}
+
+fn simple_to_option<T, U>(simple: &Simple<T>, f: impl FnOnce(&T) -> U) -> Option<U> {
+    match simple {
+        Simple::Any => None,
+        Simple::Some(val) => Some(f(val)),
+    }
+}
@@ -38,6 +38,7 @@ pub struct HakariBuilder<'g> {
     pub(crate) platforms: Vec<Arc<Platform>>,
     resolver: CargoResolverVersion,
     pub(crate) verify_mode: bool,
+    members: Option<HashSet<&'g PackageId>>,
     pub(crate) traversal_excludes: HashSet<&'g PackageId>,
     final_excludes: HashSet<&'g PackageId>,
     pub(crate) registries: BiHashMap<String, String, ahash::RandomState, ahash::RandomState>,
@@ -77,6 +78,7 @@ impl<'g> HakariBuilder<'g> {
             platforms: vec![],
             resolver: CargoResolverVersion::V2,
             verify_mode: false,
+            members: None,
             traversal_excludes: HashSet::new(),
             final_excludes: HashSet::new(),
             registries: BiHashMap::with_hashers(Default::default(), Default::default()),
@@ -212,6 +214,60 @@ impl<'g> HakariBuilder<'g> {
         Ok(excludes.is_excluded(package_id))
     }
 
+    /// Restricts the workspace packages considered by `hakari` to the given set of members.
+    ///
+    /// By default, Cargo build simulations are run for every workspace package. Setting this
+    /// option means that simulations are only run for these packages. This can be used to split a
+    /// workspace across several workspace-hack packages, each unifying the features used by its own
+    /// members.
+    ///
+    /// Unlike [traversal excludes](Self::add_traversal_excludes), workspace packages outside the
+    /// set of members are still traversed if a member depends on them. Workspace packages outside
+    /// the set of members are treated as [excluded](Self::is_excluded).
+    ///
+    /// Returns an error if any package IDs specified aren't known to the graph, or aren't in the
+    /// workspace.
+    pub fn set_members<'b>(
+        &mut self,
+        members: impl IntoIterator<Item = &'b PackageId>,
+    ) -> Result<&mut Self, guppy::Error> {
+        let members: HashSet<&'g PackageId> = members
+            .into_iter()
+            .map(|package_id| {
+                let package = self.graph.metadata(package_id)?;
+                if !package.in_workspace() {
+                    return Err(guppy::Error::NotInWorkspace(package.id().clone()));
+                }
+                Ok(package.id())
+            })
+            .collect::<Result<_, _>>()?;
+        self.members = Some(members);
+        Ok(self)
+    }
+
+    /// Returns the workspace packages `hakari` is restricted to, or `None` if all workspace
+    /// packages are considered.
+    ///
+    /// For more, see [`set_members`](Self::set_members).
+    pub fn members<'b>(&'b self) -> Option<impl Iterator<Item = &'g PackageId> + 'b> {
+        self.members.as_ref().map(|members| members.iter().copied())
+    }
+
+    /// Returns true if a package ID is a workspace package considered by `hakari`.
+    ///
+    /// This is true for all workspace packages if [`set_members`](Self::set_members) wasn't
+    /// called, and false for packages outside the workspace.
+    ///
+    /// Returns an error if this package ID isn't known to the underlying graph.
+    pub fn is_member(&self, package_id: &PackageId) -> Result<bool, guppy::Error> {
+        let package = self.graph.metadata(package_id)?;
+        Ok(package.in_workspace()
+            && self
+                .members
+                .as_ref()
+                .map_or(true, |members| members.contains(package_id)))
+    }
+
     /// Adds packages to be removed from the final output.
     ///
     /// Unlike [`traversal_excludes`](Self::traversal_excludes), these packages are considered
@@ -246,12 +302,15 @@ impl<'g> HakariBuilder<'g> {
     /// Returns true if a package ID is excluded from either the traversal or the final output.
     ///
     /// Also returns true for the Hakari package if specified. This is because the Hakari package is
-    /// treated as excluded by the algorithm.
+    /// treated as excluded by the algorithm. If [`set_members`](Self::set_members) was called,
+    /// workspace packages that aren't members are excluded as well.
     ///
     /// Returns an error if this package ID isn't known to the underlying graph.
     #[inline]
     pub fn is_excluded(&self, package_id: &PackageId) -> Result<bool, guppy::Error> {
-        Ok(self.is_traversal_excluded(package_id)? || self.is_final_excluded(package_id)?)
+        Ok(self.is_traversal_excluded(package_id)?
+            || self.is_final_excluded(package_id)?
+            || self.is_non_member(package_id))
     }
 
     /// Add alternate registries by (name, URL) pairs.
@@ -359,7 +418,20 @@ impl<'g> HakariBuilder<'g> {
         }
     }
 
-    fn make_features_only<'b>(&'b self) -> FeatureSet<'g> {
+    fn is_non_member(&self, package_id: &PackageId) -> bool {
+        match &self.members {
+            Some(members) => {
+                let in_workspace = self
+                    .graph
+                    .metadata(package_id)
+                    .is_ok_and(|package| package.in_workspace());
+                in_workspace && !members.contains(package_id)
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn make_features_only<'b>(&'b self) -> FeatureSet<'g> {
         if self.verify_mode {
             match &self.hakari_package {
                 Some(package) => package.to_package_set(),
@@ -381,6 +453,12 @@ mod summaries {
     impl<'g> HakariBuilder<'g> {
         /// Constructs a `HakariBuilder` from a `PackageGraph` and a serialized summary.
         ///
+        /// If the summary lists additional
+        /// [`workspace_hacks`](HakariBuilderSummary::workspace_hacks), the returned builder is for
+        /// the main `hakari_package`, and only considers workspace members not assigned to any of
+        /// them. To get builders for all workspace-hack packages, use
+        /// [`HakariBuilderSummary::to_hakari_builders`].
+        ///
         /// Requires the `cli-support` feature to be enabled.
         ///
         /// Returns an error if the summary references a package that's not present, or if there was
@@ -389,7 +467,17 @@ mod summaries {
             graph: &'g PackageGraph,
             summary: &HakariBuilderSummary,
         ) -> Result<Self, guppy::Error> {
-            let hakari_package = summary
+            Self::from_summary_impl(graph, summary, None)
+        }
+
+        /// Constructs a `HakariBuilder` from a summary, for either the main `hakari_package` (if
+        /// `hack_idx` is `None`) or the workspace-hack at `hack_idx`.
+        pub(crate) fn from_summary_impl(
+            graph: &'g PackageGraph,
+            summary: &HakariBuilderSummary,
+            hack_idx: Option<usize>,
+        ) -> Result<Self, guppy::Error> {
+            let main_package = summary
                 .hakari_package
                 .as_ref()
                 .map(|name| graph.workspace().member_by_name(name))
@@ -415,7 +503,7 @@ mod summaries {
                 .map(|(name, url)| (name.clone(), url.clone()))
                 .collect();
 
-            let traversal_excludes = summary
+            let mut traversal_excludes: HashSet<_> = summary
                 .traversal_excludes
                 .to_package_set_registry(
                     graph,
@@ -434,11 +522,73 @@ mod summaries {
                 .package_ids(DependencyDirection::Forward)
                 .collect();
 
+            let mut hakari_package = main_package;
+            let mut members = None;
+            if !summary.workspace_hacks.is_empty() {
+                let hacks = resolve_workspace_hacks(graph, summary, &registries)?;
+                let hack_ids: HashSet<&'g PackageId> = main_package
+                    .iter()
+                    .chain(hacks.iter().map(|(package, _)| package))
+                    .map(|package| package.id())
+                    .collect();
+
+                let this_members = match hack_idx {
+                    Some(idx) => {
+                        let (package, members) = &hacks[idx];
+                        hakari_package = Some(*package);
+                        members.clone()
+                    }
+                    None => {
+                        // The main workspace-hack gets all the members not assigned elsewhere.
+                        let assigned: HashSet<&'g PackageId> = hacks
+                            .iter()
+                            .flat_map(|(_, members)| members.iter().copied())
+                            .collect();
+                        graph
+                            .workspace()
+                            .iter()
+                            .map(|package| package.id())
+                            .filter(|id| !assigned.contains(id) && !hack_ids.contains(id))
+                            .collect()
+                    }
+                };
+                members = Some(this_members);
+
+                // Each workspace-hack is computed independently, so the other workspace-hack
+                // packages must not be traversed.
+                let own_id = hakari_package.map(|package| package.id());
+                traversal_excludes.extend(hack_ids.into_iter().filter(|id| Some(*id) != own_id));
+            }
+
+            if let (None, Some(summary_members)) = (hack_idx, &summary.members) {
+                let restricted = summary_members
+                    .to_package_set_registry(
+                        graph,
+                        |name| registries.get_by_left(name).map(|s| s.as_str()),
+                        "resolving hakari members",
+                    )?
+                    .packages(DependencyDirection::Forward)
+                    .map(|package| {
+                        if package.in_workspace() {
+                            Ok(package.id())
+                        } else {
+                            Err(guppy::Error::NotInWorkspace(package.id().clone()))
+                        }
+                    })
+                    .collect::<Result<HashSet<_>, _>>()?;
+                // Members left over from additional workspace-hacks are further restricted.
+                members = Some(match members {
+                    Some(members) => members.intersection(&restricted).copied().collect(),
+                    None => restricted,
+                });
+            }
+
             Ok(Self {
                 graph: DebugIgnore(graph),
                 hakari_package,
                 resolver: summary.resolver,
                 verify_mode: false,
+                members,
                 unify_target_host: summary.unify_target_host,
                 output_single_feature: summary.output_single_feature,
                 dep_format_version: summary.dep_format_version,
@@ -450,6 +600,50 @@ mod summaries {
             })
         }
     }
+
+    /// Resolves the additional workspace-hacks in a summary, returning each workspace-hack package
+    /// along with its members.
+    ///
+    /// A workspace member matched by more than one workspace-hack is assigned to the first one.
+    /// Workspace-hack packages are never members.
+    fn resolve_workspace_hacks<'g>(
+        graph: &'g PackageGraph,
+        summary: &HakariBuilderSummary,
+        registries: &BiHashMap<String, String, ahash::RandomState, ahash::RandomState>,
+    ) -> Result<Vec<(PackageMetadata<'g>, HashSet<&'g PackageId>)>, guppy::Error> {
+        let hack_packages = summary
+            .workspace_hacks
+            .iter()
+            .map(|hack| graph.workspace().member_by_name(&hack.package))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut hack_ids: HashSet<&'g PackageId> =
+            hack_packages.iter().map(|package| package.id()).collect();
+        if let Some(name) = &summary.hakari_package {
+            hack_ids.insert(graph.workspace().member_by_name(name)?.id());
+        }
+
+        let mut assigned = hack_ids;
+        summary
+            .workspace_hacks
+            .iter()
+            .zip(hack_packages)
+            .map(|(hack, package)| {
+                let members: HashSet<&'g PackageId> = hack
+                    .members
+                    .to_package_set_registry(
+                        graph,
+                        |name| registries.get_by_left(name).map(|s| s.as_str()),
+                        "resolving hakari workspace-hack members",
+                    )?
+                    .packages(DependencyDirection::Forward)
+                    .filter(|member| member.in_workspace())
+                    .map(|member| member.id())
+                    .filter(|id| assigned.insert(id))
+                    .collect();
+                Ok((package, members))
+            })
+            .collect()
+    }
 }
 
 /// Whether to unify feature sets for a given dependency across target and host platforms.
@@ -981,6 +1175,34 @@ impl<'g, 'b> ComputedMapBuild<'g, 'b> {
         let excludes_ref = &excludes;
         let features_only_ref = &features_only;
 
+        // Workspace packages that don't depend on any third-party packages, even through dev-only
+        // or platform-specific dependencies, can't contribute anything to the computed map. Use
+        // the reachability index to skip simulating builds for them.
+        //
+        // This doesn't apply in verify mode, where every build also includes the workspace-hack
+        // package and its third-party dependencies.
+        let reachability = builder.graph.reachability_index();
+        let has_third_party_deps: HashSet<&'g PackageId> = workspace
+            .iter()
+            .filter(|workspace_package| {
+                reachability
+                    .dependencies(workspace_package.id())
+                    .expect("workspace package IDs are valid")
+                    .packages(DependencyDirection::Forward)
+                    .any(|dep| !dep.in_workspace())
+            })
+            .map(|workspace_package| workspace_package.id())
+            .collect();
+        let has_third_party_deps_ref = &has_third_party_deps;
+        // In verify mode, the workspace-hack package is built on its own to compare against its
+        // members, even if it isn't one of them.
+        let is_verified_hakari_package = |package_id: &PackageId| {
+            builder.verify_mode
+                && builder
+                    .hakari_package
+                    .is_some_and(|package| package.id() == package_id)
+        };
+
         let computed_map: ComputedMap<'g> = platforms_features
             .into_par_iter()
             // The cargo_set computation in the inner iterator is the most expensive part of the
@@ -998,6 +1220,18 @@ impl<'g, 'b> ComputedMapBuild<'g, 'b> {
                         // Skip this package since it was excluded during traversal.
                         return BTreeMap::new();
                     }
+                    if builder.is_non_member(workspace_package.id())
+                        && !is_verified_hakari_package(workspace_package.id())
+                    {
+                        // Skip this package since it's handled by a different workspace-hack.
+                        return BTreeMap::new();
+                    }
+                    if !builder.verify_mode
+                        && !has_third_party_deps_ref.contains(workspace_package.id())
+                    {
+                        // No third-party packages can be built for this package.
+                        return BTreeMap::new();
+                    }
 
                     let initials = workspace_package
                         .to_package_set()
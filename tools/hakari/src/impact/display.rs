@@ -0,0 +1,112 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::impact::HakariImpact;
+use owo_colors::{OwoColorize, Style};
+use std::fmt;
+use tabular::{Row, Table};
+
+/// A display formatter for [`HakariImpact`].
+///
+/// Requires the `cli-support` feature.
+#[derive(Clone, Debug)]
+pub struct HakariImpactDisplay<'g, 'impact> {
+    impact: &'impact HakariImpact<'g>,
+    styles: Box<Styles>,
+}
+
+impl<'g, 'impact> HakariImpactDisplay<'g, 'impact> {
+    pub(super) fn new(impact: &'impact HakariImpact<'g>) -> Self {
+        Self {
+            impact,
+            styles: Box::default(),
+        }
+    }
+
+    /// Adds ANSI color codes to the output.
+    pub fn colorize(&mut self) -> &mut Self {
+        self.styles.colorize();
+        self
+    }
+}
+
+impl<'g, 'impact> fmt::Display for HakariImpactDisplay<'g, 'impact> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let platforms: Vec<_> = self.impact.platforms().collect();
+        for member in self.impact.members() {
+            write!(
+                f,
+                "{}",
+                member.package.name().style(self.styles.package_name_style),
+            )?;
+            if let Some(idx) = member.platform_idx {
+                write!(
+                    f,
+                    " on {}",
+                    platforms[idx].style(self.styles.build_platform_style)
+                )?;
+            }
+            write!(
+                f,
+                ": {} -> {} third-party packages",
+                member.packages_without, member.packages_with,
+            )?;
+            if member.is_empty() {
+                writeln!(f, ", no extra features")?;
+                continue;
+            }
+            writeln!(f)?;
+
+            let mut table = Table::new("    {:<}  {:<}  {:<}  {:<}");
+            for added in &member.added {
+                let status = if added.newly_built {
+                    "added".style(self.styles.added_style)
+                } else {
+                    "features".style(self.styles.features_style)
+                };
+                let features = if added.features.is_empty() {
+                    "(no features)".to_owned()
+                } else {
+                    added
+                        .features
+                        .iter()
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                let row = Row::new()
+                    .with_ansi_cell(status)
+                    .with_ansi_cell(added.build_platform.style(self.styles.build_platform_style))
+                    .with_ansi_cell(format!(
+                        "{} v{}",
+                        added.package.name().style(self.styles.package_name_style),
+                        added.package.version(),
+                    ))
+                    .with_ansi_cell(features.style(self.styles.feature_style));
+                table.add_row(row);
+            }
+            write!(f, "{}", table)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Styles {
+    package_name_style: Style,
+    build_platform_style: Style,
+    added_style: Style,
+    features_style: Style,
+    feature_style: Style,
+}
+
+impl Styles {
+    fn colorize(&mut self) {
+        self.package_name_style = Style::new().bold();
+        self.build_platform_style = Style::new().blue().bold();
+        self.added_style = Style::new().bright_green();
+        self.features_style = Style::new().bright_blue();
+        self.feature_style = Style::new().purple();
+    }
+}
@@ -0,0 +1,438 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Analysis of how the workspace-hack affects builds of individual workspace packages.
+//!
+//! The workspace-hack package trades some extra build work for individual workspace packages for
+//! better reuse across the whole workspace: building a single package alone may build more crates
+//! and features than it needs. [`HakariBuilder::impact`] measures this trade-off by simulating a
+//! build of each workspace package alone, once with the workspace-hack package and once without
+//! it, and reporting the differences. This is done separately for each configured platform.
+//!
+//! The workspace-hack package as currently present in the package graph is used, so this analysis
+//! is most useful after the workspace-hack's `Cargo.toml` has been generated.
+//!
+//! The results can be used to decide which dependencies to add to the final excludes. If the
+//! `cli-support` feature is enabled, they can be displayed or converted to a serializable
+//! `ImpactSummary`.
+
+#[cfg(feature = "cli-support")]
+mod display;
+
+#[cfg(feature = "cli-support")]
+pub use display::HakariImpactDisplay;
+
+use crate::HakariBuilder;
+use guppy::{
+    graph::{
+        cargo::{BuildPlatform, CargoOptions, CargoSet},
+        feature::{FeatureSet, StandardFeatures},
+        DependencyDirection, PackageMetadata,
+    },
+    platform::{Platform, PlatformSpec},
+    PackageId,
+};
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+impl<'g> HakariBuilder<'g> {
+    /// Simulates building each workspace package alone, with and without the workspace-hack
+    /// package, and reports the third-party packages and features that the workspace-hack adds.
+    ///
+    /// Builds are simulated with default features and without dev-dependencies. Each workspace
+    /// package is analyzed once per [platform](Self::set_platforms) configured for this builder,
+    /// or once platform-independently if none are configured. Excluded workspace packages are
+    /// skipped.
+    ///
+    /// Returns `None` if the [`hakari_package`](Self::hakari_package) is `None`.
+    ///
+    /// For more, see the documentation for the [`impact`](crate::impact) module.
+    pub fn impact(&self) -> Option<HakariImpact<'g>> {
+        let hakari_package = *self.hakari_package()?;
+
+        // Analyze each platform separately: a union of platforms would mix up dependencies that
+        // are only built on some of them.
+        let platform_idxs: Vec<_> = if self.platforms.is_empty() {
+            vec![None]
+        } else {
+            (0..self.platforms.len()).map(Some).collect()
+        };
+        let opts: Vec<_> = platform_idxs
+            .iter()
+            .map(|&platform_idx| {
+                let platform_spec = match platform_idx {
+                    Some(idx) => PlatformSpec::Platform(self.platforms[idx].clone()),
+                    None => PlatformSpec::Always,
+                };
+                let mut with_opts = CargoOptions::new();
+                with_opts
+                    .set_resolver(self.resolver())
+                    .set_platform(platform_spec)
+                    .add_omitted_packages(self.traversal_excludes.iter().copied());
+                let mut without_opts = with_opts.clone();
+                without_opts.add_omitted_packages([hakari_package.id()]);
+                (platform_idx, with_opts, without_opts)
+            })
+            .collect();
+
+        let hakari_set = hakari_package.to_package_set();
+        let features_only = self.graph().feature_graph().resolve_none();
+
+        let builds: Vec<_> = self
+            .graph()
+            .workspace()
+            .iter()
+            .filter(|package| {
+                !self
+                    .is_excluded(package.id())
+                    .expect("workspace package IDs are valid")
+            })
+            .flat_map(|package| opts.iter().map(move |opts| (package, opts)))
+            .collect();
+
+        let members = builds
+            .into_par_iter()
+            .map(|(package, (platform_idx, with_opts, without_opts))| {
+                // Building the workspace-hack package alongside this package is equivalent to this
+                // package depending on it.
+                let package_set = package.to_package_set();
+                let with = package_set
+                    .union(&hakari_set)
+                    .to_feature_set(StandardFeatures::Default);
+                let without = package_set.to_feature_set(StandardFeatures::Default);
+                let with = build_map(with, features_only.clone(), with_opts);
+                let without = build_map(without, features_only.clone(), without_opts);
+                MemberImpact::new(package, *platform_idx, &with, &without)
+            })
+            .collect();
+
+        Some(HakariImpact {
+            hakari_package,
+            platforms: self.platforms.clone(),
+            members,
+        })
+    }
+}
+
+/// The result of a Hakari impact analysis.
+///
+/// Generated by [`HakariBuilder::impact`].
+#[derive(Clone, Debug)]
+pub struct HakariImpact<'g> {
+    hakari_package: PackageMetadata<'g>,
+    platforms: Vec<Arc<Platform>>,
+    members: Vec<MemberImpact<'g>>,
+}
+
+impl<'g> HakariImpact<'g> {
+    /// Returns the workspace-hack package used for this analysis.
+    pub fn hakari_package(&self) -> PackageMetadata<'g> {
+        self.hakari_package
+    }
+
+    /// Returns the platforms the analysis was performed on, or an empty list if it was performed
+    /// platform-independently.
+    ///
+    /// [`MemberImpact::platform_idx`] is an index into this list.
+    pub fn platforms(&self) -> impl ExactSizeIterator<Item = &str> + '_ {
+        self.platforms.iter().map(|platform| platform.triple_str())
+    }
+
+    /// Returns the impact of the workspace-hack on each workspace package, in workspace order.
+    ///
+    /// Each workspace package is listed once per platform, in the order of
+    /// [`platforms`](Self::platforms).
+    pub fn members<'a>(&'a self) -> impl ExactSizeIterator<Item = &'a MemberImpact<'g>> + 'a {
+        self.members.iter()
+    }
+
+    /// Returns a displayer for the output.
+    #[cfg(feature = "cli-support")]
+    pub fn display<'impact>(&'impact self) -> HakariImpactDisplay<'g, 'impact> {
+        HakariImpactDisplay::new(self)
+    }
+
+    /// Converts this instance to a serializable summary.
+    ///
+    /// Requires the `cli-support` feature to be enabled.
+    #[cfg(feature = "cli-support")]
+    pub fn to_summary(&self) -> crate::summaries::ImpactSummary {
+        use crate::summaries::{AddedDependencySummary, ImpactSummary, MemberImpactSummary};
+
+        let members = self
+            .members
+            .iter()
+            .map(|member| MemberImpactSummary {
+                package: member.package.to_summary_id(),
+                platform: member
+                    .platform_idx
+                    .map(|idx| self.platforms[idx].triple_str().to_owned()),
+                packages_without: member.packages_without,
+                packages_with: member.packages_with,
+                added: member
+                    .added
+                    .iter()
+                    .map(|added| AddedDependencySummary {
+                        build_platform: added.build_platform.into(),
+                        package: added.package.to_summary_id(),
+                        newly_built: added.newly_built,
+                        features: added
+                            .features
+                            .iter()
+                            .map(|&feature| feature.to_owned())
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        ImpactSummary {
+            hakari_package: self.hakari_package.to_summary_id(),
+            members,
+        }
+    }
+}
+
+/// The impact of the workspace-hack on building a single workspace package.
+///
+/// Part of [`HakariImpact`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct MemberImpact<'g> {
+    /// The workspace package.
+    pub package: PackageMetadata<'g>,
+
+    /// The index of the platform this workspace package was analyzed on, or `None` if the
+    /// analysis was performed platform-independently.
+    ///
+    /// This is an index into [`HakariImpact::platforms`].
+    pub platform_idx: Option<usize>,
+
+    /// The number of third-party packages built without the workspace-hack, on the target and
+    /// host platforms combined.
+    pub packages_without: usize,
+
+    /// The number of third-party packages built with the workspace-hack, on the target and host
+    /// platforms combined.
+    pub packages_with: usize,
+
+    /// Third-party packages that are either only built with the workspace-hack, or built with
+    /// additional features.
+    ///
+    /// Sorted by build platform, then by package ID.
+    pub added: Vec<AddedDependency<'g>>,
+}
+
+impl<'g> MemberImpact<'g> {
+    fn new(
+        package: PackageMetadata<'g>,
+        platform_idx: Option<usize>,
+        with: &BuildMap<'g>,
+        without: &BuildMap<'g>,
+    ) -> Self {
+        let added = with
+            .iter()
+            .filter_map(|(key, (dep, features))| match without.get(key) {
+                Some((_, without_features)) => {
+                    let added_features: BTreeSet<_> =
+                        features.difference(without_features).copied().collect();
+                    (!added_features.is_empty()).then_some(AddedDependency {
+                        build_platform: key.0,
+                        package: *dep,
+                        newly_built: false,
+                        features: added_features,
+                    })
+                }
+                None => Some(AddedDependency {
+                    build_platform: key.0,
+                    package: *dep,
+                    newly_built: true,
+                    features: features.clone(),
+                }),
+            })
+            .collect();
+
+        Self {
+            package,
+            platform_idx,
+            packages_without: without.len(),
+            packages_with: with.len(),
+            added,
+        }
+    }
+
+    /// Returns true if the workspace-hack doesn't cause any extra packages or features to be built
+    /// for this workspace package.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+    }
+}
+
+/// A third-party package that the workspace-hack adds to, or builds with more features for, a
+/// workspace package.
+///
+/// Part of [`MemberImpact`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct AddedDependency<'g> {
+    /// The platform the package is built on.
+    pub build_platform: BuildPlatform,
+
+    /// The package.
+    pub package: PackageMetadata<'g>,
+
+    /// True if this package isn't built at all without the workspace-hack.
+    pub newly_built: bool,
+
+    /// The features added by the workspace-hack.
+    ///
+    /// If [`newly_built`](Self::newly_built) is true, these are all the features the package is
+    /// built with.
+    pub features: BTreeSet<&'g str>,
+}
+
+type BuildMap<'g> =
+    BTreeMap<(BuildPlatform, &'g PackageId), (PackageMetadata<'g>, BTreeSet<&'g str>)>;
+
+/// Simulates a build and returns the third-party packages built, along with their features.
+fn build_map<'g>(
+    initials: FeatureSet<'g>,
+    features_only: FeatureSet<'g>,
+    cargo_opts: &CargoOptions<'_>,
+) -> BuildMap<'g> {
+    let cargo_set = CargoSet::new(initials, features_only, cargo_opts)
+        .expect("cargo resolution should succeed");
+
+    cargo_set
+        .all_features()
+        .iter()
+        .flat_map(|&(build_platform, feature_set)| {
+            feature_set
+                .packages_with_features(DependencyDirection::Forward)
+                .filter(|feature_list| !feature_list.package().in_workspace())
+                .map(move |feature_list| {
+                    let dep = feature_list.package();
+                    (
+                        (build_platform, dep.id()),
+                        (*dep, feature_list.named_features().collect()),
+                    )
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HakariBuilder;
+    use fixtures::json::JsonFixture;
+
+    #[test]
+    fn impact() {
+        let graph = JsonFixture::metadata_guppy_78cb7e8().graph();
+        assert!(
+            HakariBuilder::new(graph, None)
+                .expect("builder created")
+                .impact()
+                .is_none(),
+            "no impact without a workspace-hack package"
+        );
+
+        // cargo-compare stands in for the workspace-hack package, since it depends on third-party
+        // packages that other workspace packages don't.
+        let hakari_id = graph
+            .workspace()
+            .member_by_name("cargo-compare")
+            .expect("known workspace member")
+            .id();
+        let builder = HakariBuilder::new(graph, Some(hakari_id)).expect("builder created");
+        let impact = builder.impact().expect("workspace-hack package specified");
+        assert_eq!(impact.hakari_package().id(), hakari_id);
+        assert_eq!(
+            impact.members().len(),
+            graph.workspace().member_count() - 1,
+            "all workspace packages except the workspace-hack are analyzed"
+        );
+
+        let cargo_guppy = impact
+            .members()
+            .find(|member| member.package.name() == "cargo-guppy")
+            .expect("cargo-guppy is analyzed");
+        assert!(
+            cargo_guppy
+                .added
+                .iter()
+                .any(|added| { added.newly_built && added.package.name() == "cargo" }),
+            "cargo is added to cargo-guppy"
+        );
+
+        for member in impact.members() {
+            assert_ne!(member.package.id(), hakari_id);
+            let newly_built = member
+                .added
+                .iter()
+                .filter(|added| added.newly_built)
+                .count();
+            assert_eq!(
+                member.packages_with,
+                member.packages_without + newly_built,
+                "packages built with the workspace-hack are a superset for {}",
+                member.package.name()
+            );
+            for added in &member.added {
+                assert!(
+                    !added.package.in_workspace(),
+                    "only third-party packages are reported"
+                );
+                assert!(
+                    added.newly_built || !added.features.is_empty(),
+                    "existing packages are only reported with added features"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn impact_per_platform() {
+        let graph = JsonFixture::metadata_guppy_78cb7e8().graph();
+        let hakari_id = graph
+            .workspace()
+            .member_by_name("cargo-compare")
+            .expect("known workspace member")
+            .id();
+        let mut builder = HakariBuilder::new(graph, Some(hakari_id)).expect("builder created");
+        builder
+            .set_platforms(["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc"])
+            .expect("platforms are known");
+        let impact = builder.impact().expect("workspace-hack package specified");
+        assert_eq!(
+            impact.platforms().collect::<Vec<_>>(),
+            ["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc"],
+        );
+        assert_eq!(
+            impact.members().len(),
+            2 * (graph.workspace().member_count() - 1),
+            "each workspace package is analyzed once per platform"
+        );
+
+        let mut found_windows_only = false;
+        for member in impact.members() {
+            let platform_idx = member.platform_idx.expect("platforms are configured");
+            for added in &member.added {
+                let windows_only = added.package.name().starts_with("winapi");
+                assert!(
+                    platform_idx == 1 || !windows_only,
+                    "{} isn't reported as added on Linux",
+                    added.package.name()
+                );
+                found_windows_only |= windows_only;
+            }
+        }
+        assert!(
+            found_windows_only,
+            "Windows-only packages are added on Windows"
+        );
+    }
+}
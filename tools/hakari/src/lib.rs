@@ -128,6 +128,7 @@ pub mod cli_ops;
 pub mod explain;
 mod hakari;
 mod helpers;
+pub mod impact;
 #[cfg(feature = "proptest1")]
 mod proptest_helpers;
 #[cfg(feature = "cli-support")]
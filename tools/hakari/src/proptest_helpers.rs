@@ -12,6 +12,7 @@ use guppy::{
 };
 use proptest::{
     collection::{hash_set, vec},
+    option,
     prelude::*,
 };
 
@@ -29,7 +30,7 @@ impl<'g> HakariBuilder<'g> {
     /// ## Panics
     ///
     /// Panics if:
-    /// * there are no packages in this `PackageGraph`, or
+    /// * there are no packages in this `PackageGraph` or its workspace, or
     /// * `hakari_id` is specified but it isn't known to the graph, or isn't in the workspace.
     pub fn proptest1_strategy(
         graph: &'g PackageGraph,
@@ -45,6 +46,7 @@ impl<'g> HakariBuilder<'g> {
             any::<bool>(),
             any::<DepFormatVersion>(),
             any::<WorkspaceHackLineStyle>(),
+            option::of(hash_set(graph.workspace().proptest1_id_strategy(), 0..8)),
         )
             .prop_map(
                 move |(
@@ -57,6 +59,7 @@ impl<'g> HakariBuilder<'g> {
                     output_single_feature,
                     dep_format_version,
                     line_style,
+                    members,
                 )| {
                     let mut builder = HakariBuilder::new(graph, hakari_id)
                         .expect("HakariBuilder::new returned an error");
@@ -76,6 +79,11 @@ impl<'g> HakariBuilder<'g> {
                         .set_dep_format_version(dep_format_version)
                         .set_workspace_hack_line_style(line_style)
                         .set_output_single_feature(output_single_feature);
+                    if let Some(members) = members {
+                        builder
+                            .set_members(members)
+                            .expect("members obtained from the workspace should work");
+                    }
                     builder
                 },
             )
@@ -86,7 +94,6 @@ impl<'g> HakariBuilder<'g> {
 mod test {
     use super::*;
     use fixtures::json::JsonFixture;
-    use proptest::option;
     use std::collections::HashSet;
 
     /// Ensure that HakariBuilder roundtrips to its summary format.
@@ -7,16 +7,69 @@
 
 use crate::{
     hakari::{DepFormatVersion, WorkspaceHackLineStyle},
-    HakariBuilder, HakariOutputOptions, TomlOutError, UnifyTargetHost,
+    HakariBuilder, HakariCargoToml, HakariOutputOptions, TomlOutError, UnifyTargetHost,
 };
+use diffy::Line;
 use guppy::{
     errors::TargetSpecError,
-    graph::{cargo::CargoResolverVersion, summaries::PackageSetSummary, PackageGraph},
+    graph::{
+        cargo::{BuildPlatform, CargoResolverVersion},
+        feature::StandardFeatures,
+        summaries::{PackageSetSummary, SummaryId},
+        PackageGraph,
+    },
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, fmt, str::FromStr};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    str::FromStr,
+};
 use toml::Serializer;
 
+/// An error returned by [`HakariBuilderSummary::to_hakari_builders`].
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WorkspaceHacksError {
+    /// An error occurred while creating a `HakariBuilder` from the summary.
+    Guppy(guppy::Error),
+
+    /// `hakari-package` wasn't specified, and these workspace members weren't matched by any
+    /// `[[workspace-hack]]`.
+    UnassignedMembers(Vec<String>),
+}
+
+impl From<guppy::Error> for WorkspaceHacksError {
+    fn from(err: guppy::Error) -> Self {
+        WorkspaceHacksError::Guppy(err)
+    }
+}
+
+impl fmt::Display for WorkspaceHacksError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkspaceHacksError::Guppy(_) => write!(f, "error resolving workspace-hack packages"),
+            WorkspaceHacksError::UnassignedMembers(names) => write!(
+                f,
+                "no hakari-package specified, and workspace members not matched by any \
+                 [[workspace-hack]]: {}",
+                names.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WorkspaceHacksError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WorkspaceHacksError::Guppy(err) => Some(err),
+            WorkspaceHacksError::UnassignedMembers(_) => None,
+        }
+    }
+}
+
 /// The location of the configuration used by `cargo hakari`, relative to the workspace root.
 pub static DEFAULT_CONFIG_PATH: &str = ".config/hakari.toml";
 
@@ -105,6 +158,23 @@ pub struct HakariBuilderSummary {
         with = "registries_impl"
     )]
     pub registries: BTreeMap<String, String>,
+
+    /// The workspace members `hakari_package` is restricted to, if any.
+    ///
+    /// For more, see [`HakariBuilder::set_members`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<PackageSetSummary>,
+
+    /// Additional workspace-hack packages, each of which unifies features for its own set of
+    /// workspace members.
+    ///
+    /// Workspace members not assigned to any of these are handled by `hakari_package`.
+    #[serde(
+        rename = "workspace-hack",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub workspace_hacks: Vec<WorkspaceHackSummary>,
 }
 
 impl HakariBuilderSummary {
@@ -143,6 +213,11 @@ impl HakariBuilderSummary {
             output_single_feature: builder.output_single_feature(),
             dep_format_version: builder.dep_format_version,
             workspace_hack_line_style: builder.workspace_hack_line_style,
+            members: builder.members().map(|members| {
+                PackageSetSummary::from_package_ids(builder.graph(), members)
+                    .expect("all package IDs are valid")
+            }),
+            workspace_hacks: Vec::new(),
         })
     }
 
@@ -157,6 +232,59 @@ impl HakariBuilderSummary {
         HakariBuilder::from_summary(graph, self)
     }
 
+    /// Creates a `HakariBuilder` for each workspace-hack package in this summary.
+    ///
+    /// The builder for `hakari_package`, if specified, is returned first, followed by builders for
+    /// each of the [`workspace_hacks`](Self::workspace_hacks) in order. Each builder only
+    /// considers its own workspace members, and treats the other workspace-hack packages as
+    /// excluded during traversal, so each workspace-hack can be computed and verified
+    /// independently.
+    ///
+    /// Returns an error if this summary references a package that's not present, if
+    /// `hakari_package` isn't specified and some workspace members aren't assigned to any of the
+    /// `workspace_hacks`, or if there was some other issue while creating a `HakariBuilder` from
+    /// this summary.
+    pub fn to_hakari_builders<'g>(
+        &self,
+        graph: &'g PackageGraph,
+    ) -> Result<Vec<HakariBuilder<'g>>, WorkspaceHacksError> {
+        let mut builders = Vec::with_capacity(self.workspace_hacks.len() + 1);
+        if self.hakari_package.is_some() || self.workspace_hacks.is_empty() {
+            builders.push(HakariBuilder::from_summary(graph, self)?);
+        }
+        for idx in 0..self.workspace_hacks.len() {
+            builders.push(HakariBuilder::from_summary_impl(graph, self, Some(idx))?);
+        }
+
+        if self.hakari_package.is_none() && !self.workspace_hacks.is_empty() {
+            // Without a main workspace-hack, members not matched by any of the workspace-hacks
+            // would otherwise be silently dropped.
+            let hack_ids: BTreeSet<_> = builders
+                .iter()
+                .filter_map(|builder| builder.hakari_package())
+                .map(|package| package.id())
+                .collect();
+            let unassigned: Vec<_> = graph
+                .workspace()
+                .iter()
+                .filter(|package| {
+                    !hack_ids.contains(package.id())
+                        && !builders.iter().any(|builder| {
+                            builder
+                                .is_member(package.id())
+                                .expect("workspace package IDs are valid")
+                        })
+                })
+                .map(|package| package.name().to_owned())
+                .collect();
+            if !unassigned.is_empty() {
+                return Err(WorkspaceHacksError::UnassignedMembers(unassigned));
+            }
+        }
+
+        Ok(builders)
+    }
+
     /// Serializes this summary to a TOML string.
     ///
     /// Returns an error if writing out the TOML was unsuccessful.
@@ -208,6 +336,35 @@ impl<'g> HakariBuilder<'g> {
     }
 }
 
+/// An additional workspace-hack package, in serializable form.
+///
+/// Part of [`HakariBuilderSummary`].
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct WorkspaceHackSummary {
+    /// The name of the workspace-hack package in the workspace.
+    pub package: String,
+
+    /// The workspace members that depend on this workspace-hack package.
+    ///
+    /// Only workspace members are considered. A workspace member matched by more than one
+    /// workspace-hack is assigned to the first one.
+    pub members: PackageSetSummary,
+}
+
+impl WorkspaceHackSummary {
+    /// Creates a new `WorkspaceHackSummary` from a package name and a set of members.
+    pub fn new(package: impl Into<String>, members: PackageSetSummary) -> Self {
+        Self {
+            package: package.into(),
+            members,
+        }
+    }
+}
+
 /// Options for `hakari` TOML output, in serializable form.
 ///
 /// TODO: add a configuration.md file.
@@ -248,6 +405,391 @@ impl OutputOptionsSummary {
     }
 }
 
+/// A [`HakariExplain`](crate::explain::HakariExplain) in serializable form.
+///
+/// Produced by [`HakariExplain::to_summary`](crate::explain::HakariExplain::to_summary).
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct ExplainSummary {
+    /// The dependency being explained.
+    pub dependency: SummaryId,
+
+    /// The feature sets the dependency is built with, on the target platform first and then on
+    /// the host platform.
+    pub feature_sets: Vec<ExplainFeatureSetSummary>,
+}
+
+/// A feature set a dependency is built with, in serializable form.
+///
+/// Part of [`ExplainSummary`].
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct ExplainFeatureSetSummary {
+    /// The platform the dependency is built on.
+    pub build_platform: BuildPlatformSummary,
+
+    /// The features the dependency is built with.
+    pub features: BTreeSet<String>,
+
+    /// The workspace packages that cause the dependency to be built with this feature set.
+    pub workspace_packages: Vec<ExplainPackageSummary>,
+
+    /// The platforms on which this feature set was added by the post-compute fixup.
+    #[serde(default)]
+    pub fixup_platforms: Vec<ExplainPlatformSummary>,
+}
+
+/// A workspace package responsible for a feature set, in serializable form.
+///
+/// Part of [`ExplainFeatureSetSummary`].
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct ExplainPackageSummary {
+    /// The workspace package.
+    pub package: SummaryId,
+
+    /// The builds of the workspace package that produce this feature set.
+    pub builds: Vec<ExplainBuildSummary>,
+
+    /// The links from the workspace package to the dependency, in order.
+    ///
+    /// This is empty in the unlikely case that a path couldn't be found.
+    pub path: Vec<ExplainLinkSummary>,
+}
+
+/// Options for a build of a workspace package, in serializable form.
+///
+/// Part of [`ExplainPackageSummary`]. For `include_dev` and `standard_features`, `None` means that
+/// the feature set is produced with every possible value.
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct ExplainBuildSummary {
+    /// Whether dev-dependencies are included.
+    pub include_dev: Option<bool>,
+
+    /// The standard features the workspace package is built with.
+    pub standard_features: Option<StandardFeaturesSummary>,
+
+    /// The platform the workspace package is built for.
+    pub platform: ExplainPlatformSummary,
+}
+
+/// A platform a workspace package is built for, in serializable form.
+///
+/// Part of [`ExplainBuildSummary`] and [`ExplainFeatureSetSummary`].
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExplainPlatformSummary {
+    /// Every possible platform: the result is the same regardless of the platform.
+    Any,
+
+    /// A platform-independent build, with all platform-specific dependencies included.
+    All,
+
+    /// The platform with this target triple.
+    Platform(String),
+}
+
+/// A link in a path from a workspace package to a dependency, in serializable form.
+///
+/// Part of [`ExplainPackageSummary`].
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct ExplainLinkSummary {
+    /// The package this link is from.
+    pub from: SummaryId,
+
+    /// The package this link is to.
+    pub to: SummaryId,
+
+    /// The kind of dependency.
+    pub kind: DependencyKindSummary,
+}
+
+/// [`VerifyErrors`](crate::verify::VerifyErrors) in serializable form.
+///
+/// Produced by [`VerifyErrors::to_summary`](crate::verify::VerifyErrors::to_summary). The default
+/// value has no errors, and represents a successful verification.
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct VerifyErrorsSummary {
+    /// The dependencies that were built with more than one feature set.
+    pub errors: Vec<ExplainSummary>,
+}
+
+/// A [`HakariImpact`](crate::impact::HakariImpact) in serializable form.
+///
+/// Produced by [`HakariImpact::to_summary`](crate::impact::HakariImpact::to_summary).
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct ImpactSummary {
+    /// The workspace-hack package used for the analysis.
+    pub hakari_package: SummaryId,
+
+    /// The impact of the workspace-hack on each workspace package.
+    pub members: Vec<MemberImpactSummary>,
+}
+
+/// The impact of the workspace-hack on a single workspace package, in serializable form.
+///
+/// Part of [`ImpactSummary`].
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct MemberImpactSummary {
+    /// The workspace package.
+    pub package: SummaryId,
+
+    /// The platform the workspace package was analyzed on, or `None` if the analysis was
+    /// performed platform-independently.
+    pub platform: Option<String>,
+
+    /// The number of third-party packages built without the workspace-hack.
+    pub packages_without: usize,
+
+    /// The number of third-party packages built with the workspace-hack.
+    pub packages_with: usize,
+
+    /// Third-party packages added by the workspace-hack, or built with additional features.
+    pub added: Vec<AddedDependencySummary>,
+}
+
+/// A third-party package added to a build by the workspace-hack, in serializable form.
+///
+/// Part of [`MemberImpactSummary`].
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct AddedDependencySummary {
+    /// The platform the package is built on.
+    pub build_platform: BuildPlatformSummary,
+
+    /// The package.
+    pub package: SummaryId,
+
+    /// True if this package isn't built at all without the workspace-hack.
+    pub newly_built: bool,
+
+    /// The features added by the workspace-hack, or all features if the package is newly built.
+    pub features: BTreeSet<String>,
+}
+
+/// A diff between a workspace-hack's `Cargo.toml` and new contents, in serializable form.
+///
+/// Produced by [`HakariCargoToml::to_diff_summary`].
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct TomlDiffSummary {
+    /// The path to the `Cargo.toml` file.
+    pub toml_path: String,
+
+    /// The hunks of the diff. This is empty if the contents are unchanged.
+    pub hunks: Vec<DiffHunkSummary>,
+
+    /// The diff in unified format.
+    pub patch: String,
+}
+
+impl TomlDiffSummary {
+    /// Returns true if the contents are unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+}
+
+/// A hunk in a [`TomlDiffSummary`].
+///
+/// Line numbers are relative to the generated section of the `Cargo.toml`, and start at 1.
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct DiffHunkSummary {
+    /// The first line of the hunk in the old contents.
+    pub old_start: usize,
+
+    /// The number of lines of the hunk in the old contents.
+    pub old_len: usize,
+
+    /// The first line of the hunk in the new contents.
+    pub new_start: usize,
+
+    /// The number of lines of the hunk in the new contents.
+    pub new_len: usize,
+
+    /// The lines in the hunk.
+    pub lines: Vec<DiffLineSummary>,
+}
+
+/// A line in a [`DiffHunkSummary`].
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct DiffLineSummary {
+    /// The kind of line.
+    pub kind: DiffLineKindSummary,
+
+    /// The contents of the line, without the trailing newline.
+    pub text: String,
+}
+
+/// The platform a package is built on, in serializable form.
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BuildPlatformSummary {
+    /// The target platform.
+    Target,
+
+    /// The host platform.
+    Host,
+}
+
+impl From<BuildPlatform> for BuildPlatformSummary {
+    fn from(build_platform: BuildPlatform) -> Self {
+        match build_platform {
+            BuildPlatform::Target => BuildPlatformSummary::Target,
+            BuildPlatform::Host => BuildPlatformSummary::Host,
+        }
+    }
+}
+
+/// The standard features a package is built with, in serializable form.
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StandardFeaturesSummary {
+    /// No features.
+    None,
+
+    /// Default features.
+    Default,
+
+    /// All features.
+    All,
+}
+
+impl From<StandardFeatures> for StandardFeaturesSummary {
+    fn from(features: StandardFeatures) -> Self {
+        match features {
+            StandardFeatures::None => StandardFeaturesSummary::None,
+            StandardFeatures::Default => StandardFeaturesSummary::Default,
+            StandardFeatures::All => StandardFeaturesSummary::All,
+        }
+    }
+}
+
+/// The kind of a dependency, in serializable form.
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependencyKindSummary {
+    /// A normal dependency.
+    Normal,
+
+    /// A dev-only dependency.
+    Dev,
+
+    /// A build-only dependency.
+    Build,
+}
+
+/// The kind of a line in a [`DiffHunkSummary`].
+///
+/// Requires the `cli-support` feature to be enabled.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffLineKindSummary {
+    /// A line present in both the old and new contents.
+    Context,
+
+    /// A line only present in the old contents.
+    Delete,
+
+    /// A line only present in the new contents.
+    Insert,
+}
+
+impl HakariCargoToml {
+    /// Computes the diff between the contents on disk and the provided TOML output, in
+    /// serializable form.
+    ///
+    /// Requires the `cli-support` feature to be enabled.
+    pub fn to_diff_summary(&self, toml: &str) -> TomlDiffSummary {
+        let patch = self.diff_toml(toml);
+        let hunks = patch
+            .hunks()
+            .iter()
+            .map(|hunk| {
+                let lines = hunk
+                    .lines()
+                    .iter()
+                    .map(|line| {
+                        let (kind, text) = match line {
+                            Line::Context(text) => (DiffLineKindSummary::Context, text),
+                            Line::Delete(text) => (DiffLineKindSummary::Delete, text),
+                            Line::Insert(text) => (DiffLineKindSummary::Insert, text),
+                        };
+                        DiffLineSummary {
+                            kind,
+                            text: text.trim_end_matches('\n').to_owned(),
+                        }
+                    })
+                    .collect();
+                DiffHunkSummary {
+                    old_start: hunk.old_range().start(),
+                    old_len: hunk.old_range().len(),
+                    new_start: hunk.new_range().start(),
+                    new_len: hunk.new_range().len(),
+                    lines,
+                }
+            })
+            .collect();
+
+        TomlDiffSummary {
+            toml_path: self.toml_path().to_string(),
+            hunks,
+            patch: patch.to_string(),
+        }
+    }
+}
+
 mod registries_impl {
     use super::*;
     use serde::{Deserializer, Serializer};
@@ -354,4 +896,329 @@ mod tests {
             "summary => serialized => summary roundtrip"
         );
     }
+
+    #[test]
+    fn multiple_workspace_hacks() {
+        // fixture-manager and cargo-compare stand in for workspace-hack packages.
+        static MULTIPLE_HACKS_INPUT: &str = r#"
+        hakari-package = "fixture-manager"
+        resolver = "2"
+
+        [[workspace-hack]]
+        package = "cargo-compare"
+        members = { workspace-members = ["cargo-guppy", "guppy-cmdlib"] }
+        "#;
+
+        let summary: HakariBuilderSummary =
+            toml::from_str(MULTIPLE_HACKS_INPUT).expect("failed to parse toml");
+        assert_eq!(
+            summary.workspace_hacks,
+            vec![WorkspaceHackSummary::new(
+                "cargo-compare",
+                PackageSetSummary {
+                    workspace_members: ["cargo-guppy", "guppy-cmdlib"]
+                        .into_iter()
+                        .map(|name| name.to_owned())
+                        .collect(),
+                    ..PackageSetSummary::default()
+                },
+            )]
+        );
+        let serialized = toml::to_string(&summary).expect("serialized to TOML correctly");
+        let summary2: HakariBuilderSummary =
+            toml::from_str(&serialized).expect("deserialized from TOML correctly");
+        assert_eq!(
+            summary, summary2,
+            "summary => serialized => summary roundtrip"
+        );
+
+        let graph = JsonFixture::metadata_guppy_78cb7e8().graph();
+        let id = |name: &str| {
+            graph
+                .workspace()
+                .member_by_name(name)
+                .expect("known workspace member")
+                .id()
+        };
+        let builders = summary
+            .to_hakari_builders(graph)
+            .expect("summary => builders conversion");
+        assert_eq!(builders.len(), 2, "one builder per workspace-hack");
+        let names: Vec<_> = builders
+            .iter()
+            .map(|builder| builder.hakari_package().expect("hakari package").name())
+            .collect();
+        assert_eq!(names, ["fixture-manager", "cargo-compare"]);
+
+        let (main, tools) = (&builders[0], &builders[1]);
+        for (builder, name, is_member) in [
+            (main, "guppy", true),
+            (main, "cargo-guppy", false),
+            (main, "cargo-compare", false),
+            (tools, "guppy", false),
+            (tools, "cargo-guppy", true),
+            (tools, "guppy-cmdlib", true),
+            (tools, "fixture-manager", false),
+        ] {
+            assert_eq!(
+                builder.is_member(id(name)).expect("valid package ID"),
+                is_member,
+                "for {}, {name} membership",
+                builder.hakari_package().unwrap().name(),
+            );
+        }
+        // Each workspace-hack is excluded from the other's traversals.
+        assert!(main.is_traversal_excluded(id("cargo-compare")).unwrap());
+        assert!(tools.is_traversal_excluded(id("fixture-manager")).unwrap());
+        // Non-members are excluded, so manage-deps won't add the dependency to them.
+        assert!(main.is_excluded(id("cargo-guppy")).unwrap());
+        assert!(!main.is_excluded(id("guppy")).unwrap());
+
+        // Simulated builds are only run for members.
+        let hakari = tools.clone().compute();
+        assert!(!hakari.computed_map.is_empty(), "members have dependencies");
+        for value in hakari.computed_map.values() {
+            for (_, inner_map) in value.inner_maps() {
+                for inner_value in inner_map.values() {
+                    for (package, _, _) in &inner_value.workspace_packages {
+                        assert!(
+                            ["cargo-guppy", "guppy-cmdlib"].contains(&package.name()),
+                            "unexpected workspace package {}",
+                            package.name()
+                        );
+                    }
+                }
+            }
+        }
+
+        // Without additional workspace-hacks, a single builder considering all members is
+        // returned.
+        let summary: HakariBuilderSummary =
+            toml::from_str("hakari-package = \"fixture-manager\"\nresolver = \"2\"")
+                .expect("failed to parse toml");
+        let builders = summary
+            .to_hakari_builders(graph)
+            .expect("summary => builders conversion");
+        assert_eq!(builders.len(), 1);
+        assert!(builders[0].members().is_none());
+        assert!(builders[0].is_member(id("cargo-guppy")).unwrap());
+
+        // Members set on a builder survive a round trip through its summary.
+        let mut builder = HakariBuilder::new(graph, Some(id("fixture-manager"))).expect("builder");
+        builder
+            .set_members([id("cargo-guppy"), id("guppy-cmdlib")])
+            .expect("members are in the workspace");
+        let summary = builder.to_summary().expect("builder => summary conversion");
+        assert!(summary.members.is_some(), "members are recorded");
+        let builder2 = summary
+            .to_hakari_builder(graph)
+            .expect("summary => builder conversion");
+        let mut members: Vec<_> = builder2
+            .members()
+            .expect("members are restored")
+            .map(|id| graph.metadata(id).unwrap().name())
+            .collect();
+        members.sort_unstable();
+        assert_eq!(members, ["cargo-guppy", "guppy-cmdlib"]);
+        assert_eq!(
+            builder2
+                .to_summary()
+                .expect("builder => summary conversion"),
+            summary,
+            "summary round-trips"
+        );
+
+        // Without a main workspace-hack, every workspace member must be matched by one of the
+        // additional workspace-hacks.
+        let summary: HakariBuilderSummary = toml::from_str(
+            r#"
+            resolver = "2"
+
+            [[workspace-hack]]
+            package = "cargo-compare"
+            members = { workspace-members = ["cargo-guppy", "guppy-cmdlib"] }
+            "#,
+        )
+        .expect("failed to parse toml");
+        match summary.to_hakari_builders(graph) {
+            Err(WorkspaceHacksError::UnassignedMembers(names)) => {
+                assert!(names.contains(&"guppy".to_owned()), "guppy is unassigned");
+                assert!(
+                    !names.contains(&"cargo-guppy".to_owned()),
+                    "cargo-guppy is assigned"
+                );
+                assert!(
+                    !names.contains(&"cargo-compare".to_owned()),
+                    "workspace-hack packages aren't members"
+                );
+            }
+            other => panic!("expected unassigned members, found {:?}", other),
+        }
+
+        // Members must be in the workspace.
+        let third_party = graph
+            .packages()
+            .find(|package| !package.in_workspace())
+            .expect("third-party package exists");
+        let mut builder = HakariBuilder::new(graph, None).expect("builder created");
+        match builder.set_members([third_party.id()]) {
+            Err(guppy::Error::NotInWorkspace(package_id)) => {
+                assert_eq!(&package_id, third_party.id(), "package ID matches")
+            }
+            other => panic!("expected not in workspace error, found {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn multiple_workspace_hacks_verify() {
+        // guppy-summaries stands in for a workspace-hack that is missing dependencies used by
+        // every one of its members.
+        static VERIFY_INPUT: &str = r#"
+        hakari-package = "fixture-manager"
+        resolver = "2"
+
+        [[workspace-hack]]
+        package = "guppy-summaries"
+        members = { workspace-members = ["cargo-guppy", "guppy-cmdlib"] }
+        "#;
+
+        let summary: HakariBuilderSummary =
+            toml::from_str(VERIFY_INPUT).expect("failed to parse toml");
+        let graph = JsonFixture::metadata_guppy_78cb7e8().graph();
+        let builders = summary
+            .to_hakari_builders(graph)
+            .expect("summary => builders conversion");
+        let tools = builders[1].clone();
+        assert_eq!(tools.hakari_package().unwrap().name(), "guppy-summaries");
+
+        // The workspace-hack isn't one of its own members, but it must still be built on its own
+        // so that its feature sets can be compared against those of its members.
+        let errors = tools
+            .verify()
+            .expect_err("workspace-hack is missing dependencies");
+        assert!(
+            !errors.dependency_ids.is_empty(),
+            "verify reports dependencies built with more than one feature set"
+        );
+    }
+
+    #[test]
+    fn explain_summary() {
+        let graph = JsonFixture::metadata_guppy_78cb7e8().graph();
+        let hakari = HakariBuilder::new(graph, None)
+            .expect("builder created")
+            .compute();
+        let (_, deps) = hakari
+            .output_map
+            .iter()
+            .next()
+            .expect("some dependencies were unified");
+        let dep_id = deps.keys().next().expect("at least one dependency");
+
+        let explain = hakari.explain(dep_id).expect("dependency is in output");
+        let summary = explain.to_summary();
+        assert_eq!(
+            summary.dependency,
+            graph.metadata(dep_id).unwrap().to_summary_id()
+        );
+        let path_count: usize = summary
+            .feature_sets
+            .iter()
+            .map(|feature_set| feature_set.workspace_packages.len())
+            .sum();
+        assert_eq!(path_count, explain.paths().count(), "one path per package");
+        for package in summary
+            .feature_sets
+            .iter()
+            .flat_map(|feature_set| &feature_set.workspace_packages)
+        {
+            assert!(!package.builds.is_empty(), "at least one build recorded");
+            for build in &package.builds {
+                // No platforms are configured, so every build is platform-independent.
+                assert!(
+                    matches!(
+                        build.platform,
+                        ExplainPlatformSummary::Any | ExplainPlatformSummary::All
+                    ),
+                    "unexpected platform {:?}",
+                    build.platform
+                );
+            }
+            let first = package.path.first().expect("path found");
+            assert_eq!(first.from, package.package, "path starts at package");
+        }
+
+        let serialized = serde_json::to_string(&summary).expect("serialized to JSON correctly");
+        let summary2: ExplainSummary =
+            serde_json::from_str(&serialized).expect("deserialized from JSON correctly");
+        assert_eq!(
+            summary, summary2,
+            "summary => serialized => summary roundtrip"
+        );
+
+        let value = serde_json::to_value(&summary).expect("serialized to JSON correctly");
+        let build_platform = &value["feature-sets"][0]["build-platform"];
+        assert!(
+            build_platform == "target" || build_platform == "host",
+            "build platform serialized in kebab-case: {build_platform}"
+        );
+
+        for (platform, expected) in [
+            (ExplainPlatformSummary::Any, serde_json::json!("any")),
+            (ExplainPlatformSummary::All, serde_json::json!("all")),
+            (
+                ExplainPlatformSummary::Platform("x86_64-unknown-linux-gnu".to_owned()),
+                serde_json::json!({ "platform": "x86_64-unknown-linux-gnu" }),
+            ),
+        ] {
+            let value = serde_json::to_value(&platform).expect("serialized to JSON correctly");
+            assert_eq!(value, expected, "{platform:?} serialized correctly");
+            let platform2: ExplainPlatformSummary =
+                serde_json::from_value(value).expect("deserialized from JSON correctly");
+            assert_eq!(platform, platform2, "{platform:?} roundtrips");
+        }
+    }
+
+    #[test]
+    fn toml_diff_summary() {
+        let contents = format!(
+            "[package]\nname = \"workspace-hack\"\n{}a = 1\nb = 2\nc = 3{}",
+            HakariCargoToml::BEGIN_SECTION,
+            HakariCargoToml::END_SECTION
+        );
+        let existing = HakariCargoToml::new_in_memory("workspace-hack/Cargo.toml", contents)
+            .expect("generated section found");
+
+        let unchanged = existing.to_diff_summary("a = 1\nb = 2\nc = 3\n");
+        assert!(unchanged.is_empty(), "no hunks for unchanged contents");
+
+        let summary = existing.to_diff_summary("a = 1\nb = 4\nc = 3\n");
+        assert_eq!(summary.toml_path, "workspace-hack/Cargo.toml");
+        assert_eq!(summary.hunks.len(), 1, "one hunk");
+        let hunk = &summary.hunks[0];
+        assert_eq!((hunk.old_start, hunk.old_len), (1, 3));
+        assert_eq!((hunk.new_start, hunk.new_len), (1, 3));
+        let lines: Vec<_> = hunk
+            .lines
+            .iter()
+            .map(|line| (line.kind, line.text.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (DiffLineKindSummary::Context, "a = 1"),
+                (DiffLineKindSummary::Delete, "b = 2"),
+                (DiffLineKindSummary::Insert, "b = 4"),
+                (DiffLineKindSummary::Context, "c = 3"),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&hunk.lines[1]).expect("serialized to JSON correctly"),
+            serde_json::json!({ "kind": "delete", "text": "b = 2" }),
+        );
+        assert_eq!(
+            summary.patch,
+            existing.diff_toml("a = 1\nb = 4\nc = 3\n").to_string()
+        );
+    }
 }
@@ -33,6 +33,8 @@ impl<'g> HakariBuilder<'g> {
     ///
     /// For more about how this works, see the documentation for the [`verify`](crate::verify)
     /// module.
+    // VerifyErrors carries the whole Hakari computation, and boxing it would be a breaking change.
+    #[allow(clippy::result_large_err)]
     pub fn verify(mut self) -> Result<(), VerifyErrors<'g>> {
         self.verify_mode = true;
         let hakari = self.compute();
@@ -88,6 +90,16 @@ impl<'g> VerifyErrors<'g> {
     pub fn display<'verify>(&'verify self) -> VerifyErrorsDisplay<'g, 'verify> {
         VerifyErrorsDisplay::new(self)
     }
+
+    /// Converts this instance to a serializable summary.
+    ///
+    /// Requires the `cli-support` feature to be enabled.
+    #[cfg(feature = "cli-support")]
+    pub fn to_summary(&self) -> crate::summaries::VerifyErrorsSummary {
+        crate::summaries::VerifyErrorsSummary {
+            errors: self.errors().map(|explain| explain.to_summary()).collect(),
+        }
+    }
 }
 
 #[cfg(test)]